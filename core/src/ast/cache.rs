@@ -1,3 +1,4 @@
+use crate::ast::parser::ParseHealth;
 use crate::ast::symbol::Symbol;
 use serde::{Deserialize, Serialize};
 use sha1::Digest;
@@ -10,6 +11,9 @@ use std::time::SystemTime;
 pub struct FileIndex {
     pub mtime: u64,
     pub symbols: Vec<Symbol>,
+    /// 解析状况；未启用解析错误检测（[`crate::ast::ParseErrorMode::Ignore`]）时为 None
+    #[serde(default)]
+    pub parse_health: Option<ParseHealth>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +23,26 @@ pub struct CacheData {
     pub build_time: String,
 }
 
+/// 仓库缓存目录中的索引文件名
+const CACHE_FILE_NAME: &str = "ast_index.bin";
+
+impl CacheData {
+    /// 从 `ast_index.bin` 的 bincode 内容反序列化，用于校验导入的缓存文件
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        bincode::deserialize::<CacheData>(bytes)
+            .map_err(|e| format!("Invalid AST cache file: {}", e))
+    }
+}
+
+/// 仓库在缓存目录下的子目录名：规范化路径的 SHA-1 前 16 位
+pub(crate) fn repository_cache_key(repo_path: &str) -> String {
+    let abs_path = fs::canonicalize(repo_path).unwrap_or_else(|_| PathBuf::from(repo_path));
+    let mut hasher = sha1::Sha1::new();
+    hasher.update(abs_path.to_string_lossy().as_bytes());
+    let key = format!("{:x}", hasher.finalize());
+    key.chars().take(16).collect::<String>()
+}
+
 pub struct CacheManager {
     base_cache_dir: PathBuf,
     cache_dir: PathBuf,
@@ -37,12 +61,9 @@ impl CacheManager {
 
     pub fn use_repository(&mut self, repo_path: &str) {
         let abs_path = fs::canonicalize(repo_path).unwrap_or_else(|_| PathBuf::from(repo_path));
-        let mut hasher = sha1::Sha1::new();
-        hasher.update(abs_path.to_string_lossy().as_bytes());
-        let key = format!("{:x}", hasher.finalize());
-        let key = key.chars().take(16).collect::<String>();
+        let key = repository_cache_key(repo_path);
 
-        self.repository_path = Some(abs_path.clone());
+        self.repository_path = Some(abs_path);
         self.cache_dir = self.base_cache_dir.join(&key);
     }
 
@@ -51,7 +72,7 @@ impl CacheManager {
             return None;
         }
 
-        let cache_file = self.cache_dir.join("ast_index.bin");
+        let cache_file = self.cache_file();
         if !cache_file.exists() {
             return None;
         }
@@ -78,7 +99,7 @@ impl CacheManager {
             }
         }
 
-        let cache_file = self.cache_dir.join("ast_index.bin");
+        let cache_file = self.cache_file();
         let serialized = bincode::serialize(cache_data)
             .map_err(|e| format!("Failed to serialize cache: {}", e))?;
 
@@ -149,4 +170,14 @@ impl CacheManager {
     pub fn get_cache_dir(&self) -> &Path {
         &self.cache_dir
     }
+
+    /// 所有仓库缓存目录的根目录
+    pub fn get_base_cache_dir(&self) -> &Path {
+        &self.base_cache_dir
+    }
+
+    /// 当前仓库的索引缓存文件路径
+    pub fn cache_file(&self) -> PathBuf {
+        self.cache_dir.join(CACHE_FILE_NAME)
+    }
 }
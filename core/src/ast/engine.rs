@@ -1,15 +1,50 @@
 use crate::ast::cache::{CacheData, FileIndex};
-use crate::ast::{ASTParser, CacheManager, QueryEngine, Symbol};
-use ignore::Walk;
+use crate::ast::parser::ParseHealth;
+use crate::ast::{
+    ASTParser, CacheManager, CallGraphGranularity, QueryEngine, ReportOptions, Symbol, SymbolKind,
+    DEFAULT_ROUTE_DECORATOR_PATTERN,
+};
+use ignore::WalkBuilder;
 use rayon::prelude::*;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use walkdir::WalkDir;
 
+/// 索引时如何处理解析错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseErrorMode {
+    /// 不记录解析状况（默认）
+    #[default]
+    Ignore,
+    /// 在文件索引中记录解析状况，解析失败的文件以空符号列表保留
+    Record,
+    /// 同 Record，并对有错误或解析失败的文件输出警告日志
+    Warn,
+}
+
+impl std::str::FromStr for ParseErrorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ignore" | "off" => Ok(ParseErrorMode::Ignore),
+            "record" => Ok(ParseErrorMode::Record),
+            "warn" => Ok(ParseErrorMode::Warn),
+            _ => Err(format!("Unknown parse error mode: {}", s)),
+        }
+    }
+}
+
 pub struct ASTEngine {
     parser: Arc<Mutex<ASTParser>>,
     cache_manager: Arc<Mutex<CacheManager>>,
     query_engine: Arc<Mutex<Option<QueryEngine>>>,
+    /// 遍历项目时是否跟随符号链接（默认不跟随）
+    follow_links: bool,
+    /// 遍历项目的最大目录深度
+    max_depth: usize,
+    parse_error_mode: ParseErrorMode,
 }
 
 impl ASTEngine {
@@ -18,9 +53,36 @@ impl ASTEngine {
             parser: Arc::new(Mutex::new(ASTParser::new())),
             cache_manager: Arc::new(Mutex::new(CacheManager::new(cache_dir))),
             query_engine: Arc::new(Mutex::new(None)),
+            follow_links: false,
+            max_depth: crate::scanner::DEFAULT_MAX_WALK_DEPTH,
+            parse_error_mode: ParseErrorMode::default(),
         }
     }
 
+    /// 索引时跳过名称短于 `min_len` 个字符的调用，见 [`ASTParser::with_min_call_name_len`]
+    pub fn with_min_call_name_len(mut self, min_len: usize) -> Self {
+        self.parser = Arc::new(Mutex::new(ASTParser::new().with_min_call_name_len(min_len)));
+        self
+    }
+
+    /// 设置 scan_project 是否跟随符号链接；跟随时检测到的循环链接会被跳过
+    pub fn with_follow_links(mut self, follow_links: bool) -> Self {
+        self.follow_links = follow_links;
+        self
+    }
+
+    /// 设置 scan_project 遍历的最大目录深度（根目录下的文件深度为 1）
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// 设置是否在索引中记录各文件的解析状况，见 [`ParseErrorMode`]
+    pub fn with_parse_error_mode(mut self, mode: ParseErrorMode) -> Self {
+        self.parse_error_mode = mode;
+        self
+    }
+
     pub fn use_repository(&self, repo_path: &str) {
         if let Ok(mut cache_manager) = self.cache_manager.try_lock() {
             cache_manager.use_repository(repo_path);
@@ -52,20 +114,51 @@ impl ASTEngine {
     }
 
     pub fn scan_project(&self, root_path: &str) -> Result<usize, String> {
-        let root_path = PathBuf::from(root_path);
-        if !root_path.exists() {
-            return Err(format!("Path '{}' does not exist", root_path.display()));
+        self.scan_projects(&[root_path])
+    }
+
+    /// 将多个根目录（如 monorepo 中的多个包）索引到同一个索引和缓存中，
+    /// 重叠目录下的文件只处理一次；任一根目录不存在时不做任何处理并返回错误
+    pub fn scan_projects(&self, roots: &[&str]) -> Result<usize, String> {
+        self.scan_projects_with_max_depth(roots, self.max_depth)
+    }
+
+    /// 同 [`ASTEngine::scan_projects`]，本次遍历使用指定的最大目录深度
+    pub fn scan_projects_with_max_depth(&self, roots: &[&str], max_depth: usize) -> Result<usize, String> {
+        let root_paths: Vec<PathBuf> = roots.iter().map(PathBuf::from).collect();
+        if root_paths.is_empty() {
+            return Err("No project path to scan".to_string());
+        }
+        if let Some(missing) = root_paths.iter().find(|root| !root.exists()) {
+            return Err(format!("Path '{}' does not exist", missing.display()));
         }
 
         // Collect all files to process
         let mut files_to_process = Vec::new();
+        let mut seen = HashSet::new();
 
-        for entry in Walk::new(&root_path) {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if path.is_file() && self.is_supported_file(path) {
-                    files_to_process.push(path.to_path_buf());
+        let mut walker = WalkBuilder::new(&root_paths[0]);
+        for root in &root_paths[1..] {
+            walker.add(root);
+        }
+        let walker = walker
+            .follow_links(self.follow_links)
+            .max_depth(Some(max_depth))
+            .build();
+        for entry in walker {
+            match entry {
+                Ok(entry) => {
+                    let path = entry.path();
+                    let is_file = entry.file_type().is_some_and(|ft| ft.is_file());
+                    if is_file && self.is_supported_file(path) {
+                        let canonical = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+                        if seen.insert(canonical) {
+                            files_to_process.push(path.to_path_buf());
+                        }
+                    }
                 }
+                // 包括跟随符号链接时检测到的循环
+                Err(e) => log::warn!("Skipping entry during indexing: {}", e),
             }
         }
 
@@ -73,7 +166,7 @@ impl ASTEngine {
         log::info!(
             "Found {} files to scan in {}",
             total_files,
-            root_path.display()
+            roots.join(", ")
         );
 
         // Process files in parallel
@@ -89,6 +182,10 @@ impl ASTEngine {
             })
             .collect();
 
+        if let Err(e) = self.populate_subclasses() {
+            log::error!("Failed to populate subclasses: {}", e);
+        }
+
         // Save cache
         if let Err(e) = self.save_cache() {
             log::error!("Failed to save cache: {}", e);
@@ -130,18 +227,57 @@ impl ASTEngine {
         }
 
         // Read and parse file
-        let content = std::fs::read_to_string(file_path)
+        let bytes = std::fs::read(file_path)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        if crate::scanner::looks_binary(&bytes) {
+            log::debug!("Skipping binary file {}", file_path.display());
+            return Ok(());
+        }
+        let content = String::from_utf8(bytes)
             .map_err(|e| format!("Failed to read file: {}", e))?;
 
-        let symbols = {
+        let parsed = {
             let mut parser = self.parser.try_lock()
                 .map_err(|_| "Parser lock poisoned")?;
-            parser.parse_file(file_path, &content)?
+            parser.parse_file_with_health(file_path, &content)
         };
 
+        let record_health = self.parse_error_mode != ParseErrorMode::Ignore;
+        let (symbols, health) = match parsed {
+            Ok(parsed) => parsed,
+            Err(e) if record_health => {
+                // 保留解析失败的文件，以便在文件列表中显示；mtime 不变时不会重复解析
+                if self.parse_error_mode == ParseErrorMode::Warn {
+                    log::warn!("Failed to parse {}: {}", file_path.display(), e);
+                }
+                let mtime = cache_manager.get_file_mtime(file_path)?;
+                let mut query_engine = self.query_engine.try_lock()
+                    .map_err(|_| "Query engine lock poisoned")?;
+                if let Some(ref mut engine) = *query_engine {
+                    engine.cache.index.insert(file_path_str, FileIndex {
+                        mtime,
+                        symbols: Vec::new(),
+                        parse_health: Some(ParseHealth::Failed),
+                    });
+                }
+                return Err(e);
+            }
+            Err(e) => return Err(e),
+        };
+        if self.parse_error_mode == ParseErrorMode::Warn && health == ParseHealth::HasErrors {
+            log::warn!(
+                "{} contains syntax errors, extracted symbols may be incomplete",
+                file_path.display()
+            );
+        }
+
         // Update cache
         let mtime = cache_manager.get_file_mtime(file_path)?;
-        let file_index = FileIndex { mtime, symbols };
+        let file_index = FileIndex {
+            mtime,
+            symbols,
+            parse_health: record_health.then_some(health),
+        };
 
         let mut query_engine = self.query_engine.try_lock()
             .map_err(|_| "Query engine lock poisoned")?;
@@ -162,6 +298,16 @@ impl ASTEngine {
         Ok(())
     }
 
+    /// 索引完成后填充各类型符号的 subclasses
+    pub fn populate_subclasses(&self) -> Result<(), String> {
+        let mut query_engine = self.query_engine.try_lock()
+            .map_err(|_| "Query engine lock poisoned")?;
+        if let Some(ref mut engine) = *query_engine {
+            engine.populate_subclasses();
+        }
+        Ok(())
+    }
+
     pub fn save_cache(&self) -> Result<(), String> {
         let cache_manager = self.cache_manager.try_lock()
             .map_err(|_| "Cache manager lock poisoned")?;
@@ -182,11 +328,16 @@ impl ASTEngine {
         }
     }
 
-    pub fn generate_report(&self, repository_path: &str) -> Result<serde_json::Value, String> {
+    /// 生成分析报告并保存到缓存目录，`options` 控制节点数量上限和符号类型过滤
+    pub fn generate_report(
+        &self,
+        repository_path: &str,
+        options: &ReportOptions,
+    ) -> Result<serde_json::Value, String> {
         let query_engine = self.query_engine.try_lock()
             .map_err(|_| "Query engine lock poisoned")?;
         if let Some(ref engine) = *query_engine {
-            let report = engine.generate_report(repository_path);
+            let report = engine.generate_report(repository_path, options);
 
             // Save report to cache
             let cache_manager = self.cache_manager.try_lock()
@@ -210,6 +361,44 @@ impl ASTEngine {
         }
     }
 
+    /// 查找文件中包含某行的最内层符号，见 [`QueryEngine::symbol_at_line`]
+    pub fn symbol_at_line(&self, file: &str, line: u32) -> Result<Option<Symbol>, String> {
+        let query_engine = self.query_engine.try_lock()
+            .map_err(|_| "Query engine lock poisoned")?;
+        if let Some(ref engine) = *query_engine {
+            Ok(engine.symbol_at_line(file, line).cloned())
+        } else {
+            Err("No cache loaded".to_string())
+        }
+    }
+
+    /// 模糊搜索符号，见 [`QueryEngine::fuzzy_search`]
+    pub fn fuzzy_search(&self, query: &str, limit: usize) -> Result<Vec<(Symbol, f64)>, String> {
+        let query_engine = self.query_engine.try_lock()
+            .map_err(|_| "Query engine lock poisoned")?;
+        if let Some(ref engine) = *query_engine {
+            let results = engine.fuzzy_search(query, limit);
+            Ok(results.into_iter().map(|(symbol, score)| (symbol.clone(), score)).collect())
+        } else {
+            Err("No cache loaded".to_string())
+        }
+    }
+
+    pub fn list_by_kind(
+        &self,
+        kind: SymbolKind,
+        visibility: Option<&str>,
+    ) -> Result<Vec<Symbol>, String> {
+        let query_engine = self.query_engine.try_lock()
+            .map_err(|_| "Query engine lock poisoned")?;
+        if let Some(ref engine) = *query_engine {
+            let results = engine.list_by_kind(kind, visibility);
+            Ok(results.into_iter().cloned().collect())
+        } else {
+            Err("No cache loaded".to_string())
+        }
+    }
+
     pub fn find_call_sites(&self, callee_name: &str) -> Result<Vec<Symbol>, String> {
         let query_engine = self.query_engine.try_lock()
             .map_err(|_| "Query engine lock poisoned")?;
@@ -221,6 +410,46 @@ impl ASTEngine {
         }
     }
 
+    /// 带有匹配装饰器/注解的函数和方法，`pattern` 为正则表达式，缺省使用
+    /// [`DEFAULT_ROUTE_DECORATOR_PATTERN`]，见 [`QueryEngine::find_route_handlers`]
+    pub fn find_route_handlers(&self, pattern: Option<&str>) -> Result<Vec<Symbol>, String> {
+        let pattern = pattern
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .unwrap_or(DEFAULT_ROUTE_DECORATOR_PATTERN);
+        let pattern = regex::Regex::new(pattern)
+            .map_err(|e| format!("Invalid decorator pattern: {}", e))?;
+        let query_engine = self.query_engine.try_lock()
+            .map_err(|_| "Query engine lock poisoned")?;
+        if let Some(ref engine) = *query_engine {
+            Ok(engine.find_route_handlers(&pattern).into_iter().cloned().collect())
+        } else {
+            Err("No cache loaded".to_string())
+        }
+    }
+
+    /// Web 框架入口，见 [`QueryEngine::find_entrypoints`]
+    pub fn find_entrypoints(&self) -> Result<Vec<Symbol>, String> {
+        let query_engine = self.query_engine.try_lock()
+            .map_err(|_| "Query engine lock poisoned")?;
+        if let Some(ref engine) = *query_engine {
+            Ok(engine.find_entrypoints().into_iter().cloned().collect())
+        } else {
+            Err("No cache loaded".to_string())
+        }
+    }
+
+    /// 没有调用点的函数和方法，见 [`QueryEngine::find_unused_functions`]
+    pub fn find_unused_functions(&self) -> Result<Vec<Symbol>, String> {
+        let query_engine = self.query_engine.try_lock()
+            .map_err(|_| "Query engine lock poisoned")?;
+        if let Some(ref engine) = *query_engine {
+            Ok(engine.find_unused_functions().into_iter().cloned().collect())
+        } else {
+            Err("No cache loaded".to_string())
+        }
+    }
+
     pub fn get_call_graph(
         &self,
         entry: &str,
@@ -235,6 +464,44 @@ impl ASTEngine {
         }
     }
 
+    /// 指定粒度的调用图，见 [`QueryEngine::get_call_graph_with_granularity`]
+    pub fn get_call_graph_with_granularity(
+        &self,
+        entry: &str,
+        max_depth: usize,
+        granularity: CallGraphGranularity,
+    ) -> Result<serde_json::Value, String> {
+        let query_engine = self.query_engine.try_lock()
+            .map_err(|_| "Query engine lock poisoned")?;
+        if let Some(ref engine) = *query_engine {
+            Ok(engine.get_call_graph_with_granularity(entry, max_depth, granularity))
+        } else {
+            Err("No cache loaded".to_string())
+        }
+    }
+
+    /// 文件到导入模块的依赖图，见 [`QueryEngine::get_dependency_graph`]
+    pub fn get_dependency_graph(&self, filter: Option<&str>) -> Result<serde_json::Value, String> {
+        let query_engine = self.query_engine.try_lock()
+            .map_err(|_| "Query engine lock poisoned")?;
+        if let Some(ref engine) = *query_engine {
+            Ok(engine.get_dependency_graph(filter))
+        } else {
+            Err("No cache loaded".to_string())
+        }
+    }
+
+    /// 重命名影响分析，见 [`QueryEngine::rename_impact`]
+    pub fn rename_impact(&self, name: &str, new_name: Option<&str>) -> Result<serde_json::Value, String> {
+        let query_engine = self.query_engine.try_lock()
+            .map_err(|_| "Query engine lock poisoned")?;
+        if let Some(ref engine) = *query_engine {
+            Ok(engine.rename_impact(name, new_name))
+        } else {
+            Err("No cache loaded".to_string())
+        }
+    }
+
     pub fn get_file_structure(&self, file_path: &str) -> Result<Vec<Symbol>, String> {
         let query_engine = self.query_engine.try_lock()
             .map_err(|_| "Query engine lock poisoned")?;
@@ -246,6 +513,27 @@ impl ASTEngine {
         }
     }
 
+    pub fn get_file_symbol_counts(&self) -> Result<std::collections::HashMap<String, usize>, String> {
+        let query_engine = self.query_engine.try_lock()
+            .map_err(|_| "Query engine lock poisoned")?;
+        if let Some(ref engine) = *query_engine {
+            Ok(engine.file_symbol_counts())
+        } else {
+            Err("No cache loaded".to_string())
+        }
+    }
+
+    /// 各文件的解析状况（仅包含已记录的文件），见 [`ParseErrorMode`]
+    pub fn get_file_parse_health(&self) -> Result<std::collections::HashMap<String, ParseHealth>, String> {
+        let query_engine = self.query_engine.try_lock()
+            .map_err(|_| "Query engine lock poisoned")?;
+        if let Some(ref engine) = *query_engine {
+            Ok(engine.file_parse_health())
+        } else {
+            Err("No cache loaded".to_string())
+        }
+    }
+
     pub fn get_class_hierarchy(&self, class_name: &str) -> Result<serde_json::Value, String> {
         let query_engine = self.query_engine.try_lock()
             .map_err(|_| "Query engine lock poisoned")?;
@@ -256,6 +544,17 @@ impl ASTEngine {
         }
     }
 
+    /// 当前索引数据的副本
+    pub fn cache_data(&self) -> Result<CacheData, String> {
+        let query_engine = self.query_engine.try_lock()
+            .map_err(|_| "Query engine lock poisoned")?;
+        if let Some(ref engine) = *query_engine {
+            Ok(engine.cache.clone())
+        } else {
+            Err("No cache loaded".to_string())
+        }
+    }
+
     pub fn get_all_symbols(&self) -> Result<Vec<Symbol>, String> {
         let query_engine = self.query_engine.try_lock()
             .map_err(|_| "Query engine lock poisoned")?;
@@ -298,6 +597,7 @@ impl ASTEngine {
                     | "cpp"
                     | "hpp"
                     | "cc"
+                    | "rb"
             )
         } else {
             false
@@ -365,6 +665,7 @@ impl SecurityScanner {
         custom_rules: &std::collections::HashMap<String, Vec<CustomRule>>,
         include_dirs: &[String],
         exclude_dirs: &[String],
+        follow_links: bool,
     ) -> Result<Vec<SecurityFinding>, String> {
         let mut files_to_scan = Vec::new();
 
@@ -386,7 +687,13 @@ impl SecurityScanner {
             .collect();
 
         // Collect files with filtering
-        for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
+        // walkdir 在跟随符号链接时会把循环链接报告为错误，这里直接跳过
+        for entry in WalkDir::new(path)
+            .follow_links(follow_links)
+            .max_depth(crate::scanner::DEFAULT_MAX_WALK_DEPTH)
+            .into_iter()
+            .filter_map(|e| e.ok())
+        {
             let path = entry.path();
 
             // Check if directory should be excluded
@@ -409,7 +716,7 @@ impl SecurityScanner {
                 }
             }
 
-            if path.is_file() {
+            if entry.file_type().is_file() {
                 files_to_scan.push(path.to_path_buf());
             }
         }
@@ -446,3 +753,157 @@ pub struct SecurityFinding {
     pub message: String,
     pub code: String,
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// 在后台线程运行 `walk`，超时未结束视为陷入链接环
+    fn finishes<T: Send + 'static>(walk: impl FnOnce() -> T + Send + 'static) -> T {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(walk());
+        });
+        receiver
+            .recv_timeout(Duration::from_secs(30))
+            .expect("walk did not terminate")
+    }
+
+    #[test]
+    fn scan_project_populates_subclasses() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(project.join("Base.java"), "class Base {\n}\n").unwrap();
+        std::fs::write(
+            project.join("Children.java"),
+            "class Second extends Base {\n}\n\nclass First extends Base {\n}\n",
+        )
+        .unwrap();
+        let cache = dir.path().join("cache").to_string_lossy().to_string();
+
+        let root = project.to_string_lossy().to_string();
+        let engine = ASTEngine::new(&cache);
+        engine.use_repository(&root);
+        assert_eq!(engine.scan_project(&root), Ok(2));
+        let symbols = engine.get_all_symbols().unwrap();
+        let subclasses = |name: &str| {
+            symbols
+                .iter()
+                .find(|s| s.kind == SymbolKind::Class && s.name == name)
+                .map(|s| s.subclasses.clone())
+                .unwrap()
+        };
+        assert_eq!(subclasses("Base"), vec!["First".to_string(), "Second".to_string()]);
+        assert!(subclasses("First").is_empty());
+    }
+
+    #[test]
+    fn broken_files_are_recorded_with_parse_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(project.join("clean.py"), "def ok():\n    pass\n").unwrap();
+        std::fs::write(project.join("broken.py"), "def broken(:\n    return (1,\n\ndef after():\n    pass\n").unwrap();
+        let root = project.to_string_lossy().to_string();
+        let cache = dir.path().join("cache").to_string_lossy().to_string();
+        let health = |mode: ParseErrorMode| {
+            let engine = ASTEngine::new(&cache).with_parse_error_mode(mode);
+            engine.use_repository(&root);
+            engine.scan_project(&root).unwrap();
+            let mut health: Vec<(String, &str)> = engine
+                .get_file_parse_health()
+                .unwrap()
+                .into_iter()
+                .map(|(path, health)| {
+                    let name = Path::new(&path).file_name().unwrap().to_string_lossy().to_string();
+                    (name, health.as_str())
+                })
+                .collect();
+            health.sort();
+            health
+        };
+
+        assert_eq!(
+            health(ParseErrorMode::Record),
+            vec![("broken.py".to_string(), "has_errors"), ("clean.py".to_string(), "clean")]
+        );
+        // 默认不记录解析状况（缓存中已记录的文件 mtime 未变，不会重新解析，因此使用新的缓存目录）
+        let cache = dir.path().join("cache-ignore").to_string_lossy().to_string();
+        let engine = ASTEngine::new(&cache);
+        engine.use_repository(&root);
+        engine.scan_project(&root).unwrap();
+        assert!(engine.get_file_parse_health().unwrap().is_empty());
+    }
+
+    #[test]
+    fn scan_projects_indexes_overlapping_roots_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first");
+        let second = dir.path().join("second");
+        std::fs::create_dir_all(first.join("pkg")).unwrap();
+        std::fs::create_dir_all(&second).unwrap();
+        std::fs::write(first.join("pkg/a.py"), "def alpha():\n    pass\n").unwrap();
+        std::fs::write(second.join("b.py"), "def beta():\n    pass\n").unwrap();
+        let (first, second, nested) = (
+            first.to_string_lossy().to_string(),
+            second.to_string_lossy().to_string(),
+            first.join("pkg").to_string_lossy().to_string(),
+        );
+        let cache = dir.path().join("cache").to_string_lossy().to_string();
+
+        let engine = ASTEngine::new(&cache);
+        engine.use_repository(&first);
+        assert_eq!(engine.scan_projects(&[&first, &second, &nested]), Ok(2));
+        let mut functions: Vec<String> = engine
+            .get_all_symbols()
+            .unwrap()
+            .into_iter()
+            .filter(|s| s.kind == SymbolKind::Function)
+            .map(|s| s.name)
+            .collect();
+        functions.sort();
+        assert_eq!(functions, ["alpha", "beta"]);
+        assert!(engine.scan_projects(&[&first, "/no/such/root"]).is_err());
+    }
+
+    #[test]
+    fn indexing_stops_at_max_depth() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        std::fs::create_dir_all(project.join("a/b/c")).unwrap();
+        std::fs::write(project.join("top.py"), "def top():\n    pass\n").unwrap();
+        std::fs::write(project.join("a/mid.py"), "def mid():\n    pass\n").unwrap();
+        std::fs::write(project.join("a/b/c/deep.py"), "def deep():\n    pass\n").unwrap();
+        let root = project.to_string_lossy().to_string();
+        let cache = dir.path().join("cache").to_string_lossy().to_string();
+
+        assert_eq!(ASTEngine::new(&cache).with_max_depth(2).scan_project(&root), Ok(2));
+        assert_eq!(ASTEngine::new(&cache).scan_project(&root), Ok(3));
+    }
+
+    #[test]
+    fn symlink_cycles_do_not_hang_walks() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        std::fs::create_dir_all(project.join("pkg")).unwrap();
+        std::fs::write(project.join("pkg/app.py"), "def handler():\n    pass\n").unwrap();
+        // pkg/loop -> project，跟随时形成环
+        std::os::unix::fs::symlink(&project, project.join("pkg/loop")).unwrap();
+        let root = project.to_string_lossy().to_string();
+        let cache = dir.path().join("cache").to_string_lossy().to_string();
+
+        for follow_links in [false, true] {
+            let engine = ASTEngine::new(&cache).with_follow_links(follow_links);
+            let root = root.clone();
+            assert_eq!(finishes(move || engine.scan_project(&root)), Ok(1));
+
+            let project = project.clone();
+            let findings = finishes(move || {
+                SecurityScanner::scan_directory(&project, &Default::default(), &[], &[], follow_links)
+            });
+            assert!(findings.is_ok());
+        }
+    }
+}
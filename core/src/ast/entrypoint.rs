@@ -0,0 +1,247 @@
+// Web 框架入口识别 - 根据装饰器/注解或路由注册调用判断符号是否为 HTTP 入口
+//
+// 只做文本层面的识别：路径取参数中的第一个字符串字面量，不解析拼接、常量引用，
+// 也不合并类级别的路由前缀（如 Spring 类上的 @RequestMapping）
+
+use crate::ast::symbol::{Symbol, SymbolKind};
+use serde::Serialize;
+
+/// HTTP 方法简写（Flask/FastAPI 的 `app.get`、Express 的 `router.post` 等）
+const HTTP_METHODS: &[&str] = &["get", "post", "put", "delete", "patch", "head", "options"];
+
+/// 入口的路由信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RouteInfo {
+    /// flask / fastapi / django-rest / spring / express
+    pub framework: &'static str,
+    /// 大写的 HTTP 方法，空表示未限定（如未指定 method 的 @RequestMapping）
+    pub methods: Vec<String>,
+    /// 路由路径，无法从参数中取得时为 None
+    pub path: Option<String>,
+}
+
+/// 识别符号是否为 Web 框架入口：
+/// - Python：`@app.route` / `@blueprint.route`（Flask）、`@app.get` 等（FastAPI）、`@api_view`（DRF）
+/// - Java：`@GetMapping` 等和 `@RequestMapping`（Spring）
+/// - JavaScript/TypeScript：`app.get('/x', ...)`、`router.post('/x', ...)`（Express 路由注册，符号为调用本身）
+pub fn route_info(symbol: &Symbol) -> Option<RouteInfo> {
+    match symbol.kind {
+        SymbolKind::Function | SymbolKind::Method => match symbol.language() {
+            "python" => symbol.decorators().into_iter().find_map(python_route),
+            "java" => symbol.decorators().into_iter().find_map(java_route),
+            _ => None,
+        },
+        SymbolKind::MethodCall => match symbol.language() {
+            "javascript" | "typescript" => express_route(symbol),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn python_route(decorator: &str) -> Option<RouteInfo> {
+    let (name, args) = split_call(decorator);
+    let (receiver, method) = match name.rsplit_once('.') {
+        Some((receiver, method)) => (Some(receiver), method),
+        None => (None, name),
+    };
+
+    match (receiver, method) {
+        (Some(_), "route") => {
+            // Flask 未指定 methods 时只接受 GET
+            let methods = keyword_strings(args, "methods");
+            Some(RouteInfo {
+                framework: "flask",
+                methods: if methods.is_empty() { vec!["GET".to_string()] } else { upper(methods) },
+                path: first_string_literal(args),
+            })
+        }
+        (Some(_), method) if HTTP_METHODS.contains(&method) => Some(RouteInfo {
+            framework: "fastapi",
+            methods: vec![method.to_uppercase()],
+            path: first_string_literal(args),
+        }),
+        (_, "api_view") => Some(RouteInfo {
+            framework: "django-rest",
+            methods: upper(string_literals(args)),
+            path: None,
+        }),
+        _ => None,
+    }
+}
+
+fn java_route(annotation: &str) -> Option<RouteInfo> {
+    let (name, args) = split_call(annotation);
+    let name = name.rsplit('.').next().unwrap_or(name);
+
+    let methods = match name {
+        "RequestMapping" => args
+            .match_indices("RequestMethod.")
+            .map(|(i, prefix)| {
+                args[i + prefix.len()..]
+                    .chars()
+                    .take_while(|c| c.is_ascii_alphabetic())
+                    .collect::<String>()
+            })
+            .filter(|method| !method.is_empty())
+            .collect(),
+        _ => {
+            let method = name.strip_suffix("Mapping")?;
+            if !HTTP_METHODS.contains(&method.to_lowercase().as_str()) {
+                return None;
+            }
+            vec![method.to_uppercase()]
+        }
+    };
+
+    Some(RouteInfo {
+        framework: "spring",
+        methods,
+        path: first_string_literal(args),
+    })
+}
+
+fn express_route(symbol: &Symbol) -> Option<RouteInfo> {
+    let method = symbol.name.as_str();
+    if method != "all" && !HTTP_METHODS.contains(&method) {
+        return None;
+    }
+    let receiver = symbol.metadata.get("receiver")?.as_str()?;
+    let receiver = receiver.rsplit('.').next().unwrap_or(receiver);
+    if receiver != "app" && !receiver.to_lowercase().ends_with("router") {
+        return None;
+    }
+
+    // 第一个参数必须是以 `/` 开头的路径字面量，排除 `app.get('env')` 之类的配置读取
+    let (_, args) = symbol.code.split_once('(')?;
+    if !args.trim_start().starts_with(['\'', '"', '`']) {
+        return None;
+    }
+    let path = first_string_literal(args).filter(|path| path.starts_with('/'))?;
+
+    Some(RouteInfo {
+        framework: "express",
+        methods: if method == "all" { Vec::new() } else { vec![method.to_uppercase()] },
+        path: Some(path),
+    })
+}
+
+/// 拆分 `name(args)`，返回名称和括号内的参数文本（没有括号时参数为空）
+fn split_call(text: &str) -> (&str, &str) {
+    match text.split_once('(') {
+        Some((name, rest)) => (name.trim(), rest.strip_suffix(')').unwrap_or(rest)),
+        None => (text.trim(), ""),
+    }
+}
+
+/// 文本中的第一个字符串字面量（支持 `'`、`"` 和反引号）
+fn first_string_literal(text: &str) -> Option<String> {
+    string_literals(text).into_iter().next()
+}
+
+/// 文本中的全部字符串字面量，按出现顺序
+fn string_literals(text: &str) -> Vec<String> {
+    let mut literals = Vec::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if !matches!(c, '\'' | '"' | '`') {
+            continue;
+        }
+        let mut literal = String::new();
+        let mut escaped = false;
+        for next in chars.by_ref() {
+            if escaped {
+                literal.push(next);
+                escaped = false;
+            } else if next == '\\' {
+                escaped = true;
+            } else if next == c {
+                break;
+            } else {
+                literal.push(next);
+            }
+        }
+        literals.push(literal);
+    }
+    literals
+}
+
+/// `key=[...]` 形式的关键字参数中的字符串（如 Flask 的 `methods=["GET", "POST"]`）
+fn keyword_strings(args: &str, key: &str) -> Vec<String> {
+    let Some(value) = args
+        .find(key)
+        .and_then(|start| args[start + key.len()..].trim_start().strip_prefix('='))
+    else {
+        return Vec::new();
+    };
+    let end = value.find([']', ')']).unwrap_or(value.len());
+    string_literals(&value[..end])
+}
+
+fn upper(values: Vec<String>) -> Vec<String> {
+    values.into_iter().map(|value| value.to_uppercase()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::ASTParser;
+    use std::path::Path;
+
+    /// (符号名, 路由信息)，只保留识别为入口的符号
+    fn routes(file: &str, content: &str) -> Vec<(String, RouteInfo)> {
+        ASTParser::new()
+            .parse_file(Path::new(file), content)
+            .unwrap()
+            .iter()
+            .filter_map(|symbol| route_info(symbol).map(|route| (symbol.name.clone(), route)))
+            .collect()
+    }
+
+    fn route(framework: &'static str, methods: &[&str], path: Option<&str>) -> RouteInfo {
+        RouteInfo {
+            framework,
+            methods: methods.iter().map(|m| m.to_string()).collect(),
+            path: path.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn flask_and_fastapi_routes_capture_path_and_methods() {
+        let content = "@app.route(\"/users/<int:id>\", methods=[\"get\", \"DELETE\"])\ndef user(id):\n    pass\n\n\
+@bp.route('/health')\ndef health():\n    pass\n\n\
+@api.post(\"/items\")\nasync def create_item():\n    pass\n\n\
+@login_required\ndef profile():\n    pass\n";
+        assert_eq!(
+            routes("app.py", content),
+            vec![
+                ("user".to_string(), route("flask", &["GET", "DELETE"], Some("/users/<int:id>"))),
+                ("health".to_string(), route("flask", &["GET"], Some("/health"))),
+                ("create_item".to_string(), route("fastapi", &["POST"], Some("/items"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn spring_mappings_and_express_registrations_are_entrypoints() {
+        let java = "class Api {\n    @GetMapping(\"/orders\")\n    public void list() {}\n\n\
+    @RequestMapping(value = \"/orders\", method = RequestMethod.PUT)\n    public void update() {}\n\n\
+    @Override\n    public String toString() { return \"\"; }\n}\n";
+        assert_eq!(
+            routes("Api.java", java),
+            vec![
+                ("list".to_string(), route("spring", &["GET"], Some("/orders"))),
+                ("update".to_string(), route("spring", &["PUT"], Some("/orders"))),
+            ]
+        );
+
+        let js = "app.get('/login', handler);\nrouter.post(\"/upload\", upload);\nconst env = app.get('env');\n";
+        assert_eq!(
+            routes("server.js", js),
+            vec![
+                ("get".to_string(), route("express", &["GET"], Some("/login"))),
+                ("post".to_string(), route("express", &["POST"], Some("/upload"))),
+            ]
+        );
+    }
+}
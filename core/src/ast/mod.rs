@@ -1,11 +1,16 @@
 pub mod cache;
 pub mod engine;
+pub mod entrypoint;
 pub mod parser;
 pub mod query;
 pub mod symbol;
 
 pub use cache::{CacheData, CacheManager, FileIndex};
-pub use engine::{ASTEngine, CustomRule, SecurityScanner};
-pub use parser::ASTParser;
-pub use query::QueryEngine;
-pub use symbol::{Symbol, SymbolKind};
+pub use engine::{ASTEngine, CustomRule, ParseErrorMode, SecurityScanner};
+pub use entrypoint::{route_info, RouteInfo};
+pub use parser::{ASTParser, ParseHealth, DEFAULT_PARSE_TIMEOUT_MICROS};
+pub use query::{
+    diff_symbols, CallGraphGranularity, QueryEngine, ReportOptions, SymbolDiff, SymbolRef,
+    DEFAULT_ROUTE_DECORATOR_PATTERN,
+};
+pub use symbol::{stable_ids, Symbol, SymbolKind};
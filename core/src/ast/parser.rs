@@ -1,14 +1,63 @@
 use crate::ast::symbol::{Field, Symbol, SymbolKind};
 use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 use tree_sitter::{Language, Node, Parser, Query};
 
+/// 单个文件解析的默认超时时间（微秒），防止异常文件导致解析长时间阻塞
+pub const DEFAULT_PARSE_TIMEOUT_MICROS: u64 = 5_000_000;
+
+/// 符号代码片段保留的最大字符数
+const MAX_CODE_CHARS: usize = 200;
+
+/// 文件解析状况：tree-sitter 在语法错误时仍会生成树（插入 ERROR/MISSING 节点），此时提取的符号可能不完整
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseHealth {
+    /// 语法树中没有错误节点
+    Clean,
+    /// 语法树包含 ERROR 或 MISSING 节点
+    HasErrors,
+    /// 解析失败（如超时），没有提取任何符号
+    Failed,
+}
+
+impl ParseHealth {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ParseHealth::Clean => "clean",
+            ParseHealth::HasErrors => "has_errors",
+            ParseHealth::Failed => "failed",
+        }
+    }
+}
+
+impl std::str::FromStr for ParseHealth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clean" => Ok(ParseHealth::Clean),
+            "has_errors" => Ok(ParseHealth::HasErrors),
+            "failed" => Ok(ParseHealth::Failed),
+            _ => Err(format!("Unknown parse health: {}", s)),
+        }
+    }
+}
+
 pub struct ASTParser {
     parsers: HashMap<String, Parser>,
+    /// MethodCall 符号名称的最小长度（字符数），更短的调用不记录；默认 1，即不过滤
+    min_call_name_len: usize,
 }
 
 impl ASTParser {
     pub fn new() -> Self {
+        Self::with_timeout(DEFAULT_PARSE_TIMEOUT_MICROS)
+    }
+
+    /// 使用指定的解析超时（微秒）创建解析器，0 表示不限制
+    pub fn with_timeout(timeout_micros: u64) -> Self {
         let mut parsers = HashMap::new();
 
         // Initialize parsers for supported languages
@@ -31,6 +80,7 @@ impl ASTParser {
             (".cpp", tree_sitter_cpp::LANGUAGE.into()),
             (".hpp", tree_sitter_cpp::LANGUAGE.into()),
             (".cc", tree_sitter_cpp::LANGUAGE.into()),
+            (".rb", tree_sitter_ruby::LANGUAGE.into()),
         ];
 
         for (ext, language) in supported_extensions {
@@ -39,38 +89,84 @@ impl ASTParser {
                 log::warn!("Failed to load parser for extension: {}", ext);
                 continue;
             }
+            parser.set_timeout_micros(timeout_micros);
             parsers.insert(ext.to_string(), parser);
         }
 
-        Self { parsers }
+        Self {
+            parsers,
+            min_call_name_len: 1,
+        }
+    }
+
+    /// 跳过名称短于 `min_len` 个字符的调用（如压缩/混淆代码中的 `a()`、`_()`），减少调用图噪音
+    pub fn with_min_call_name_len(mut self, min_len: usize) -> Self {
+        self.min_call_name_len = min_len;
+        self
+    }
+
+    /// 是否有可以解析该文件（按扩展名）的语法
+    pub fn supports(&self, file_path: &Path) -> bool {
+        self.parsers.contains_key(&parser_key(file_path))
     }
 
     pub fn parse_file(&mut self, file_path: &Path, content: &str) -> Result<Vec<Symbol>, String> {
-        let ext = file_path
-            .extension()
-            .and_then(|s| s.to_str())
-            .map(|s| format!(".{}", s))
-            .unwrap_or_default();
+        self.parse_file_with_health(file_path, content)
+            .map(|(symbols, _)| symbols)
+    }
+
+    /// 解析文件并返回语法树是否包含 ERROR/MISSING 节点（[`ParseHealth::Clean`] 或 [`ParseHealth::HasErrors`]）
+    pub fn parse_file_with_health(
+        &mut self,
+        file_path: &Path,
+        content: &str,
+    ) -> Result<(Vec<Symbol>, ParseHealth), String> {
+        let ext = parser_key(file_path);
 
         let parser = self
             .parsers
             .get_mut(&ext)
             .ok_or_else(|| format!("Unsupported file extension: {}", ext))?;
 
-        let tree = parser
-            .parse(content, None)
-            .ok_or_else(|| "Failed to parse file".to_string())?;
+        let tree = match parser.parse(content, None) {
+            Some(tree) => tree,
+            None => {
+                // 超时后需要重置，否则下次解析会从中断处继续
+                parser.reset();
+                log::warn!(
+                    "Parsing {} timed out or was cancelled, skipping file",
+                    file_path.display()
+                );
+                return Err("Failed to parse file: timed out".to_string());
+            }
+        };
 
         let root_node = tree.root_node();
+        // has_error 同时覆盖 ERROR 节点和 MISSING 节点
+        let health = if root_node.has_error() {
+            ParseHealth::HasErrors
+        } else {
+            ParseHealth::Clean
+        };
 
-        match ext.as_str() {
+        let mut symbols = match ext.as_str() {
             ".java" => self.extract_java_symbols(file_path, content, root_node),
             ".py" => self.extract_python_symbols(file_path, content, root_node),
             ".rs" => self.extract_rust_symbols(file_path, content, root_node),
             ".ts" | ".tsx" => self.extract_typescript_symbols(file_path, content, root_node),
             ".js" | ".jsx" => self.extract_javascript_symbols(file_path, content, root_node),
+            ".rb" => self.extract_ruby_symbols(file_path, content, root_node),
+            ".go" => self.extract_go_symbols(file_path, content, root_node),
             _ => self.extract_generic_symbols(file_path, content, &ext, root_node),
+        }?;
+
+        if self.min_call_name_len > 1 {
+            symbols.retain(|symbol| {
+                symbol.kind != SymbolKind::MethodCall
+                    || symbol.name.chars().count() >= self.min_call_name_len
+            });
         }
+        Ok((symbols, health))
     }
 
     fn extract_java_symbols(
@@ -118,12 +214,7 @@ impl ASTParser {
 
                         let start_line = node.start_position().row + 1;
                         let end_line = node.end_position().row + 1;
-                        let code = content[node.byte_range()].to_string();
-                        let code = if code.len() > 500 {
-                            format!("{}...", &code[..500])
-                        } else {
-                            code
-                        };
+                        let code = truncate_code_to(&node, content, 500);
 
                         let kind = if node.kind() == "class_declaration" {
                             SymbolKind::Class
@@ -131,13 +222,7 @@ impl ASTParser {
                             SymbolKind::Interface
                         };
 
-                        // Extract modifiers
-                        let mut modifiers = Vec::new();
-                        if let Some(modifiers_node) = node.child_by_field_name("modifiers") {
-                            for child in modifiers_node.children(&mut modifiers_node.walk()) {
-                                modifiers.push(content[child.byte_range()].to_string());
-                            }
-                        }
+                        let modifiers = java_modifiers(&node, content);
 
                         // Extract superclass
                         let mut parent_classes = Vec::new();
@@ -190,12 +275,7 @@ impl ASTParser {
 
                         let start_line = node.start_position().row + 1;
                         let end_line = node.end_position().row + 1;
-                        let code = content[node.byte_range()].to_string();
-                        let code = if code.len() > 300 {
-                            format!("{}...", &code[..300])
-                        } else {
-                            code
-                        };
+                        let code = truncate_code_to(&node, content, 300);
 
                         let mut metadata = HashMap::new();
                         if let Some(class_name) = class_stack.last() {
@@ -204,6 +284,7 @@ impl ASTParser {
                                 serde_json::Value::String(class_name.clone()),
                             );
                         }
+                        insert_decorators(&mut metadata, java_annotations(&node, content));
 
                         let symbol = Symbol::new(
                             name,
@@ -214,22 +295,44 @@ impl ASTParser {
                         )
                         .with_end_line(end_line as u32)
                         .with_package(package_name.to_string())
+                        .with_modifiers(java_modifiers(&node, content))
                         .with_metadata(metadata);
 
                         symbols.push(symbol);
                     }
                 }
+                "import_declaration" => {
+                    // import [static] a.b.C; / import a.b.*;
+                    let mut module = String::new();
+                    let mut is_static = false;
+                    let mut wildcard = false;
+                    for child in node.children(&mut node.walk()) {
+                        match child.kind() {
+                            "scoped_identifier" | "identifier" => {
+                                module = content[child.byte_range()].to_string();
+                            }
+                            "static" => is_static = true,
+                            "asterisk" => wildcard = true,
+                            _ => {}
+                        }
+                    }
+                    if !module.is_empty() {
+                        if wildcard {
+                            module.push_str(".*");
+                        }
+                        let modifiers = if is_static { vec!["static".to_string()] } else { Vec::new() };
+                        let symbol = import_symbol(&node, content, file_path, module, Vec::new(), None)
+                            .with_package(package_name.to_string())
+                            .with_modifiers(modifiers);
+                        symbols.push(symbol);
+                    }
+                }
                 "method_invocation" => {
                     let name = extract_method_name(&node, content);
                     if !name.is_empty() {
                         let start_line = node.start_position().row + 1;
                         let end_line = node.end_position().row + 1;
-                        let code = content[node.byte_range()].to_string();
-                        let code = if code.len() > 200 {
-                            format!("{}...", &code[..200])
-                        } else {
-                            code
-                        };
+                        let code = truncate_code(&node, content);
 
                         let mut metadata = HashMap::new();
                         if let Some(class_name) = class_stack.last() {
@@ -244,6 +347,9 @@ impl ASTParser {
                                 serde_json::Value::String(method_name.clone()),
                             );
                         }
+                        if let Some(receiver) = extract_call_receiver(&node, content) {
+                            metadata.insert("receiver".to_string(), serde_json::Value::String(receiver));
+                        }
 
                         let symbol = Symbol::new(
                             name,
@@ -320,13 +426,9 @@ impl ASTParser {
 
                         let start_line = node.start_position().row + 1;
                         let end_line = node.end_position().row + 1;
-                        let code = content[node.byte_range()].to_string();
-                        let code = if code.len() > 200 {
-                            format!("{}...", &code[..200])
-                        } else {
-                            code
-                        };
+                        let code = truncate_code(&node, content);
 
+                        let modifiers = vec![python_visibility(&name).to_string()];
                         let symbol = Symbol::new(
                             name,
                             SymbolKind::Class,
@@ -334,7 +436,8 @@ impl ASTParser {
                             start_line as u32,
                             code,
                         )
-                        .with_end_line(end_line as u32);
+                        .with_end_line(end_line as u32)
+                        .with_modifiers(modifiers);
 
                         symbols.push(symbol);
                     }
@@ -346,12 +449,7 @@ impl ASTParser {
 
                         let start_line = node.start_position().row + 1;
                         let end_line = node.end_position().row + 1;
-                        let code = content[node.byte_range()].to_string();
-                        let code = if code.len() > 200 {
-                            format!("{}...", &code[..200])
-                        } else {
-                            code
-                        };
+                        let code = truncate_code(&node, content);
 
                         let kind = if class_stack.is_empty() {
                             SymbolKind::Function
@@ -372,7 +470,9 @@ impl ASTParser {
                                 serde_json::Value::String(func_name.clone()),
                             );
                         }
+                        insert_decorators(&mut metadata, python_decorators(&node, content));
 
+                        let modifiers = vec![python_visibility(&name).to_string()];
                         let symbol = Symbol::new(
                             name,
                             kind,
@@ -381,23 +481,53 @@ impl ASTParser {
                             code,
                         )
                         .with_end_line(end_line as u32)
+                        .with_modifiers(modifiers)
                         .with_metadata(metadata);
 
                         symbols.push(symbol);
                     }
                 }
+                "import_statement" => {
+                    // import a.b, c as d：每个模块一个符号
+                    for name_node in node.children_by_field_name("name", &mut node.walk()) {
+                        let (module, alias) = match name_node.kind() {
+                            "aliased_import" => (
+                                name_node
+                                    .child_by_field_name("name")
+                                    .map(|n| content[n.byte_range()].to_string())
+                                    .unwrap_or_default(),
+                                name_node
+                                    .child_by_field_name("alias")
+                                    .map(|n| content[n.byte_range()].to_string()),
+                            ),
+                            _ => (content[name_node.byte_range()].to_string(), None),
+                        };
+                        if !module.is_empty() {
+                            symbols.push(import_symbol(&node, content, file_path, module, Vec::new(), alias));
+                        }
+                    }
+                }
+                "import_from_statement" => {
+                    // from .a.b import c, d as e / from a import *
+                    if let Some(module_node) = node.child_by_field_name("module_name") {
+                        let module = content[module_node.byte_range()].to_string();
+                        let mut imported: Vec<String> = node
+                            .children_by_field_name("name", &mut node.walk())
+                            .map(|n| content[n.byte_range()].to_string())
+                            .collect();
+                        if node.children(&mut node.walk()).any(|c| c.kind() == "wildcard_import") {
+                            imported.push("*".to_string());
+                        }
+                        symbols.push(import_symbol(&node, content, file_path, module, imported, None));
+                    }
+                }
                 "call" => {
                     if let Some(function_node) = node.child_by_field_name("function") {
                         let name = extract_last_name(&function_node, content);
                         if !name.is_empty() {
                             let start_line = node.start_position().row + 1;
                             let end_line = node.end_position().row + 1;
-                            let code = content[node.byte_range()].to_string();
-                            let code = if code.len() > 200 {
-                                format!("{}...", &code[..200])
-                            } else {
-                                code
-                            };
+                            let code = truncate_code(&node, content);
 
                             let mut metadata = HashMap::new();
                             if let Some(class_name) = class_stack.last() {
@@ -412,6 +542,9 @@ impl ASTParser {
                                     serde_json::Value::String(func_name.clone()),
                                 );
                             }
+                            if let Some(receiver) = extract_call_receiver(&function_node, content) {
+                                metadata.insert("receiver".to_string(), serde_json::Value::String(receiver));
+                            }
 
                             let symbol = Symbol::new(
                                 name,
@@ -461,14 +594,32 @@ impl ASTParser {
     ) -> Result<Vec<Symbol>, String> {
         let mut symbols = Vec::new();
         let mut func_stack: Vec<String> = Vec::new();
+        // 当前所在的 impl / trait：(所属类型或 trait 名, impl 实现的 trait)
+        let mut owner_stack: Vec<(String, Option<String>)> = Vec::new();
+        // 文件中的 trait 实现关系：(目标类型, trait)
+        let mut trait_impls: Vec<(String, String)> = Vec::new();
+
+        /// 函数是否直接定义在 impl / trait 块中（而非方法内部的嵌套函数）
+        fn is_associated_item(node: &Node) -> bool {
+            node.parent()
+                .filter(|parent| parent.kind() == "declaration_list")
+                .and_then(|parent| parent.parent())
+                .map(|owner| matches!(owner.kind(), "impl_item" | "trait_item"))
+                .unwrap_or(false)
+        }
 
+        #[allow(clippy::too_many_arguments)]
         fn visit_node(
             node: Node,
             content: &str,
             file_path: &Path,
             symbols: &mut Vec<Symbol>,
             func_stack: &mut Vec<String>,
+            owner_stack: &mut Vec<(String, Option<String>)>,
+            trait_impls: &mut Vec<(String, String)>,
         ) {
+            let mut pushed_owner = false;
+
             match node.kind() {
                 "struct_item" => {
                     if let Some(name_node) = node.child_by_field_name("name") {
@@ -476,72 +627,155 @@ impl ASTParser {
 
                         let start_line = node.start_position().row + 1;
                         let end_line = node.end_position().row + 1;
-                        let code = content[node.byte_range()].to_string();
-                        let code = if code.len() > 200 {
-                            format!("{}...", &code[..200])
-                        } else {
-                            code
-                        };
 
                         let symbol = Symbol::new(
                             name,
                             SymbolKind::Struct,
                             file_path.to_string_lossy().to_string(),
                             start_line as u32,
-                            code,
+                            truncate_code(&node, content),
                         )
-                        .with_end_line(end_line as u32);
+                        .with_end_line(end_line as u32)
+                        .with_modifiers(collect_child_modifiers(&node, content, &["visibility_modifier"]));
 
                         symbols.push(symbol);
                     }
                 }
-                "function_item" => {
+                "enum_item" => {
                     if let Some(name_node) = node.child_by_field_name("name") {
                         let name = content[name_node.byte_range()].to_string();
-                        func_stack.push(name.clone());
 
                         let start_line = node.start_position().row + 1;
                         let end_line = node.end_position().row + 1;
-                        let code = content[node.byte_range()].to_string();
-                        let code = if code.len() > 200 {
-                            format!("{}...", &code[..200])
-                        } else {
-                            code
-                        };
+
+                        // 记录枚举的变体名称
+                        let variants: Vec<serde_json::Value> = node
+                            .child_by_field_name("body")
+                            .map(|body| {
+                                body.children(&mut body.walk())
+                                    .filter(|child| child.kind() == "enum_variant")
+                                    .filter_map(|variant| variant.child_by_field_name("name"))
+                                    .map(|n| serde_json::Value::String(content[n.byte_range()].to_string()))
+                                    .collect()
+                            })
+                            .unwrap_or_default();
 
                         let mut metadata = HashMap::new();
-                        if let Some(func_name) = func_stack.last() {
-                            metadata.insert(
-                                "callerFunction".to_string(),
-                                serde_json::Value::String(func_name.clone()),
-                            );
+                        metadata.insert("variants".to_string(), serde_json::Value::Array(variants));
+
+                        let symbol = Symbol::new(
+                            name,
+                            SymbolKind::Enum,
+                            file_path.to_string_lossy().to_string(),
+                            start_line as u32,
+                            truncate_code(&node, content),
+                        )
+                        .with_end_line(end_line as u32)
+                        .with_modifiers(collect_child_modifiers(&node, content, &["visibility_modifier"]))
+                        .with_metadata(metadata);
+
+                        symbols.push(symbol);
+                    }
+                }
+                "trait_item" => {
+                    if let Some(name_node) = node.child_by_field_name("name") {
+                        let name = content[name_node.byte_range()].to_string();
+
+                        let start_line = node.start_position().row + 1;
+                        let end_line = node.end_position().row + 1;
+
+                        let symbol = Symbol::new(
+                            name.clone(),
+                            SymbolKind::Interface,
+                            file_path.to_string_lossy().to_string(),
+                            start_line as u32,
+                            truncate_code(&node, content),
+                        )
+                        .with_end_line(end_line as u32)
+                        .with_modifiers(collect_child_modifiers(&node, content, &["visibility_modifier"]));
+
+                        symbols.push(symbol);
+                        owner_stack.push((name, None));
+                        pushed_owner = true;
+                    }
+                }
+                "impl_item" => {
+                    if let Some(type_node) = node.child_by_field_name("type") {
+                        let target = rust_type_name(&type_node, content);
+                        let implemented_trait = node
+                            .child_by_field_name("trait")
+                            .map(|trait_node| rust_type_name(&trait_node, content));
+
+                        if let Some(trait_name) = &implemented_trait {
+                            trait_impls.push((target.clone(), trait_name.clone()));
+                        }
+                        owner_stack.push((target, implemented_trait));
+                        pushed_owner = true;
+                    }
+                }
+                "function_item" | "function_signature_item" => {
+                    if let Some(name_node) = node.child_by_field_name("name") {
+                        let name = content[name_node.byte_range()].to_string();
+                        if node.kind() == "function_item" {
+                            func_stack.push(name.clone());
                         }
 
+                        let start_line = node.start_position().row + 1;
+                        let end_line = node.end_position().row + 1;
+
+                        let mut metadata = HashMap::new();
+                        metadata.insert(
+                            "callerFunction".to_string(),
+                            serde_json::Value::String(name.clone()),
+                        );
+
+                        // impl / trait 中的函数作为所属类型的方法
+                        let owner = owner_stack.last().filter(|_| is_associated_item(&node));
+                        let kind = match owner {
+                            Some((owner_name, implemented_trait)) => {
+                                metadata.insert(
+                                    "ownerClass".to_string(),
+                                    serde_json::Value::String(owner_name.clone()),
+                                );
+                                if let Some(trait_name) = implemented_trait {
+                                    metadata.insert(
+                                        "implTrait".to_string(),
+                                        serde_json::Value::String(trait_name.clone()),
+                                    );
+                                }
+                                SymbolKind::Method
+                            }
+                            None => SymbolKind::Function,
+                        };
+
                         let symbol = Symbol::new(
                             name,
-                            SymbolKind::Function,
+                            kind,
                             file_path.to_string_lossy().to_string(),
                             start_line as u32,
-                            code,
+                            truncate_code(&node, content),
                         )
                         .with_end_line(end_line as u32)
+                        .with_modifiers(collect_child_modifiers(&node, content, &["visibility_modifier"]))
                         .with_metadata(metadata);
 
                         symbols.push(symbol);
                     }
                 }
+                "use_declaration" => {
+                    if let Some(argument) = node.child_by_field_name("argument") {
+                        let (module, imported, alias) = rust_use_path(&argument, content);
+                        if !module.is_empty() {
+                            symbols.push(import_symbol(&node, content, file_path, module, imported, alias));
+                        }
+                    }
+                }
                 "call_expression" => {
                     if let Some(function_node) = node.child_by_field_name("function") {
                         let name = extract_last_name(&function_node, content);
                         if !name.is_empty() {
                             let start_line = node.start_position().row + 1;
                             let end_line = node.end_position().row + 1;
-                            let code = content[node.byte_range()].to_string();
-                            let code = if code.len() > 200 {
-                                format!("{}...", &code[..200])
-                            } else {
-                                code
-                            };
 
                             let mut metadata = HashMap::new();
                             if let Some(func_name) = func_stack.last() {
@@ -550,13 +784,19 @@ impl ASTParser {
                                     serde_json::Value::String(func_name.clone()),
                                 );
                             }
+                            if let Some((owner_name, _)) = owner_stack.last() {
+                                metadata.insert(
+                                    "callerClass".to_string(),
+                                    serde_json::Value::String(owner_name.clone()),
+                                );
+                            }
 
                             let symbol = Symbol::new(
                                 name,
                                 SymbolKind::MethodCall,
                                 file_path.to_string_lossy().to_string(),
                                 start_line as u32,
-                                code,
+                                truncate_code(&node, content),
                             )
                             .with_end_line(end_line as u32)
                             .with_metadata(metadata);
@@ -569,15 +809,39 @@ impl ASTParser {
             }
 
             for child in node.children(&mut node.walk()) {
-                visit_node(child, content, file_path, symbols, func_stack);
+                visit_node(child, content, file_path, symbols, func_stack, owner_stack, trait_impls);
             }
 
-            if node.kind() == "function_item" {
+            if node.kind() == "function_item" && node.child_by_field_name("name").is_some() {
                 func_stack.pop();
             }
+            if pushed_owner {
+                owner_stack.pop();
+            }
+        }
+
+        visit_node(
+            root_node,
+            content,
+            file_path,
+            &mut symbols,
+            &mut func_stack,
+            &mut owner_stack,
+            &mut trait_impls,
+        );
+
+        // 将同一文件中的 trait 实现记录为结构体 / 枚举的父类型
+        for symbol in symbols
+            .iter_mut()
+            .filter(|s| matches!(s.kind, SymbolKind::Struct | SymbolKind::Enum))
+        {
+            for (target, trait_name) in &trait_impls {
+                if *target == symbol.name && !symbol.parent_classes.contains(trait_name) {
+                    symbol.parent_classes.push(trait_name.clone());
+                }
+            }
         }
 
-        visit_node(root_node, content, file_path, &mut symbols, &mut func_stack);
         Ok(symbols)
     }
 
@@ -617,12 +881,7 @@ impl ASTParser {
 
                         let start_line = node.start_position().row + 1;
                         let end_line = node.end_position().row + 1;
-                        let code = content[node.byte_range()].to_string();
-                        let code = if code.len() > 200 {
-                            format!("{}...", &code[..200])
-                        } else {
-                            code
-                        };
+                        let code = truncate_code(&node, content);
 
                         let symbol = Symbol::new(
                             name,
@@ -643,12 +902,7 @@ impl ASTParser {
 
                         let start_line = node.start_position().row + 1;
                         let end_line = node.end_position().row + 1;
-                        let code = content[node.byte_range()].to_string();
-                        let code = if code.len() > 200 {
-                            format!("{}...", &code[..200])
-                        } else {
-                            code
-                        };
+                        let code = truncate_code(&node, content);
 
                         let kind = if class_stack.is_empty() {
                             SymbolKind::Function
@@ -670,6 +924,10 @@ impl ASTParser {
                             );
                         }
 
+                        // TypeScript 的 public / private / protected 修饰符
+                        let modifiers =
+                            collect_child_modifiers(&node, content, &["accessibility_modifier"]);
+
                         let symbol = Symbol::new(
                             name,
                             kind,
@@ -678,23 +936,26 @@ impl ASTParser {
                             code,
                         )
                         .with_end_line(end_line as u32)
+                        .with_modifiers(modifiers)
                         .with_metadata(metadata);
 
                         symbols.push(symbol);
                     }
                 }
+                "import_statement" => {
+                    if let Some(source) = node.child_by_field_name("source") {
+                        let module = unquote(&content[source.byte_range()]);
+                        let imported = js_imported_names(&node, content);
+                        symbols.push(import_symbol(&node, content, file_path, module, imported, None));
+                    }
+                }
                 "call_expression" => {
                     if let Some(function_node) = node.child_by_field_name("function") {
                         let name = extract_last_name(&function_node, content);
                         if !name.is_empty() {
                             let start_line = node.start_position().row + 1;
                             let end_line = node.end_position().row + 1;
-                            let code = content[node.byte_range()].to_string();
-                            let code = if code.len() > 200 {
-                                format!("{}...", &code[..200])
-                            } else {
-                                code
-                            };
+                            let code = truncate_code(&node, content);
 
                             let mut metadata = HashMap::new();
                             if let Some(class_name) = class_stack.last() {
@@ -709,6 +970,9 @@ impl ASTParser {
                                     serde_json::Value::String(func_name.clone()),
                                 );
                             }
+                            if let Some(receiver) = extract_call_receiver(&function_node, content) {
+                                metadata.insert("receiver".to_string(), serde_json::Value::String(receiver));
+                            }
 
                             let symbol = Symbol::new(
                                 name,
@@ -722,6 +986,18 @@ impl ASTParser {
 
                             symbols.push(symbol);
                         }
+
+                        // CommonJS：require('x') 同时记为导入
+                        if function_node.kind() == "identifier" && &content[function_node.byte_range()] == "require" {
+                            let source = node
+                                .child_by_field_name("arguments")
+                                .and_then(|args| args.named_child(0))
+                                .filter(|arg| arg.kind() == "string");
+                            if let Some(source) = source {
+                                let module = unquote(&content[source.byte_range()]);
+                                symbols.push(import_symbol(&node, content, file_path, module, Vec::new(), None));
+                            }
+                        }
                     }
                 }
                 _ => {}
@@ -750,6 +1026,204 @@ impl ASTParser {
         Ok(symbols)
     }
 
+    fn extract_ruby_symbols(
+        &self,
+        file_path: &Path,
+        content: &str,
+        root_node: Node,
+    ) -> Result<Vec<Symbol>, String> {
+        let mut symbols = Vec::new();
+        let mut class_stack: Vec<String> = Vec::new();
+        let mut func_stack: Vec<String> = Vec::new();
+
+        fn visit_node(
+            node: Node,
+            content: &str,
+            file_path: &Path,
+            symbols: &mut Vec<Symbol>,
+            class_stack: &mut Vec<String>,
+            func_stack: &mut Vec<String>,
+        ) {
+            // 关键字 token（如 `class`、`def`）与节点同名，只处理具名节点
+            if !node.is_named() {
+                return;
+            }
+
+            match node.kind() {
+                // module 没有单独的类型，按 Class 处理
+                "class" | "module" => {
+                    if let Some(name_node) = node.child_by_field_name("name") {
+                        let name = extract_last_name(&name_node, content);
+                        class_stack.push(name.clone());
+
+                        let start_line = node.start_position().row + 1;
+                        let end_line = node.end_position().row + 1;
+                        let code = truncate_code(&node, content);
+
+                        let mut parent_classes = Vec::new();
+                        if let Some(superclass) = node.child_by_field_name("superclass") {
+                            // superclass 节点形如 "< Base"
+                            let parent = content[superclass.byte_range()]
+                                .trim_start_matches('<')
+                                .trim();
+                            if !parent.is_empty() {
+                                parent_classes.push(parent.replace("::", "."));
+                            }
+                        }
+
+                        let mut metadata = HashMap::new();
+                        if node.kind() == "module" {
+                            metadata.insert("isModule".to_string(), serde_json::Value::Bool(true));
+                        }
+
+                        let symbol = Symbol::new(
+                            name,
+                            SymbolKind::Class,
+                            file_path.to_string_lossy().to_string(),
+                            start_line as u32,
+                            code,
+                        )
+                        .with_end_line(end_line as u32)
+                        .with_parent_classes(parent_classes)
+                        .with_metadata(metadata);
+
+                        symbols.push(symbol);
+                    }
+                }
+                "method" | "singleton_method" => {
+                    if let Some(name_node) = node.child_by_field_name("name") {
+                        let name = content[name_node.byte_range()].to_string();
+                        func_stack.push(name.clone());
+
+                        let start_line = node.start_position().row + 1;
+                        let end_line = node.end_position().row + 1;
+                        let code = truncate_code(&node, content);
+
+                        let kind = if class_stack.is_empty() {
+                            SymbolKind::Function
+                        } else {
+                            SymbolKind::Method
+                        };
+
+                        let mut metadata = HashMap::new();
+                        if let Some(class_name) = class_stack.last() {
+                            metadata.insert(
+                                "ownerClass".to_string(),
+                                serde_json::Value::String(class_name.clone()),
+                            );
+                        }
+                        if let Some(func_name) = func_stack.last() {
+                            metadata.insert(
+                                "callerFunction".to_string(),
+                                serde_json::Value::String(func_name.clone()),
+                            );
+                        }
+
+                        let symbol = Symbol::new(
+                            name,
+                            kind,
+                            file_path.to_string_lossy().to_string(),
+                            start_line as u32,
+                            code,
+                        )
+                        .with_end_line(end_line as u32)
+                        .with_metadata(metadata);
+
+                        symbols.push(symbol);
+                    }
+                }
+                "call" => {
+                    if let Some(method_node) = node.child_by_field_name("method") {
+                        let name = content[method_node.byte_range()].to_string();
+                        if !name.is_empty() {
+                            let start_line = node.start_position().row + 1;
+                            let end_line = node.end_position().row + 1;
+                            let code = truncate_code(&node, content);
+
+                            let mut metadata = HashMap::new();
+                            if let Some(class_name) = class_stack.last() {
+                                metadata.insert(
+                                    "callerClass".to_string(),
+                                    serde_json::Value::String(class_name.clone()),
+                                );
+                            }
+                            if let Some(func_name) = func_stack.last() {
+                                metadata.insert(
+                                    "callerFunction".to_string(),
+                                    serde_json::Value::String(func_name.clone()),
+                                );
+                            }
+
+                            let symbol = Symbol::new(
+                                name,
+                                SymbolKind::MethodCall,
+                                file_path.to_string_lossy().to_string(),
+                                start_line as u32,
+                                code,
+                            )
+                            .with_end_line(end_line as u32)
+                            .with_metadata(metadata);
+
+                            symbols.push(symbol);
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            for child in node.children(&mut node.walk()) {
+                visit_node(child, content, file_path, symbols, class_stack, func_stack);
+            }
+
+            if matches!(node.kind(), "class" | "module") {
+                class_stack.pop();
+            }
+            if matches!(node.kind(), "method" | "singleton_method") {
+                func_stack.pop();
+            }
+        }
+
+        visit_node(
+            root_node,
+            content,
+            file_path,
+            &mut symbols,
+            &mut class_stack,
+            &mut func_stack,
+        );
+        Ok(symbols)
+    }
+
+    /// Go 目前只提取导入（每个 import_spec 一个符号），供依赖分析使用
+    fn extract_go_symbols(
+        &self,
+        file_path: &Path,
+        content: &str,
+        root_node: Node,
+    ) -> Result<Vec<Symbol>, String> {
+        let mut symbols = Vec::new();
+
+        fn visit_node(node: Node, content: &str, file_path: &Path, symbols: &mut Vec<Symbol>) {
+            if node.kind() == "import_spec" {
+                if let Some(path_node) = node.child_by_field_name("path") {
+                    let module = unquote(&content[path_node.byte_range()]);
+                    let alias = node
+                        .child_by_field_name("name")
+                        .map(|n| content[n.byte_range()].to_string());
+                    symbols.push(import_symbol(&node, content, file_path, module, Vec::new(), alias));
+                }
+                return;
+            }
+
+            for child in node.children(&mut node.walk()) {
+                visit_node(child, content, file_path, symbols);
+            }
+        }
+
+        visit_node(root_node, content, file_path, &mut symbols);
+        Ok(symbols)
+    }
+
     fn extract_generic_symbols(
         &self,
         _file_path: &Path,
@@ -766,6 +1240,15 @@ impl ASTParser {
     }
 }
 
+/// parsers 表的键：带 `.` 前缀的扩展名，如 `.py`
+fn parser_key(file_path: &Path) -> String {
+    file_path
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| format!(".{}", s))
+        .unwrap_or_default()
+}
+
 fn extract_java_field(node: &Node, content: &str) -> Result<Field, String> {
     if let Some(name_node) = node.child_by_field_name("declarator") {
         if let Some(name_node) = name_node.child_by_field_name("name") {
@@ -780,12 +1263,7 @@ fn extract_java_field(node: &Node, content: &str) -> Result<Field, String> {
             let start_line = node.start_position().row + 1;
             let end_line = node.end_position().row + 1;
 
-            let mut modifiers = Vec::new();
-            if let Some(modifiers_node) = node.child_by_field_name("modifiers") {
-                for child in modifiers_node.children(&mut modifiers_node.walk()) {
-                    modifiers.push(content[child.byte_range()].to_string());
-                }
-            }
+            let modifiers = java_modifiers(node, content);
 
             let mut metadata = HashMap::new();
             metadata.insert(
@@ -809,6 +1287,224 @@ fn extract_java_field(node: &Node, content: &str) -> Result<Field, String> {
     }
 }
 
+/// 符号的代码片段：节点源码，超过 [`MAX_CODE_CHARS`] 个字符时截断并追加 `...`
+fn truncate_code(node: &Node, content: &str) -> String {
+    truncate_code_to(node, content, MAX_CODE_CHARS)
+}
+
+/// 同 [`truncate_code`]，最多保留 `max_chars` 个字符
+///
+/// 按字符而不是字节截断，避免在多字节字符（如中文注释、标识符）中间切开
+fn truncate_code_to(node: &Node, content: &str, max_chars: usize) -> String {
+    let code = &content[node.byte_range()];
+    match code.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}...", &code[..end]),
+        None => code.to_string(),
+    }
+}
+
+/// 导入符号：名称和 metadata.module 为导入的模块路径，`imported` 为导入的成员，`alias` 为模块别名
+fn import_symbol(
+    node: &Node,
+    content: &str,
+    file_path: &Path,
+    module: String,
+    imported: Vec<String>,
+    alias: Option<String>,
+) -> Symbol {
+    let start_line = node.start_position().row + 1;
+    let end_line = node.end_position().row + 1;
+    let code = truncate_code(node, content);
+
+    let mut metadata = HashMap::new();
+    metadata.insert("module".to_string(), serde_json::Value::String(module.clone()));
+    if !imported.is_empty() {
+        metadata.insert(
+            "imported".to_string(),
+            serde_json::Value::Array(imported.into_iter().map(serde_json::Value::String).collect()),
+        );
+    }
+    if let Some(alias) = alias {
+        metadata.insert("alias".to_string(), serde_json::Value::String(alias));
+    }
+
+    Symbol::new(
+        module,
+        SymbolKind::Import,
+        file_path.to_string_lossy().to_string(),
+        start_line as u32,
+        code,
+    )
+    .with_end_line(end_line as u32)
+    .with_metadata(metadata)
+}
+
+/// 去掉字符串字面量两端的引号（`'x'`、`"x"`、`` `x` ``）
+fn unquote(text: &str) -> String {
+    text.trim().trim_matches(|c| c == '"' || c == '\'' || c == '`').to_string()
+}
+
+/// JS/TS import 语句导入的本地成员：默认导入、`* as ns` 和命名导入的原始名称
+fn js_imported_names(node: &Node, content: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let Some(clause) = node
+        .children(&mut node.walk())
+        .find(|child| child.kind() == "import_clause")
+    else {
+        return names;
+    };
+
+    for child in clause.children(&mut clause.walk()) {
+        match child.kind() {
+            "identifier" | "namespace_import" => names.push(content[child.byte_range()].to_string()),
+            "named_imports" => {
+                for specifier in child.children(&mut child.walk()) {
+                    if specifier.kind() == "import_specifier" {
+                        if let Some(name) = specifier.child_by_field_name("name") {
+                            names.push(content[name.byte_range()].to_string());
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+/// 解析 Rust `use` 的参数：(模块路径, 导入的成员, 别名)
+///
+/// `a::b::{C, d::E}` -> (`a::b`, [`C`, `d::E`])，`a::b::*` -> (`a::b`, [`*`])，
+/// `a::B as C` -> (`a::B`, [], `C`)，其余形式整体作为路径
+fn rust_use_path(node: &Node, content: &str) -> (String, Vec<String>, Option<String>) {
+    let text = |n: &Node| content[n.byte_range()].split_whitespace().collect::<String>();
+    match node.kind() {
+        "scoped_use_list" => {
+            let module = node.child_by_field_name("path").map(|p| text(&p)).unwrap_or_default();
+            let imported = node
+                .child_by_field_name("list")
+                .map(|list| {
+                    list.named_children(&mut list.walk())
+                        .map(|item| text(&item))
+                        .collect()
+                })
+                .unwrap_or_default();
+            (module, imported, None)
+        }
+        "use_wildcard" => {
+            let full = text(node);
+            let module = full.trim_end_matches('*').trim_end_matches("::").to_string();
+            (module, vec!["*".to_string()], None)
+        }
+        "use_as_clause" => (
+            node.child_by_field_name("path").map(|p| text(&p)).unwrap_or_default(),
+            Vec::new(),
+            node.child_by_field_name("alias").map(|a| text(&a)),
+        ),
+        _ => (text(node), Vec::new(), None),
+    }
+}
+
+/// 收集指定类型的直接子节点文本（如 Rust 的 `visibility_modifier`）
+fn collect_child_modifiers(node: &Node, content: &str, kinds: &[&str]) -> Vec<String> {
+    node.children(&mut node.walk())
+        .filter(|child| kinds.contains(&child.kind()))
+        .map(|child| content[child.byte_range()].to_string())
+        .collect()
+}
+
+/// Python 函数的装饰器，去掉 `@`（`@app.route("/")` -> `app.route("/")`）
+///
+/// 装饰器位于外层的 `decorated_definition` 节点中，按书写顺序返回
+fn python_decorators(node: &Node, content: &str) -> Vec<String> {
+    let Some(parent) = node.parent().filter(|parent| parent.kind() == "decorated_definition") else {
+        return Vec::new();
+    };
+    parent
+        .children(&mut parent.walk())
+        .filter(|child| child.kind() == "decorator")
+        .map(|child| decorator_text(&content[child.byte_range()]))
+        .collect()
+}
+
+/// Java 声明的修饰符关键字（`public`、`static` 等），不含注解
+///
+/// `modifiers` 是声明节点的普通子节点而不是字段，不能用 `child_by_field_name` 获取
+fn java_modifiers(node: &Node, content: &str) -> Vec<String> {
+    node.children(&mut node.walk())
+        .filter(|child| child.kind() == "modifiers")
+        .flat_map(|modifiers| {
+            modifiers
+                .children(&mut modifiers.walk())
+                .filter(|child| !matches!(child.kind(), "annotation" | "marker_annotation"))
+                .map(|child| content[child.byte_range()].to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Java 方法修饰符中的注解，去掉 `@`（`@GetMapping("/")` -> `GetMapping("/")`）
+fn java_annotations(node: &Node, content: &str) -> Vec<String> {
+    node.children(&mut node.walk())
+        .filter(|child| child.kind() == "modifiers")
+        .flat_map(|modifiers| {
+            modifiers
+                .children(&mut modifiers.walk())
+                .filter(|child| matches!(child.kind(), "annotation" | "marker_annotation"))
+                .map(|child| decorator_text(&content[child.byte_range()]))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn decorator_text(text: &str) -> String {
+    text.trim().trim_start_matches('@').trim().to_string()
+}
+
+/// 有装饰器/注解时写入元数据的 `decorators` 字段，见 [`Symbol::decorators`]
+fn insert_decorators(metadata: &mut HashMap<String, serde_json::Value>, decorators: Vec<String>) {
+    if !decorators.is_empty() {
+        metadata.insert(
+            "decorators".to_string(),
+            serde_json::Value::Array(decorators.into_iter().map(serde_json::Value::String).collect()),
+        );
+    }
+}
+
+/// 按 Python 下划线约定推断可见性：`__x` 为 private，`_x` 为 protected，其余（含 `__x__`）为 public
+fn python_visibility(name: &str) -> &'static str {
+    if name.starts_with("__") && !name.ends_with("__") {
+        "private"
+    } else if name.starts_with('_') && !name.starts_with("__") {
+        "protected"
+    } else {
+        "public"
+    }
+}
+
+/// Rust 类型节点的名称，去掉泛型参数和路径前缀（`crate::auth::Token<T>` -> `Token`）
+fn rust_type_name(node: &Node, content: &str) -> String {
+    match node.kind() {
+        "generic_type" => node
+            .child_by_field_name("type")
+            .map(|inner| rust_type_name(&inner, content))
+            .unwrap_or_else(|| extract_last_name(node, content)),
+        "scoped_type_identifier" => node
+            .child_by_field_name("name")
+            .map(|name| content[name.byte_range()].to_string())
+            .unwrap_or_else(|| extract_last_name(node, content)),
+        _ => extract_last_name(node, content),
+    }
+}
+
+/// 调用的接收者表达式文本（`obj.foo().bar()` 中 `bar` 的接收者为 `obj.foo()`）；
+/// 取节点的 `object` 字段，适用于 Java 的 method_invocation、Python 的 attribute、JS/TS 的 member_expression
+fn extract_call_receiver(node: &Node, content: &str) -> Option<String> {
+    node.child_by_field_name("object")
+        .map(|object| content[object.byte_range()].trim().to_string())
+        .filter(|receiver| !receiver.is_empty())
+}
+
 fn extract_method_name(node: &Node, content: &str) -> String {
     if let Some(name_node) = node.child_by_field_name("name") {
         content[name_node.byte_range()].to_string()
@@ -834,3 +1530,331 @@ fn extract_last_name(node: &Node, content: &str) -> String {
     // Get the last part after splitting by dots
     text.split('.').last().unwrap_or(&text).to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// (名称, 可见性)，不含调用和导入
+    fn visibilities(file: &str, content: &str) -> Vec<(String, Option<&'static str>)> {
+        ASTParser::new()
+            .parse_file(Path::new(file), content)
+            .unwrap()
+            .into_iter()
+            .filter(|s| !matches!(s.kind, SymbolKind::MethodCall | SymbolKind::Import))
+            .map(|s| (s.name.clone(), s.visibility()))
+            .collect()
+    }
+
+    fn expected(items: &[(&str, &'static str)]) -> Vec<(String, Option<&'static str>)> {
+        items.iter().map(|(name, v)| (name.to_string(), Some(*v))).collect()
+    }
+
+    #[test]
+    fn captures_rust_visibility() {
+        let content = "pub fn exported() {}\npub(crate) fn internal() {}\nfn hidden() {}\n";
+        assert_eq!(
+            visibilities("lib.rs", content),
+            expected(&[("exported", "public"), ("internal", "internal"), ("hidden", "private")])
+        );
+    }
+
+    #[test]
+    fn infers_python_visibility_from_underscores() {
+        let content = "def run():\n    pass\ndef _helper():\n    pass\ndef __secret():\n    pass\ndef __init__():\n    pass\n";
+        assert_eq!(
+            visibilities("app.py", content),
+            expected(&[("run", "public"), ("_helper", "protected"), ("__secret", "private"), ("__init__", "public")])
+        );
+    }
+
+    #[test]
+    fn captures_typescript_accessibility() {
+        let content = "class Service {\n  public open() {}\n  private secret() {}\n  protected guarded() {}\n  plain() {}\n}\n";
+        assert_eq!(
+            visibilities("service.ts", content),
+            expected(&[
+                ("Service", "public"),
+                ("open", "public"),
+                ("secret", "private"),
+                ("guarded", "protected"),
+                ("plain", "public"),
+            ])
+        );
+    }
+
+    #[test]
+    fn captures_java_modifiers() {
+        let content = "public class Api {\n  @GetMapping(\"/\")\n  public static void open() {}\n  private void hidden() {}\n  void local() {}\n}\n";
+        let symbols = ASTParser::new().parse_file(Path::new("Api.java"), content).unwrap();
+        let open = symbols.iter().find(|s| s.name == "open").unwrap();
+        assert_eq!(open.modifiers, vec!["public", "static"]);
+        assert_eq!(open.decorators(), vec!["GetMapping(\"/\")"]);
+        assert_eq!(
+            visibilities("Api.java", content),
+            expected(&[("Api", "public"), ("open", "public"), ("hidden", "private"), ("local", "internal")])
+        );
+    }
+
+    #[test]
+    fn parse_timeout_skips_large_file() {
+        let large: String = (0..50_000)
+            .map(|i| format!("def f{}(x):\n    return g(x) + {}\n", i, i))
+            .collect();
+        let started = std::time::Instant::now();
+        let result = ASTParser::with_timeout(1).parse_file(Path::new("big.py"), &large);
+        assert_eq!(result.unwrap_err(), "Failed to parse file: timed out");
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[test]
+    fn extracts_ruby_classes_methods_and_calls() {
+        let content = "module Billing\n  class Invoice < Base\n    def total\n      items.sum(&:price)\n    end\n\n    def self.create(attrs)\n      repo.save(attrs)\n    end\n  end\nend\n";
+        let symbols = ASTParser::new().parse_file(Path::new("invoice.rb"), content).unwrap();
+        let summary: Vec<(SymbolKind, &str, u32, u32)> = symbols
+            .iter()
+            .map(|s| (s.kind.clone(), s.name.as_str(), s.start_line, s.end_line))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (SymbolKind::Class, "Billing", 1, 11),
+                (SymbolKind::Class, "Invoice", 2, 10),
+                (SymbolKind::Method, "total", 3, 5),
+                (SymbolKind::MethodCall, "sum", 4, 4),
+                (SymbolKind::Method, "create", 7, 9),
+                (SymbolKind::MethodCall, "save", 8, 8),
+            ]
+        );
+        assert_eq!(symbols[0].metadata.get("isModule"), Some(&serde_json::Value::Bool(true)));
+        assert_eq!(symbols[1].parent_classes, vec!["Base"]);
+        assert_eq!(symbols[5].metadata.get("callerFunction"), Some(&serde_json::json!("create")));
+        assert!(crate::scanner::is_supported_file(Path::new("invoice.rb")));
+        assert!(crate::rules::model::Language::from("ruby").matches_extension("rb"));
+    }
+
+    #[test]
+    fn extracts_rust_traits_enums_and_impl_ownership() {
+        let content = "pub trait Authenticator {\n    fn verify(&self, token: &str) -> bool;\n}\n\npub enum Role {\n    Admin,\n    User,\n}\n\npub struct TokenAuth {\n    secret: String,\n}\n\nimpl TokenAuth {\n    pub fn new(secret: String) -> Self {\n        Self { secret }\n    }\n}\n\nimpl Authenticator for TokenAuth {\n    fn verify(&self, token: &str) -> bool {\n        token == self.secret\n    }\n}\n";
+        let symbols = ASTParser::new().parse_file(Path::new("auth.rs"), content).unwrap();
+        let summary: Vec<(SymbolKind, &str, u32, u32)> = symbols
+            .iter()
+            .map(|s| (s.kind.clone(), s.name.as_str(), s.start_line, s.end_line))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (SymbolKind::Interface, "Authenticator", 1, 3),
+                (SymbolKind::Method, "verify", 2, 2),
+                (SymbolKind::Enum, "Role", 5, 8),
+                (SymbolKind::Struct, "TokenAuth", 10, 12),
+                (SymbolKind::Method, "new", 15, 17),
+                (SymbolKind::Method, "verify", 21, 23),
+            ]
+        );
+        let meta = |index: usize, key: &str| symbols[index].metadata.get(key).cloned();
+
+        assert_eq!(meta(1, "ownerClass"), Some(serde_json::json!("Authenticator")));
+        assert_eq!(meta(2, "variants"), Some(serde_json::json!(["Admin", "User"])));
+        // impl 块中的方法归属于目标类型，trait 实现同时记录 trait 名称并作为父类
+        assert_eq!(symbols[3].parent_classes, vec!["Authenticator"]);
+        assert_eq!(meta(4, "ownerClass"), Some(serde_json::json!("TokenAuth")));
+        assert_eq!(meta(4, "implTrait"), None);
+        assert_eq!(meta(5, "ownerClass"), Some(serde_json::json!("TokenAuth")));
+        assert_eq!(meta(5, "implTrait"), Some(serde_json::json!("Authenticator")));
+    }
+
+    #[test]
+    fn short_call_names_are_skipped_below_threshold() {
+        let content = "def main():\n    a()\n    ab()\n    run()\n";
+        let calls = |parser: &mut ASTParser| {
+            parser
+                .parse_file(Path::new("app.py"), content)
+                .unwrap()
+                .into_iter()
+                .filter(|s| s.kind == SymbolKind::MethodCall)
+                .map(|s| s.name)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(calls(&mut ASTParser::new()), ["a", "ab", "run"]);
+        assert_eq!(calls(&mut ASTParser::new().with_min_call_name_len(2)), ["ab", "run"]);
+        assert_eq!(calls(&mut ASTParser::new().with_min_call_name_len(3)), ["run"]);
+    }
+
+    #[test]
+    fn chained_calls_record_their_receiver() {
+        let mut parser = ASTParser::new();
+        let files = [
+            ("app.py", "def main():\n    a.b().c()\n"),
+            ("app.js", "function main() {\n    a.b().c();\n}\n"),
+            ("App.java", "class App {\n    void main() {\n        a.b().c();\n    }\n}\n"),
+        ];
+        for (name, content) in files {
+            let mut calls: Vec<(String, Option<String>)> = parser
+                .parse_file(Path::new(name), content)
+                .unwrap()
+                .into_iter()
+                .filter(|s| s.kind == SymbolKind::MethodCall)
+                .map(|s| {
+                    let receiver = s.metadata.get("receiver").and_then(|r| r.as_str()).map(str::to_string);
+                    (s.name, receiver)
+                })
+                .collect();
+            calls.sort();
+            assert_eq!(
+                calls,
+                vec![
+                    ("b".to_string(), Some("a".to_string())),
+                    ("c".to_string(), Some("a.b()".to_string())),
+                ],
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn import_statements_record_module_paths() {
+        let mut parser = ASTParser::new();
+        let mut imports = |name: &str, content: &str| {
+            parser
+                .parse_file(Path::new(name), content)
+                .unwrap()
+                .into_iter()
+                .filter(|s| s.kind == SymbolKind::Import)
+                .map(|s| {
+                    assert_eq!(s.metadata.get("module"), Some(&serde_json::json!(s.name)));
+                    let imported = s.metadata.get("imported").cloned().unwrap_or_default();
+                    (s.name, imported)
+                })
+                .collect::<Vec<_>>()
+        };
+        let none = serde_json::Value::Null;
+        let names = |names: &[&str]| serde_json::json!(names);
+
+        assert_eq!(
+            imports("app.py", "import os\nimport os.path as osp\nfrom flask import request, jsonify\n"),
+            vec![
+                ("os".to_string(), none.clone()),
+                ("os.path".to_string(), none.clone()),
+                ("flask".to_string(), names(&["request", "jsonify"])),
+            ]
+        );
+        assert_eq!(
+            imports("app.js", "import fs from 'fs';\nconst cp = require('child_process');\n"),
+            vec![("fs".to_string(), names(&["fs"])), ("child_process".to_string(), none.clone())]
+        );
+        assert_eq!(
+            imports("app.ts", "import { readFile } from 'fs/promises';\n"),
+            vec![("fs/promises".to_string(), names(&["readFile"]))]
+        );
+        assert_eq!(
+            imports("App.java", "import java.io.File;\nimport java.util.*;\nclass App {}\n"),
+            vec![("java.io.File".to_string(), none.clone()), ("java.util.*".to_string(), none.clone())]
+        );
+        assert_eq!(
+            imports("lib.rs", "use std::collections::HashMap;\nuse serde::{Deserialize, Serialize};\n"),
+            vec![
+                ("std::collections::HashMap".to_string(), none.clone()),
+                ("serde".to_string(), names(&["Deserialize", "Serialize"])),
+            ]
+        );
+        assert_eq!(
+            imports("main.go", "package main\n\nimport (\n    \"fmt\"\n    ex \"os/exec\"\n)\n"),
+            vec![("fmt".to_string(), none.clone()), ("os/exec".to_string(), none)]
+        );
+    }
+
+    #[test]
+    fn records_python_decorators_and_java_annotations() {
+        let decorators = |file: &str, content: &str| -> Vec<(String, Vec<String>)> {
+            ASTParser::new()
+                .parse_file(Path::new(file), content)
+                .unwrap()
+                .into_iter()
+                .filter(|s| matches!(s.kind, SymbolKind::Function | SymbolKind::Method))
+                .map(|s| (s.name.clone(), s.decorators().into_iter().map(str::to_string).collect()))
+                .collect()
+        };
+        let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            decorators(
+                "app.py",
+                "@app.route(\"/users\", methods=[\"POST\"])\n@login_required\ndef create_user():\n    pass\n\ndef helper():\n    pass\n",
+            ),
+            vec![
+                ("create_user".to_string(), strings(&["app.route(\"/users\", methods=[\"POST\"])", "login_required"])),
+                ("helper".to_string(), Vec::new()),
+            ]
+        );
+        assert_eq!(
+            decorators(
+                "UserController.java",
+                "class UserController {\n    @Override\n    @GetMapping(\"/users\")\n    public List<User> list() { return null; }\n}\n",
+            ),
+            vec![("list".to_string(), strings(&["Override", "GetMapping(\"/users\")"]))]
+        );
+    }
+
+    #[test]
+    fn long_rust_snippets_are_truncated_on_char_boundaries() {
+        // 每行 3 字节的中文注释使字节 200 落在多字节字符中间
+        let comments: String = (0..40).map(|i| format!("    // 说明第{}行\n", i)).collect();
+        let content = format!("trait Store {{\n{}    fn save(&self);\n}}\n\nenum Kind {{\n{}    A,\n}}\n", comments, comments);
+        let symbols = ASTParser::new().parse_file(Path::new("lib.rs"), &content).unwrap();
+
+        for name in ["Store", "Kind"] {
+            let symbol = symbols.iter().find(|s| s.name == name).unwrap();
+            assert!(symbol.code.ends_with("..."));
+            assert_eq!(symbol.code.trim_end_matches("...").chars().count(), MAX_CODE_CHARS);
+        }
+    }
+
+    #[test]
+    fn ruby_snippets_with_multibyte_text_are_truncated_safely() {
+        // 字节 200 落在中文注释的多字节字符中间
+        let comments: String = (0..30).map(|i| format!("  # 用户第{}条说明\n", i)).collect();
+        let content = format!(
+            "module Bi\n  class Invoice\n{}    def total\n      items.sum(:amount)\n    end\n  end\nend\n\nrender(\"x{}\")\n",
+            comments,
+            "报表".repeat(120)
+        );
+        let symbols = ASTParser::new().parse_file(Path::new("invoice.rb"), &content).unwrap();
+
+        for name in ["Bi", "Invoice", "render"] {
+            let symbol = symbols.iter().find(|s| s.name == name).unwrap();
+            assert!(symbol.code.ends_with("..."), "{} was not truncated", name);
+            assert_eq!(symbol.code.trim_end_matches("...").chars().count(), MAX_CODE_CHARS);
+        }
+        assert!(symbols.iter().any(|s| s.name == "total" && s.kind == SymbolKind::Method));
+    }
+
+    #[test]
+    fn long_non_ascii_imports_are_truncated_safely() {
+        let names: Vec<String> = (0..60).map(|i| format!("名字{}", i)).collect();
+        let content = format!("from m import ({})\n", names.join(", "));
+        let symbols = ASTParser::new().parse_file(Path::new("app.py"), &content).unwrap();
+
+        let import = symbols.iter().find(|s| s.kind == SymbolKind::Import).unwrap();
+        assert_eq!(import.name, "m");
+        assert!(import.code.ends_with("..."));
+        assert_eq!(import.code.trim_end_matches("...").chars().count(), MAX_CODE_CHARS);
+        assert_eq!(import.metadata["imported"].as_array().unwrap().len(), 60);
+    }
+
+    #[test]
+    fn java_snippets_with_multibyte_text_are_truncated_safely() {
+        // 字节 500 / 300 都落在中文注释的多字节字符中间
+        let comments = "        // 校验订单金额是否有效\n".repeat(60);
+        let content = format!("class 订单服务 {{\n    void 提交V2() {{\n{}    }}\n}}\n", comments);
+        let symbols = ASTParser::new().parse_file(Path::new("Order.java"), &content).unwrap();
+
+        for (name, max) in [("订单服务", 500), ("提交V2", 300)] {
+            let symbol = symbols.iter().find(|s| s.name == name).unwrap();
+            assert!(symbol.code.ends_with("..."));
+            assert_eq!(symbol.code.trim_end_matches("...").chars().count(), max);
+        }
+    }
+}
@@ -1,12 +1,77 @@
 use crate::ast::cache::CacheData;
-use crate::ast::symbol::Symbol;
+use crate::ast::parser::ParseHealth;
+use crate::ast::symbol::{Symbol, SymbolKind};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+/// `query` 与 `text` 中任意子串之间的最小编辑距离（Levenshtein，子串起止位置不计代价）
+fn substring_edit_distance(query: &[char], text: &[char]) -> usize {
+    // prev[j]：query 前 i 个字符与以 text[j] 之前结尾的子串的最小距离
+    let mut prev = vec![0usize; text.len() + 1];
+    for (i, qc) in query.iter().enumerate() {
+        let mut current = vec![i + 1; text.len() + 1];
+        for (j, tc) in text.iter().enumerate() {
+            let substitution = prev[j] + usize::from(qc != tc);
+            current[j + 1] = substitution.min(prev[j + 1] + 1).min(current[j] + 1);
+        }
+        prev = current;
+    }
+    prev.into_iter().min().unwrap_or(query.len())
+}
+
+/// `start_line..=end_line` 包含 `line` 的定义中范围最小的一个（嵌套时即最内层）
+pub(crate) fn innermost_symbol(symbols: &[Symbol], line: u32) -> Option<&Symbol> {
+    symbols
+        .iter()
+        .filter(|symbol| !matches!(symbol.kind, SymbolKind::MethodCall | SymbolKind::Import))
+        .filter(|symbol| symbol.start_line <= line && line <= symbol.end_line)
+        .min_by_key(|symbol| (symbol.end_line - symbol.start_line, std::cmp::Reverse(symbol.start_line)))
+}
 
 pub struct QueryEngine {
     pub cache: CacheData,
 }
 
+/// 常见 Web 框架路由装饰器/注解的默认匹配模式：Flask/FastAPI（`app.route`、`router.get` 等）、
+/// Django REST framework（`api_view`）、Spring（`GetMapping` 等）和 JAX-RS（`Path`、`GET` 等）
+pub const DEFAULT_ROUTE_DECORATOR_PATTERN: &str = r"(?i)^(?:[\w.]+\.)?(?:route|get|post|put|delete|patch|head|options|websocket|api_view|requestmapping|getmapping|postmapping|putmapping|deletemapping|patchmapping|path)\b";
+
+/// 分析报告选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReportOptions {
+    /// 报告中最多包含的节点数（按节点 ID 排序后截断）；None 表示不限制
+    pub max_nodes: Option<usize>,
+    /// 只包含这些类型的符号（如只保留类和函数）；None 表示全部类型
+    pub kinds: Option<Vec<SymbolKind>>,
+}
+
+/// 调用图的粒度：按函数（默认），或把函数折叠到所在的文件/模块（文件所在目录）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CallGraphGranularity {
+    #[default]
+    Function,
+    File,
+    Module,
+}
+
+impl CallGraphGranularity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CallGraphGranularity::Function => "function",
+            CallGraphGranularity::File => "file",
+            CallGraphGranularity::Module => "module",
+        }
+    }
+}
+
+impl ReportOptions {
+    fn includes(&self, kind: &SymbolKind) -> bool {
+        self.kinds.as_ref().map_or(true, |kinds| kinds.contains(kind))
+    }
+}
+
 impl QueryEngine {
     pub fn new(cache: CacheData) -> Self {
         Self { cache }
@@ -27,6 +92,76 @@ impl QueryEngine {
         results
     }
 
+    /// 返回文件中包含 `line` 的最内层符号（函数、方法、类等定义，不含调用），见 [`innermost_symbol`]
+    pub fn symbol_at_line(&self, file: &str, line: u32) -> Option<&Symbol> {
+        let file_index = self.cache.index.get(file)?;
+        innermost_symbol(&file_index.symbols, line)
+    }
+
+    /// 模糊搜索符号名，返回按匹配度（0~1，越大越好）降序排列的前 `limit` 个结果
+    ///
+    /// 名称包含查询串时得分不低于 0.8；否则按查询串与名称中最相近子串的编辑距离打分，
+    /// 编辑距离超过查询长度三分之一的符号不返回
+    pub fn fuzzy_search(&self, query: &str, limit: usize) -> Vec<(&Symbol, f64)> {
+        let query: Vec<char> = query.to_lowercase().chars().collect();
+        if query.is_empty() || limit == 0 {
+            return Vec::new();
+        }
+
+        let max_distance = query.len() / 3;
+        let mut results: Vec<(&Symbol, f64)> = self
+            .cache
+            .index
+            .values()
+            .flat_map(|file_index| file_index.symbols.iter())
+            .filter_map(|symbol| {
+                let name: Vec<char> = symbol.name.to_lowercase().chars().collect();
+                let distance = substring_edit_distance(&query, &name);
+                if distance > max_distance {
+                    return None;
+                }
+
+                let score = if distance == 0 {
+                    0.8 + 0.2 * query.len() as f64 / name.len().max(1) as f64
+                } else {
+                    0.8 * (1.0 - distance as f64 / query.len() as f64)
+                };
+                Some((symbol, score))
+            })
+            .collect();
+
+        results.sort_by(|(a, score_a), (b, score_b)| {
+            score_b
+                .total_cmp(score_a)
+                .then_with(|| a.name.len().cmp(&b.name.len()))
+                .then_with(|| a.file_path.cmp(&b.file_path))
+                .then_with(|| a.start_line.cmp(&b.start_line))
+        });
+        results.truncate(limit);
+        results
+    }
+
+    /// 列出索引中某一类型的全部符号，按文件路径和行号排序
+    ///
+    /// 指定 `visibility` 时只返回该可见性的符号（见 [`Symbol::visibility`]）
+    pub fn list_by_kind(&self, kind: SymbolKind, visibility: Option<&str>) -> Vec<&Symbol> {
+        let mut results: Vec<&Symbol> = self
+            .cache
+            .index
+            .values()
+            .flat_map(|file_index| file_index.symbols.iter())
+            .filter(|symbol| symbol.kind == kind)
+            .filter(|symbol| visibility.is_none() || symbol.visibility() == visibility)
+            .collect();
+
+        results.sort_by(|a, b| {
+            a.file_path
+                .cmp(&b.file_path)
+                .then(a.start_line.cmp(&b.start_line))
+        });
+        results
+    }
+
     pub fn find_call_sites(&self, callee_name: &str) -> Vec<&Symbol> {
         let needle = callee_name.trim();
         if needle.is_empty() {
@@ -47,6 +182,77 @@ impl QueryEngine {
         results
     }
 
+    /// 查找带有匹配 `pattern` 的装饰器/注解的函数和方法（如 HTTP 路由处理函数），
+    /// 按文件路径和行号排序；`pattern` 与去掉 `@` 的装饰器全文匹配，见 [`DEFAULT_ROUTE_DECORATOR_PATTERN`]
+    pub fn find_route_handlers(&self, pattern: &regex::Regex) -> Vec<&Symbol> {
+        let mut results: Vec<&Symbol> = self
+            .cache
+            .index
+            .values()
+            .flat_map(|file_index| file_index.symbols.iter())
+            .filter(|symbol| matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method))
+            .filter(|symbol| symbol.decorators().iter().any(|d| pattern.is_match(d)))
+            .collect();
+
+        results.sort_by(|a, b| {
+            a.file_path
+                .cmp(&b.file_path)
+                .then(a.start_line.cmp(&b.start_line))
+        });
+        results
+    }
+
+    /// Web 框架入口：带路由装饰器/注解的函数和方法，以及 Express 的路由注册调用，
+    /// 按文件路径和行号排序；识别规则和路由信息见 [`crate::ast::entrypoint::route_info`]
+    pub fn find_entrypoints(&self) -> Vec<&Symbol> {
+        let mut results: Vec<&Symbol> = self
+            .cache
+            .index
+            .values()
+            .flat_map(|file_index| file_index.symbols.iter())
+            .filter(|symbol| crate::ast::entrypoint::route_info(symbol).is_some())
+            .collect();
+
+        results.sort_by(|a, b| {
+            a.file_path
+                .cmp(&b.file_path)
+                .then(a.start_line.cmp(&b.start_line))
+        });
+        results
+    }
+
+    /// 查找索引中没有任何调用点的函数和方法（名称从未作为 MethodCall 出现）
+    ///
+    /// 这是按名称匹配的启发式结果：反射、动态分派、回调/装饰器注册、框架入口（如 `main`、
+    /// 路由处理函数、Python 的 `__init__` 等魔术方法）以及供外部调用的导出 API 都不会在索引中
+    /// 留下调用点；反之，同名函数中任一个被调用，其余同名函数也不会被报告
+    pub fn find_unused_functions(&self) -> Vec<&Symbol> {
+        let called: HashSet<&str> = self
+            .cache
+            .index
+            .values()
+            .flat_map(|file_index| file_index.symbols.iter())
+            .filter(|symbol| symbol.kind == SymbolKind::MethodCall)
+            .map(|symbol| symbol.name.as_str())
+            .collect();
+
+        let mut results: Vec<&Symbol> = self
+            .cache
+            .index
+            .values()
+            .flat_map(|file_index| file_index.symbols.iter())
+            .filter(|symbol| matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method))
+            .filter(|symbol| !called.contains(symbol.name.as_str()))
+            .collect();
+
+        results.sort_by(|a, b| {
+            a.file_path
+                .cmp(&b.file_path)
+                .then(a.start_line.cmp(&b.start_line))
+        });
+        results
+    }
+
     pub fn get_call_graph(&self, entry: &str, max_depth: usize) -> Value {
         let entry = entry.trim();
         if entry.is_empty() {
@@ -110,13 +316,17 @@ impl QueryEngine {
                                 })
                             });
 
-                            // Add edge
-                            edges.push(serde_json::json!({
+                            // Add edge（带上接收者，便于区分不同对象上的同名方法）
+                            let mut edge = serde_json::json!({
                                 "from": caller_id,
                                 "to": callee_id,
                                 "file": symbol.file_path,
                                 "line": symbol.start_line
-                            }));
+                            });
+                            if let Some(receiver) = metadata.get("receiver") {
+                                edge["receiver"] = receiver.clone();
+                            }
+                            edges.push(edge);
 
                             if !visited.contains(callee) {
                                 next_queue.push_back(callee_id);
@@ -137,6 +347,155 @@ impl QueryEngine {
         })
     }
 
+    /// 指定粒度的调用图，函数粒度与 [`QueryEngine::get_call_graph`] 相同
+    ///
+    /// 文件/模块粒度下，每条调用边的调用方取调用所在的文件，被调用方取索引中同名函数/方法
+    /// 的定义所在文件（优先同一文件，否则按路径取第一个），再按粒度映射为节点；同一对节点间
+    /// 的边合并为一条，`weight` 为其包含的调用次数。节点内部的调用不产生边，索引中没有定义的
+    /// 被调用方（库函数等）计入 `unresolved_calls`
+    pub fn get_call_graph_with_granularity(
+        &self,
+        entry: &str,
+        max_depth: usize,
+        granularity: CallGraphGranularity,
+    ) -> Value {
+        let graph = self.get_call_graph(entry, max_depth);
+        if granularity == CallGraphGranularity::Function {
+            return graph;
+        }
+
+        // 函数/方法名 -> 定义所在的文件（有序）
+        let mut definitions: HashMap<&str, Vec<&str>> = HashMap::new();
+        for symbol in self.cache.index.values().flat_map(|f| f.symbols.iter()) {
+            if matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method) {
+                definitions
+                    .entry(symbol.name.as_str())
+                    .or_default()
+                    .push(symbol.file_path.as_str());
+            }
+        }
+        for files in definitions.values_mut() {
+            files.sort_unstable();
+            files.dedup();
+        }
+        let resolve = |name: &str, near: Option<&str>| -> Option<String> {
+            let files = definitions.get(name)?;
+            near.filter(|near| files.contains(near))
+                .or_else(|| files.first().copied())
+                .map(str::to_string)
+        };
+        let unit = |file: &str| -> String {
+            match granularity {
+                CallGraphGranularity::Module => std::path::Path::new(file)
+                    .parent()
+                    .map(|dir| dir.to_string_lossy().to_string())
+                    .filter(|dir| !dir.is_empty())
+                    .unwrap_or_else(|| ".".to_string()),
+                _ => file.to_string(),
+            }
+        };
+
+        let mut nodes = BTreeSet::new();
+        let entry = graph["entry"].as_str().unwrap_or_default();
+        if let Some(file) = resolve(entry, None) {
+            nodes.insert(unit(&file));
+        }
+
+        let mut weights: BTreeMap<(String, String), usize> = BTreeMap::new();
+        let mut unresolved_calls = 0;
+        for edge in graph["edges"].as_array().into_iter().flatten() {
+            let Some(caller_file) = edge["file"].as_str() else {
+                continue;
+            };
+            let callee = edge["to"].as_str().unwrap_or_default();
+            let Some(callee_file) = resolve(callee, Some(caller_file)) else {
+                unresolved_calls += 1;
+                continue;
+            };
+
+            let (from, to) = (unit(caller_file), unit(&callee_file));
+            nodes.insert(from.clone());
+            nodes.insert(to.clone());
+            if from != to {
+                *weights.entry((from, to)).or_default() += 1;
+            }
+        }
+
+        let kind = granularity.as_str();
+        let nodes: Vec<Value> = nodes
+            .into_iter()
+            .map(|id| serde_json::json!({ "id": id, "label": id, "type": kind }))
+            .collect();
+        let edges: Vec<Value> = weights
+            .into_iter()
+            .map(|((from, to), weight)| serde_json::json!({ "from": from, "to": to, "weight": weight }))
+            .collect();
+
+        serde_json::json!({
+            "entry": entry,
+            "granularity": kind,
+            "nodes": nodes,
+            "edges": edges,
+            "unresolved_calls": unresolved_calls
+        })
+    }
+
+    /// 由 Import 符号构建的文件 -> 模块依赖图；`filter` 为模块名子串（不区分大小写），
+    /// 设置后只保留导入了匹配模块的边。同一文件多次导入同一模块只保留第一处
+    pub fn get_dependency_graph(&self, filter: Option<&str>) -> Value {
+        let filter = filter
+            .map(|f| f.trim().to_lowercase())
+            .filter(|f| !f.is_empty());
+
+        // (文件, 模块) -> (行号, 导入的成员)，有序以保证输出稳定
+        let mut imports: BTreeMap<(&str, &str), (u32, Vec<Value>)> = BTreeMap::new();
+        for file_index in self.cache.index.values() {
+            for symbol in file_index.symbols.iter().filter(|s| s.kind == SymbolKind::Import) {
+                if filter
+                    .as_ref()
+                    .is_some_and(|f| !symbol.name.to_lowercase().contains(f.as_str()))
+                {
+                    continue;
+                }
+                let imported = symbol
+                    .metadata
+                    .get("imported")
+                    .and_then(|v| v.as_array())
+                    .cloned()
+                    .unwrap_or_default();
+                imports
+                    .entry((symbol.file_path.as_str(), symbol.name.as_str()))
+                    .or_insert((symbol.start_line, imported));
+            }
+        }
+
+        let mut files = BTreeMap::new();
+        let mut modules = BTreeMap::new();
+        let mut edges = Vec::new();
+        for ((file, module), (line, imported)) in imports {
+            let file_id = format!("file:{}", file);
+            let module_id = format!("module:{}", module);
+            files.entry(file).or_insert_with(|| {
+                serde_json::json!({ "id": file_id, "label": file, "type": "file" })
+            });
+            modules.entry(module).or_insert_with(|| {
+                serde_json::json!({ "id": module_id, "label": module, "type": "module" })
+            });
+            edges.push(serde_json::json!({
+                "from": file_id,
+                "to": module_id,
+                "line": line,
+                "imported": imported
+            }));
+        }
+
+        let nodes: Vec<Value> = files.into_values().chain(modules.into_values()).collect();
+        serde_json::json!({
+            "nodes": nodes,
+            "edges": edges
+        })
+    }
+
     pub fn get_class_hierarchy(&self, class_name: &str) -> Value {
         // Find the class symbol
         let target_symbol = self.find_class_symbol(class_name);
@@ -211,6 +570,137 @@ impl QueryEngine {
         })
     }
 
+    /// 重命名 `name` 前的影响分析（只读，不修改索引）：
+    ///
+    /// - `definitions`：同名的定义，方法附带所属类型 `owner`，覆盖了祖先类型中同名方法时
+    ///   `overrides` 为该祖先类型
+    /// - `call_sites`：按名称匹配的全部调用点及其所在的函数/方法
+    /// - `potential_conflicts`：已经叫 `new_name` 的定义，重命名后可能与之冲突
+    ///
+    /// 与调用图相同，这里按名称匹配，无法区分同名的不同函数
+    pub fn rename_impact(&self, name: &str, new_name: Option<&str>) -> Value {
+        let name = name.trim();
+        let new_name = new_name.map(str::trim).filter(|n| !n.is_empty() && *n != name);
+        let definitions_named = |target: &str| {
+            let mut found: Vec<&Symbol> = self
+                .cache
+                .index
+                .values()
+                .flat_map(|file_index| file_index.symbols.iter())
+                .filter(|s| !matches!(s.kind, SymbolKind::MethodCall | SymbolKind::Import))
+                .filter(|s| s.name == target)
+                .collect();
+            found.sort_by(|a, b| {
+                a.file_path
+                    .cmp(&b.file_path)
+                    .then(a.start_line.cmp(&b.start_line))
+            });
+            found
+        };
+
+        let definitions = if name.is_empty() { Vec::new() } else { definitions_named(name) };
+        let owners: Vec<Option<&Symbol>> = definitions.iter().map(|d| self.owning_type(d)).collect();
+        let owner_names: HashSet<&str> = owners.iter().flatten().map(|o| o.name.as_str()).collect();
+        let mut types: HashMap<&str, Vec<&Symbol>> = HashMap::new();
+        if !owner_names.is_empty() {
+            for symbol in self.cache.index.values().flat_map(|f| f.symbols.iter()) {
+                if is_type_kind(&symbol.kind) {
+                    types.entry(symbol.name.as_str()).or_default().push(symbol);
+                }
+            }
+        }
+
+        let definitions: Vec<Value> = definitions
+            .iter()
+            .zip(&owners)
+            .map(|(definition, owner)| {
+                // 沿 parent_classes 向上查找，最近的一个也定义了同名方法的祖先类型
+                let overrides = owner.and_then(|owner| {
+                    let mut queue: VecDeque<&Symbol> = VecDeque::from([owner]);
+                    let mut visited: HashSet<&str> = HashSet::from([owner.name.as_str()]);
+                    while let Some(current) = queue.pop_front() {
+                        for parent in &current.parent_classes {
+                            let parent = simple_type_name(parent);
+                            if parent.is_empty() || !visited.insert(parent) {
+                                continue;
+                            }
+                            if owner_names.contains(parent) {
+                                return Some(parent);
+                            }
+                            queue.extend(types.get(parent).into_iter().flatten());
+                        }
+                    }
+                    None
+                });
+                serde_json::json!({
+                    "name": definition.name,
+                    "kind": definition.kind_to_string(),
+                    "file": definition.file_path,
+                    "line": definition.start_line,
+                    "end_line": definition.end_line,
+                    "owner": owner.map(|o| o.name.as_str()),
+                    "overrides": overrides
+                })
+            })
+            .collect();
+
+        let mut call_sites = self.find_call_sites(name);
+        call_sites.sort_by(|a, b| {
+            a.file_path
+                .cmp(&b.file_path)
+                .then(a.start_line.cmp(&b.start_line))
+        });
+        let call_sites: Vec<Value> = call_sites
+            .into_iter()
+            .map(|call| {
+                let caller = self
+                    .symbol_at_line(&call.file_path, call.start_line)
+                    .map(|s| s.name.as_str());
+                serde_json::json!({
+                    "file": call.file_path,
+                    "line": call.start_line,
+                    "caller": caller,
+                    "receiver": call.metadata.get("receiver")
+                })
+            })
+            .collect();
+
+        let potential_conflicts: Vec<Value> = new_name
+            .map(definitions_named)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|symbol| {
+                serde_json::json!({
+                    "name": symbol.name,
+                    "kind": symbol.kind_to_string(),
+                    "file": symbol.file_path,
+                    "line": symbol.start_line,
+                    "owner": self.owning_type(symbol).map(|o| o.name.as_str())
+                })
+            })
+            .collect();
+
+        serde_json::json!({
+            "name": name,
+            "new_name": new_name,
+            "definitions": definitions,
+            "call_sites": call_sites,
+            "potential_conflicts": potential_conflicts
+        })
+    }
+
+    /// 包含该符号的最内层类型定义（类、接口、结构体、枚举），用于确定方法所属的类型
+    fn owning_type(&self, symbol: &Symbol) -> Option<&Symbol> {
+        self.cache
+            .index
+            .get(&symbol.file_path)?
+            .symbols
+            .iter()
+            .filter(|s| is_type_kind(&s.kind) && !std::ptr::eq(*s, symbol))
+            .filter(|s| s.start_line <= symbol.start_line && symbol.start_line <= s.end_line)
+            .min_by_key(|s| s.end_line - s.start_line)
+    }
+
     pub fn get_file_structure(&self, file_path: &str) -> Vec<&Symbol> {
         if let Some(file_index) = self.cache.index.get(file_path) {
             file_index.symbols.iter().collect()
@@ -219,11 +709,33 @@ impl QueryEngine {
         }
     }
 
+    /// 每个文件中的符号数量（file_path -> count）
+    pub fn file_symbol_counts(&self) -> HashMap<String, usize> {
+        self.cache
+            .index
+            .iter()
+            .map(|(file_path, data)| (file_path.clone(), data.symbols.len()))
+            .collect()
+    }
+
+    /// 记录了解析状况的文件：file_path -> ParseHealth
+    pub fn file_parse_health(&self) -> HashMap<String, ParseHealth> {
+        self.cache
+            .index
+            .iter()
+            .filter_map(|(file_path, data)| data.parse_health.map(|health| (file_path.clone(), health)))
+            .collect()
+    }
+
     pub fn get_statistics(&self) -> Value {
         let mut total_nodes = 0;
         let mut type_counts = HashMap::new();
+        let mut parse_health_counts: HashMap<&str, usize> = HashMap::new();
 
         for file_data in self.cache.index.values() {
+            if let Some(health) = file_data.parse_health {
+                *parse_health_counts.entry(health.as_str()).or_insert(0) += 1;
+            }
             total_nodes += file_data.symbols.len();
             for symbol in &file_data.symbols {
                 let display_kind = match symbol.kind {
@@ -233,6 +745,8 @@ impl QueryEngine {
                     crate::ast::symbol::SymbolKind::Method => "Method".to_string(),
                     crate::ast::symbol::SymbolKind::MethodCall => "MethodCall".to_string(),
                     crate::ast::symbol::SymbolKind::Struct => "Struct".to_string(),
+                    crate::ast::symbol::SymbolKind::Enum => "Enum".to_string(),
+                    crate::ast::symbol::SymbolKind::Import => "Import".to_string(),
                 };
 
                 *type_counts.entry(display_kind).or_insert(0) += 1;
@@ -241,27 +755,42 @@ impl QueryEngine {
 
         serde_json::json!({
             "total_nodes": total_nodes,
-            "type_counts": type_counts
+            "type_counts": type_counts,
+            "parse_health_counts": parse_health_counts
         })
     }
 
-    pub fn generate_report(&self, repository_path: &str) -> Value {
-        let mut nodes = serde_json::Map::new();
-
-        for (_file_path, data) in &self.cache.index {
-            for symbol in &data.symbols {
-                let symbol_dict = symbol.to_dict();
-                if let Some(id) = symbol_dict.get("id").and_then(|v| v.as_str()) {
-                    nodes.insert(id.to_string(), symbol_dict);
+    pub fn generate_report(&self, repository_path: &str, options: &ReportOptions) -> Value {
+        let mut selected = Vec::new();
+        for data in self.cache.index.values() {
+            let ids = crate::ast::symbol::stable_ids(&data.symbols);
+            for (symbol, id) in data.symbols.iter().zip(ids) {
+                if options.includes(&symbol.kind) {
+                    selected.push((id, symbol));
                 }
             }
         }
 
+        // 按 ID 排序后截断，保证同一索引多次生成的报告一致
+        selected.sort_by(|a, b| a.0.cmp(&b.0));
+        let total_nodes = selected.len();
+        if let Some(max_nodes) = options.max_nodes {
+            selected.truncate(max_nodes);
+        }
+
+        let mut nodes = serde_json::Map::new();
+        for (id, symbol) in selected {
+            let symbol_dict = symbol.to_dict_with_id(&id);
+            nodes.insert(id, symbol_dict);
+        }
+
         serde_json::json!({
             "metadata": {
                 "build_time": chrono::Utc::now().to_rfc3339(),
                 "cache_version": "1.0",
                 "node_count": nodes.len(),
+                "total_nodes": total_nodes,
+                "truncated": nodes.len() < total_nodes,
                 "repository_path": repository_path
             },
             "nodes": nodes
@@ -287,6 +816,34 @@ impl QueryEngine {
         }
     }
 
+    /// 根据各类型的 parent_classes 反向填充父类型的 subclasses（按名称匹配，限定名取最后一段）
+    pub fn populate_subclasses(&mut self) {
+        let mut children: HashMap<String, Vec<String>> = HashMap::new();
+        for data in self.cache.index.values() {
+            for symbol in data.symbols.iter().filter(|s| is_type_kind(&s.kind)) {
+                for parent in &symbol.parent_classes {
+                    let parent = simple_type_name(parent);
+                    if !parent.is_empty() && parent != symbol.name {
+                        children
+                            .entry(parent.to_string())
+                            .or_default()
+                            .push(symbol.name.clone());
+                    }
+                }
+            }
+        }
+        for names in children.values_mut() {
+            names.sort();
+            names.dedup();
+        }
+
+        for data in self.cache.index.values_mut() {
+            for symbol in data.symbols.iter_mut().filter(|s| is_type_kind(&s.kind)) {
+                symbol.subclasses = children.get(&symbol.name).cloned().unwrap_or_default();
+            }
+        }
+    }
+
     pub fn rebuild_class_map(&mut self) {
         self.cache.class_map.clear();
 
@@ -301,3 +858,456 @@ impl QueryEngine {
         }
     }
 }
+
+/// 去掉关键字、泛型参数和限定前缀，如 `extends java.util.List<T>` -> `List`
+fn simple_type_name(parent: &str) -> &str {
+    let parent = parent.split('<').next().unwrap_or(parent);
+    let parent = parent.split_whitespace().last().unwrap_or("");
+    parent.rsplit(['.', ':']).next().unwrap_or(parent)
+}
+
+fn is_type_kind(kind: &SymbolKind) -> bool {
+    matches!(
+        kind,
+        SymbolKind::Class | SymbolKind::Interface | SymbolKind::Struct | SymbolKind::Enum
+    )
+}
+
+/// 符号清单差异中的一项
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolRef {
+    pub qualified_name: String,
+    pub kind: String,
+    pub file_path: String,
+    pub line: u32,
+}
+
+/// 两份索引之间的符号清单差异（按全限定名比较，忽略方法调用）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SymbolDiff {
+    pub only_in_a: Vec<SymbolRef>,
+    pub only_in_b: Vec<SymbolRef>,
+}
+
+/// 比较两份索引中的函数/类等定义，返回各自独有的符号
+pub fn diff_symbols(a: &CacheData, b: &CacheData) -> SymbolDiff {
+    let defs_a = collect_definitions(a);
+    let defs_b = collect_definitions(b);
+
+    let only = |from: &BTreeMap<String, SymbolRef>, other: &BTreeMap<String, SymbolRef>| {
+        from.iter()
+            .filter(|(key, _)| !other.contains_key(*key))
+            .map(|(_, symbol)| symbol.clone())
+            .collect()
+    };
+
+    SymbolDiff {
+        only_in_a: only(&defs_a, &defs_b),
+        only_in_b: only(&defs_b, &defs_a),
+    }
+}
+
+/// 以 "kind:全限定名" 为键收集定义类符号，键有序以保证输出稳定
+fn collect_definitions(cache: &CacheData) -> BTreeMap<String, SymbolRef> {
+    let mut definitions = BTreeMap::new();
+    for file_index in cache.index.values() {
+        for symbol in &file_index.symbols {
+            if matches!(symbol.kind, SymbolKind::MethodCall | SymbolKind::Import) {
+                continue;
+            }
+
+            let qualified_name = symbol.qualified_name();
+            let kind = symbol.kind_to_string();
+            definitions
+                .entry(format!("{}:{}", kind, qualified_name))
+                .or_insert_with(|| SymbolRef {
+                    qualified_name,
+                    kind,
+                    file_path: symbol.file_path.clone(),
+                    line: symbol.start_line,
+                });
+        }
+    }
+    definitions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::cache::FileIndex;
+    use crate::ast::ASTParser;
+
+    /// 解析内存中的文件得到索引数据（文件路径即索引中的键）
+    fn cache_data(files: &[(&str, &str)]) -> CacheData {
+        let mut parser = ASTParser::new();
+        let index = files
+            .iter()
+            .map(|(path, content)| {
+                let symbols = parser.parse_file(std::path::Path::new(path), content).unwrap();
+                let file_index = FileIndex {
+                    mtime: 0,
+                    symbols,
+                    parse_health: None,
+                };
+                (path.to_string(), file_index)
+            })
+            .collect();
+        CacheData {
+            index,
+            class_map: HashMap::new(),
+            build_time: String::new(),
+        }
+    }
+
+    /// 解析内存中的文件并建立索引
+    fn engine(files: &[(&str, &str)]) -> QueryEngine {
+        let mut engine = QueryEngine::new(cache_data(files));
+        engine.rebuild_class_map();
+        engine.populate_subclasses();
+        engine
+    }
+
+    #[test]
+    fn counts_symbols_per_file() {
+        let engine = engine(&[
+            ("app.py", "def first():\n    pass\n\ndef second():\n    first()\n"),
+            ("lib.py", "class Store:\n    pass\n"),
+        ]);
+        let counts = engine.file_symbol_counts();
+        assert_eq!(counts.len(), 2);
+        // 两个函数和一个调用
+        assert_eq!(counts["app.py"], 3);
+        assert_eq!(counts["lib.py"], 1);
+    }
+
+    #[test]
+    fn list_by_kind_returns_every_class_sorted() {
+        let engine = engine(&[
+            ("b.py", "class Beta:\n    def run(self):\n        pass\n"),
+            ("a.py", "class Alpha:\n    pass\n\nclass Gamma(Alpha):\n    pass\n\ndef helper():\n    pass\n"),
+        ]);
+        let classes: Vec<(&str, &str)> = engine
+            .list_by_kind(SymbolKind::Class, None)
+            .iter()
+            .map(|s| (s.file_path.as_str(), s.name.as_str()))
+            .collect();
+        assert_eq!(classes, vec![("a.py", "Alpha"), ("a.py", "Gamma"), ("b.py", "Beta")]);
+        let functions: Vec<&str> = engine
+            .list_by_kind(SymbolKind::Function, None)
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(functions, vec!["helper"]);
+    }
+
+    #[test]
+    fn list_by_kind_filters_by_visibility() {
+        let engine = engine(&[
+            ("app.py", "def run():\n    pass\n\ndef _helper():\n    pass\n"),
+            ("lib.rs", "pub fn exported() {}\nfn hidden() {}\n"),
+        ]);
+        let names = |visibility| {
+            engine
+                .list_by_kind(SymbolKind::Function, visibility)
+                .iter()
+                .map(|s| s.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(Some("public")), vec!["run", "exported"]);
+        assert_eq!(names(Some("private")), vec!["hidden"]);
+        assert_eq!(names(None).len(), 4);
+    }
+
+    #[test]
+    fn diff_symbols_reports_definitions_missing_on_either_side() {
+        let before = cache_data(&[(
+            "store.py",
+            "class Store:\n    def save(self):\n        pass\n\n    def load(self):\n        helper()\n\ndef helper():\n    pass\n",
+        )]);
+        // 移动文件不算差异，调用不参与比较
+        let after = cache_data(&[
+            ("store.py", "class Store:\n    def save(self):\n        pass\n"),
+            ("util.py", "def helper():\n    pass\n\ndef extra():\n    helper()\n"),
+        ]);
+
+        let diff = diff_symbols(&before, &after);
+        let names = |refs: &[SymbolRef]| {
+            refs.iter()
+                .map(|r| (r.kind.clone(), r.qualified_name.clone(), r.file_path.clone(), r.line))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(&diff.only_in_a),
+            [("method".to_string(), "Store.load".to_string(), "store.py".to_string(), 5)]
+        );
+        assert_eq!(
+            names(&diff.only_in_b),
+            [("function".to_string(), "extra".to_string(), "util.py".to_string(), 4)]
+        );
+        assert!(diff_symbols(&before, &before).only_in_a.is_empty());
+    }
+
+    #[test]
+    fn fuzzy_search_ranks_near_misses_best_first() {
+        let engine = engine(&[(
+            "auth.py",
+            "def authenticate(user):\n    pass\n\ndef authorize(user):\n    pass\n\ndef load_config():\n    pass\n",
+        )]);
+        let names = |query: &str| {
+            engine
+                .fuzzy_search(query, 3)
+                .into_iter()
+                .map(|(symbol, _)| symbol.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        // 拼写错误的查询仍能把目标排在第一位，明显无关的符号不返回
+        assert_eq!(names("authentcate"), ["authenticate"]);
+        assert_eq!(names("autenticate")[0], "authenticate");
+        assert_eq!(names("auth"), ["authorize", "authenticate"]);
+        assert!(names("xyz").is_empty());
+
+        let results = engine.fuzzy_search("authenticate", 10);
+        assert_eq!(results[0].0.name, "authenticate");
+        assert!(results[0].1 > 0.8);
+        assert!(results.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        assert_eq!(engine.fuzzy_search("auth", 1).len(), 1);
+    }
+
+    #[test]
+    fn report_respects_node_limit_and_kind_filter() {
+        let engine = engine(&[
+            ("a.py", "class A:\n    def run(self):\n        helper()\n"),
+            ("b.py", "def helper():\n    pass\n\ndef other():\n    helper()\n"),
+        ]);
+        let node_ids = |report: &Value| report["nodes"].as_object().unwrap().keys().cloned().collect::<Vec<_>>();
+
+        let full = engine.generate_report("/repo", &ReportOptions::default());
+        assert_eq!(full["metadata"]["total_nodes"], 6);
+        assert_eq!(full["metadata"]["truncated"], false);
+
+        let limited = engine.generate_report("/repo", &ReportOptions { max_nodes: Some(2), kinds: None });
+        assert_eq!(limited["metadata"]["node_count"], 2);
+        assert_eq!(limited["metadata"]["total_nodes"], 6);
+        assert_eq!(limited["metadata"]["truncated"], true);
+        // 截断按节点 ID 排序，结果稳定
+        assert_eq!(node_ids(&limited), node_ids(&full)[..2]);
+
+        let definitions = engine.generate_report(
+            "/repo",
+            &ReportOptions {
+                max_nodes: None,
+                kinds: Some(vec![SymbolKind::Class, SymbolKind::Function]),
+            },
+        );
+        assert_eq!(definitions["metadata"]["total_nodes"], 3);
+        assert_eq!(definitions["metadata"]["truncated"], false);
+        let mut types: Vec<&str> = definitions["nodes"]
+            .as_object()
+            .unwrap()
+            .values()
+            .map(|node| node["type"].as_str().unwrap())
+            .collect();
+        types.sort();
+        assert_eq!(types, ["Class", "Function", "Function"]);
+    }
+
+    #[test]
+    fn symbol_at_line_returns_innermost_definition() {
+        let engine = engine(&[(
+            "store.py",
+            "import os\n\nclass Store:\n    limit = 10\n\n    def save(self):\n        os.system(cmd)\n\ndef main():\n    pass\n",
+        )]);
+        let at = |line: u32| engine.symbol_at_line("store.py", line).map(|s| (s.name.clone(), s.kind_to_string()));
+
+        assert_eq!(at(7), Some(("save".to_string(), "method".to_string())));
+        assert_eq!(at(4), Some(("Store".to_string(), "class".to_string())));
+        assert_eq!(at(10), Some(("main".to_string(), "function".to_string())));
+        assert_eq!(at(1), None);
+        assert!(engine.symbol_at_line("missing.py", 7).is_none());
+    }
+
+    #[test]
+    fn unused_functions_are_those_never_called() {
+        let engine = engine(&[
+            ("app.py", "def main():\n    helper()\n\ndef helper():\n    pass\n\ndef orphan():\n    pass\n"),
+            ("store.py", "class Store:\n    def save(self):\n        pass\n\n    def unused(self):\n        pass\n\ndef run(store):\n    store.save()\n"),
+        ]);
+        let unused: Vec<(&str, &str)> = engine
+            .find_unused_functions()
+            .into_iter()
+            .map(|s| (s.file_path.as_str(), s.name.as_str()))
+            .collect();
+        assert_eq!(
+            unused,
+            vec![("app.py", "main"), ("app.py", "orphan"), ("store.py", "unused"), ("store.py", "run")]
+        );
+    }
+
+    #[test]
+    fn dependency_graph_has_an_edge_per_imported_module() {
+        let engine = engine(&[
+            ("app.py", "import pickle\nfrom flask import request\nimport pickle\n"),
+            ("util.py", "import os\n"),
+        ]);
+        let edges = |filter: Option<&str>| {
+            let graph = engine.get_dependency_graph(filter);
+            graph["edges"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|e| (e["from"].as_str().unwrap().to_string(), e["to"].as_str().unwrap().to_string()))
+                .collect::<Vec<_>>()
+        };
+        let edge = |file: &str, module: &str| (format!("file:{}", file), format!("module:{}", module));
+
+        // 同一文件重复导入同一模块只算一条边
+        assert_eq!(
+            edges(None),
+            vec![edge("app.py", "flask"), edge("app.py", "pickle"), edge("util.py", "os")]
+        );
+        assert_eq!(edges(Some("PICK")), vec![edge("app.py", "pickle")]);
+        let graph = engine.get_dependency_graph(Some("pickle"));
+        let nodes: Vec<&str> = graph["nodes"].as_array().unwrap().iter().map(|n| n["id"].as_str().unwrap()).collect();
+        assert_eq!(nodes, ["file:app.py", "module:pickle"]);
+        assert_eq!(graph["edges"][0]["line"], 1);
+    }
+
+    #[test]
+    fn rename_impact_reports_call_sites_overrides_and_conflicts() {
+        let engine = engine(&[
+            (
+                "store.py",
+                "class Store:\n    def save(self):\n        pass\n\n    def persist(self):\n        pass\n\ndef run(store):\n    store.save()\n",
+            ),
+            ("jobs.py", "def nightly(store):\n    store.save()\n\ndef persist():\n    pass\n"),
+            ("models.rb", "class Base\n  def save\n  end\nend\n\nclass User < Base\n  def save\n  end\nend\n"),
+        ]);
+
+        let impact = engine.rename_impact("save", Some("persist"));
+        let definitions: Vec<(&str, u64, &str, Option<&str>)> = impact["definitions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|d| {
+                (
+                    d["file"].as_str().unwrap(),
+                    d["line"].as_u64().unwrap(),
+                    d["owner"].as_str().unwrap(),
+                    d["overrides"].as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            definitions,
+            [("models.rb", 2, "Base", None), ("models.rb", 7, "User", Some("Base")), ("store.py", 2, "Store", None)]
+        );
+
+        let call_sites: Vec<(&str, u64, &str)> = impact["call_sites"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| (c["file"].as_str().unwrap(), c["line"].as_u64().unwrap(), c["caller"].as_str().unwrap()))
+            .collect();
+        assert_eq!(call_sites, [("jobs.py", 2, "nightly"), ("store.py", 9, "run")]);
+
+        let conflicts: Vec<(&str, Option<&str>)> = impact["potential_conflicts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| (c["file"].as_str().unwrap(), c["owner"].as_str()))
+            .collect();
+        assert_eq!(conflicts, [("jobs.py", None), ("store.py", Some("Store"))]);
+
+        // 新名称与原名称相同或未指定时不报告冲突
+        let unchanged = engine.rename_impact("save", Some("save"));
+        assert!(unchanged["new_name"].is_null());
+        assert!(unchanged["potential_conflicts"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn call_graph_collapses_calls_into_weighted_file_and_module_edges() {
+        let engine = engine(&[
+            ("app/main.py", "def main():\n    load()\n    save()\n    print(\"done\")\n"),
+            ("lib/util.py", "def load():\n    pass\n\ndef save():\n    connect()\n"),
+            ("lib/db.py", "def connect():\n    pass\n"),
+        ]);
+        let edges = |graph: &Value| {
+            graph["edges"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|e| (e["from"].as_str().unwrap().to_string(), e["to"].as_str().unwrap().to_string(), e["weight"].as_u64().unwrap()))
+                .collect::<Vec<_>>()
+        };
+        let edge = |from: &str, to: &str, weight: u64| (from.to_string(), to.to_string(), weight);
+
+        let files = engine.get_call_graph_with_granularity("main", 5, CallGraphGranularity::File);
+        assert_eq!(files["granularity"], "file");
+        // main 对 util.py 的两次调用合并为一条权重为 2 的边
+        assert_eq!(
+            edges(&files),
+            vec![edge("app/main.py", "lib/util.py", 2), edge("lib/util.py", "lib/db.py", 1)]
+        );
+        assert_eq!(files["nodes"].as_array().unwrap().len(), 3);
+        assert_eq!(files["unresolved_calls"], 1);
+
+        // 同一模块内的调用不产生边
+        let modules = engine.get_call_graph_with_granularity("main", 5, CallGraphGranularity::Module);
+        assert_eq!(edges(&modules), vec![edge("app", "lib", 2)]);
+
+        let functions = engine.get_call_graph_with_granularity("main", 5, CallGraphGranularity::Function);
+        assert_eq!(functions["edges"], engine.get_call_graph("main", 5)["edges"]);
+        assert!(functions.get("granularity").is_none());
+    }
+
+    #[test]
+    fn route_handlers_are_found_by_decorator_pattern() {
+        let engine = engine(&[
+            (
+                "views.py",
+                "@app.route(\"/\")\ndef index():\n    pass\n\n@cache\ndef stats():\n    pass\n\n@router.post(\"/items\")\nasync def create_item():\n    pass\n",
+            ),
+            (
+                "Api.java",
+                "class Api {\n    @PostMapping(\"/orders\")\n    public void order() {}\n\n    @Override\n    public String toString() { return \"\"; }\n}\n",
+            ),
+        ]);
+        let handlers = |pattern: &str| -> Vec<String> {
+            engine
+                .find_route_handlers(&regex::Regex::new(pattern).unwrap())
+                .into_iter()
+                .map(|s| format!("{}:{}", s.file_path, s.name))
+                .collect()
+        };
+
+        assert_eq!(
+            handlers(DEFAULT_ROUTE_DECORATOR_PATTERN),
+            ["Api.java:order", "views.py:index", "views.py:create_item"]
+        );
+        assert_eq!(handlers("^cache$"), ["views.py:stats"]);
+    }
+
+    #[test]
+    fn entrypoints_include_flask_routes_and_skip_plain_functions() {
+        let engine = engine(&[
+            ("app.py", "@app.route(\"/login\", methods=[\"POST\"])\ndef login():\n    check()\n\ndef check():\n    pass\n"),
+            ("server.js", "const env = app.get('env');\napp.get('/status', status);\n"),
+        ]);
+        let entrypoints: Vec<(&str, &str, u32, Option<String>)> = engine
+            .find_entrypoints()
+            .into_iter()
+            .map(|s| {
+                let path = crate::ast::entrypoint::route_info(s).and_then(|route| route.path);
+                (s.file_path.as_str(), s.name.as_str(), s.start_line, path)
+            })
+            .collect();
+        assert_eq!(
+            entrypoints,
+            [
+                ("app.py", "login", 2, Some("/login".to_string())),
+                ("server.js", "get", 2, Some("/status".to_string())),
+            ]
+        );
+    }
+}
@@ -14,11 +14,12 @@ pub struct Symbol {
     pub package: String,
     pub modifiers: Vec<String>,
     pub fields: Vec<Field>,
+    #[serde(with = "metadata_serde")]
     pub metadata: HashMap<String, serde_json::Value>,
-    pub subclasses: Vec<String>, // Populated post-analysis
+    pub subclasses: Vec<String>, // Populated post-analysis (QueryEngine::populate_subclasses)
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SymbolKind {
     Class,
     Function,
@@ -26,6 +27,28 @@ pub enum SymbolKind {
     MethodCall,
     Interface,
     Struct,
+    Enum,
+    /// 导入语句（import / require / use），名称为导入的模块路径
+    Import,
+}
+
+impl std::str::FromStr for SymbolKind {
+    type Err = String;
+
+    /// 解析 `kind_to_string` 输出的小写名称（如 "class"、"method_call"）
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "class" => Ok(SymbolKind::Class),
+            "function" => Ok(SymbolKind::Function),
+            "method" => Ok(SymbolKind::Method),
+            "method_call" | "methodcall" => Ok(SymbolKind::MethodCall),
+            "interface" => Ok(SymbolKind::Interface),
+            "struct" => Ok(SymbolKind::Struct),
+            "enum" => Ok(SymbolKind::Enum),
+            "import" => Ok(SymbolKind::Import),
+            _ => Err(format!("Unknown symbol kind: {}", s)),
+        }
+    }
 }
 
 impl Symbol {
@@ -85,24 +108,108 @@ impl Symbol {
         self
     }
 
-    pub fn to_dict(&self) -> serde_json::Value {
-        // Determine language from file extension
+    /// 根据文件扩展名判断符号所属语言
+    pub fn language(&self) -> &'static str {
         let ext = std::path::Path::new(&self.file_path)
             .extension()
             .and_then(|s| s.to_str())
             .unwrap_or("")
             .to_lowercase();
 
-        let language = match ext.as_str() {
+        match ext.as_str() {
             "rs" => "rust",
             "py" => "python",
-            "js" => "javascript",
+            "js" | "jsx" => "javascript",
             "ts" | "tsx" => "typescript",
-            _ => &ext,
-        };
+            "java" => "java",
+            "go" => "go",
+            "c" | "h" => "c",
+            "cpp" | "hpp" | "cc" => "cpp",
+            "php" => "php",
+            "rb" => "ruby",
+            "html" | "htm" => "html",
+            "vue" => "vue",
+            "css" => "css",
+            "json" => "json",
+            _ => "unknown",
+        }
+    }
+
+    /// 符号的可见性：public / private / protected / internal
+    ///
+    /// 优先使用解析得到的修饰符；没有修饰符时按语言默认值推断，无法判断时返回 None
+    pub fn visibility(&self) -> Option<&'static str> {
+        for modifier in &self.modifiers {
+            match modifier.as_str() {
+                "public" | "pub" => return Some("public"),
+                "private" => return Some("private"),
+                "protected" => return Some("protected"),
+                m if m.starts_with("pub(") => return Some("internal"),
+                _ => {}
+            }
+        }
+
+        match self.language() {
+            "rust" => Some("private"),
+            "java" => Some("internal"),
+            "javascript" | "typescript" => Some("public"),
+            _ => None,
+        }
+    }
+
+    /// 函数/方法上的装饰器（Python）或注解（Java），不含 `@`，按书写顺序排列
+    pub fn decorators(&self) -> Vec<&str> {
+        self.metadata
+            .get("decorators")
+            .and_then(|v| v.as_array())
+            .map(|values| values.iter().filter_map(|v| v.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    /// 全限定名：包名 + 所属类 + 符号名，以 `.` 连接
+    ///
+    /// 所属类取 `ownerClass`；JavaScript/TypeScript 的方法只记录了 `callerClass`，方法缺少
+    /// `ownerClass` 时改用它（方法调用的 `callerClass` 是调用方所在的类，不作为所属类）
+    pub fn qualified_name(&self) -> String {
+        let owner = self
+            .metadata
+            .get("ownerClass")
+            .or_else(|| {
+                self.metadata
+                    .get("callerClass")
+                    .filter(|_| self.kind == SymbolKind::Method)
+            })
+            .and_then(|v| v.as_str())
+            .filter(|owner| !owner.is_empty());
+
+        [Some(self.package.as_str()).filter(|p| !p.is_empty()), owner, Some(self.name.as_str())]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    /// 与行号无关的符号 ID：`file_path:qualified_name:kind`
+    ///
+    /// 编辑导致行号变化时 ID 保持不变，适合持久化书签和批注；代价是同一文件中
+    /// 同名同类的符号（如重载）会得到相同的基础 ID，需要通过 [`stable_ids`] 追加序号区分，
+    /// 而序号依赖它们在文件中的先后顺序，调整重载顺序会使 ID 互换。
+    pub fn stable_id(&self) -> String {
+        format!("{}:{}:{}", self.file_path, self.qualified_name(), self.kind_to_string())
+    }
+
+    /// 基于行号的位置 ID：`file_path:name:start_line`
+    pub fn position_id(&self) -> String {
+        format!("{}:{}:{}", self.file_path, self.name, self.start_line)
+    }
+
+    pub fn to_dict(&self) -> serde_json::Value {
+        self.to_dict_with_id(&self.stable_id())
+    }
 
-        // Generate ID
-        let node_id = format!("{}:{}:{}", self.file_path, self.name, self.start_line);
+    /// 使用指定 ID 生成字典（同一文件内的重载需传入 [`stable_ids`] 的结果）
+    pub fn to_dict_with_id(&self, node_id: &str) -> serde_json::Value {
+        let language = self.language();
 
         let node_type = match self.kind {
             SymbolKind::MethodCall => "MethodCall".to_string(),
@@ -111,10 +218,15 @@ impl Symbol {
             SymbolKind::Class => "Class".to_string(),
             SymbolKind::Interface => "Interface".to_string(),
             SymbolKind::Struct => "Struct".to_string(),
+            SymbolKind::Enum => "Enum".to_string(),
+            SymbolKind::Import => "Import".to_string(),
         };
 
         let mut meta = serde_json::Map::new();
-        if matches!(self.kind, SymbolKind::Class | SymbolKind::Interface) {
+        if matches!(
+            self.kind,
+            SymbolKind::Class | SymbolKind::Interface | SymbolKind::Struct | SymbolKind::Enum
+        ) {
             meta.insert(
                 "superClasses".to_string(),
                 serde_json::Value::String(self.parent_classes.join(", ")),
@@ -127,6 +239,7 @@ impl Symbol {
 
         serde_json::json!({
             "id": node_id,
+            "position_id": self.position_id(),
             "language": language,
             "type": node_type,
             "name": self.name,
@@ -159,10 +272,30 @@ impl Symbol {
             SymbolKind::MethodCall => "method_call".to_string(),
             SymbolKind::Interface => "interface".to_string(),
             SymbolKind::Struct => "struct".to_string(),
+            SymbolKind::Enum => "enum".to_string(),
+            SymbolKind::Import => "import".to_string(),
         }
     }
 }
 
+/// 为同一文件中的符号计算稳定 ID，基础 ID 重复时（重载）按出现顺序追加 `#2`、`#3`…
+pub fn stable_ids(symbols: &[Symbol]) -> Vec<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    symbols
+        .iter()
+        .map(|symbol| {
+            let base = symbol.stable_id();
+            let count = seen.entry(base.clone()).or_insert(0);
+            *count += 1;
+            if *count == 1 {
+                base
+            } else {
+                format!("{}#{}", base, count)
+            }
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Field {
     pub name: String,
@@ -170,5 +303,88 @@ pub struct Field {
     pub start_line: u32,
     pub end_line: u32,
     pub modifiers: Vec<String>,
+    #[serde(with = "metadata_serde")]
     pub metadata: HashMap<String, serde_json::Value>,
 }
+
+/// bincode 等非自描述格式无法反序列化 `serde_json::Value`，这类格式下将 metadata 存为 JSON 字符串；
+/// JSON 等自描述格式保持原样
+mod metadata_serde {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::HashMap;
+
+    type Metadata = HashMap<String, serde_json::Value>;
+
+    pub fn serialize<S: Serializer>(metadata: &Metadata, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            metadata.serialize(serializer)
+        } else {
+            let json = serde_json::to_string(metadata).map_err(serde::ser::Error::custom)?;
+            json.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Metadata, D::Error> {
+        if deserializer.is_human_readable() {
+            Metadata::deserialize(deserializer)
+        } else {
+            let json = String::deserialize(deserializer)?;
+            serde_json::from_str(&json).map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Vec<Symbol> {
+        crate::ast::ASTParser::new()
+            .parse_file(std::path::Path::new("src/Service.java"), content)
+            .unwrap()
+            .into_iter()
+            .filter(|s| s.kind != SymbolKind::MethodCall)
+            .collect()
+    }
+
+    #[test]
+    fn stable_ids_survive_line_shifts_and_disambiguate_overloads() {
+        let original = "class Service {\n    void run(int x) {}\n    void run(String s) {}\n}\n";
+        let shifted = "\n// header\n\nclass Service {\n\n    void run(int x) {}\n\n    void run(String s) {}\n}\n";
+        let (before, after) = (parse(original), parse(shifted));
+
+        let ids = stable_ids(&before);
+        assert_eq!(
+            ids,
+            [
+                "src/Service.java:Service:class",
+                "src/Service.java:Service.run:method",
+                "src/Service.java:Service.run:method#2",
+            ]
+        );
+        assert_eq!(stable_ids(&after), ids);
+
+        let positions = |symbols: &[Symbol]| symbols.iter().map(Symbol::position_id).collect::<Vec<_>>();
+        assert_ne!(positions(&before), positions(&after));
+
+        let dict = after[1].to_dict();
+        assert_eq!(dict["id"], "src/Service.java:Service.run:method");
+        assert_eq!(dict["position_id"], "src/Service.java:run:6");
+    }
+
+    #[test]
+    fn js_methods_are_qualified_by_their_class() {
+        let content = "class Store {\n  load() {\n    this.fetch();\n  }\n}\n";
+        let symbols = crate::ast::ASTParser::new()
+            .parse_file(std::path::Path::new("src/store.ts"), content)
+            .unwrap();
+
+        let method = symbols.iter().find(|s| s.kind == SymbolKind::Method).unwrap();
+        assert_eq!(method.qualified_name(), "Store.load");
+        assert_eq!(method.stable_id(), "src/store.ts:Store.load:method");
+
+        // 方法调用的 callerClass 是调用方所在的类，不拼进被调用方法的名称
+        let call = symbols.iter().find(|s| s.kind == SymbolKind::MethodCall).unwrap();
+        assert_eq!(call.qualified_name(), call.name);
+    }
+}
@@ -0,0 +1,66 @@
+// 命令行入口：扫描标准输入并以 JSON 数组（或 `--format ndjson` 时每行一个发现）输出发现
+//
+// 用法：cat app.py | ctxaudit --lang python [--format json|ndjson]
+// 有发现时退出码为 1，出错时为 2，便于在 CI 和编辑器中使用
+
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: ctxaudit --lang <language> [--format json|ndjson] < file";
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let mut language = None;
+    let mut ndjson = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lang" | "-l" => language = args.next(),
+            "--format" | "-f" => match args.next().as_deref() {
+                Some("json") => ndjson = false,
+                Some("ndjson") => ndjson = true,
+                other => {
+                    eprintln!("Unknown format: {}\n{}", other.unwrap_or(""), USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            other => {
+                eprintln!("Unknown argument: {}\n{}", other, USAGE);
+                return ExitCode::from(2);
+            }
+        }
+    }
+    let Some(language) = language else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let findings = match deepaudit_core::scan_stdin(&language).await {
+        Ok(findings) => findings,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    if ndjson {
+        print!("{}", deepaudit_core::to_ndjson(&findings));
+    } else {
+        match serde_json::to_string_pretty(&findings) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to serialize findings: {}", e);
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    if findings.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    }
+}
@@ -7,13 +7,39 @@ pub mod rules;
 mod diff;
 
 // 重新导出常用类型
-pub use ast::{ASTEngine, ASTParser, CacheData, CacheManager, FileIndex, QueryEngine, Symbol, SymbolKind};
+pub use ast::{
+    diff_symbols, route_info, stable_ids, ASTEngine, ASTParser, CacheData, CacheManager,
+    CallGraphGranularity, FileIndex, ParseErrorMode, ParseHealth, QueryEngine, ReportOptions,
+    RouteInfo, Symbol, SymbolDiff, SymbolKind, SymbolRef, DEFAULT_ROUTE_DECORATOR_PATTERN,
+};
 pub use diff::DiffEngine;
-pub use scanner::{Finding, Scanner, scan_directory};
+pub use scanner::{
+    detector_precision, has_findings_at_or_above, normalize_finding_path, parse_cwe, priority_score,
+    scan_directories, scan_directories_report, scan_directories_stream, scan_directory,
+    scan_directory_report, scan_directory_stream, scoped_stable_finding_id, split_lines, stable_finding_id, FileHash,
+    Finding, LoadedRules,
+    ScanEvent, ScanMetrics, ScanOptions, ScanReport, ScannedFile, Scanner, SeverityOverride,
+    DEFAULT_MAX_FINDINGS_PER_FILE, DEFAULT_MAX_WALK_DEPTH,
+};
+pub use scanner::archive::{scan_archive, scan_archive_report};
+pub use scanner::git_ref::{scan_git_ref, scan_git_ref_report, scan_git_ref_stream};
+pub use scanner::stdin::{scan_content, scan_stdin};
+pub use scanner::command_injection::CommandInjectionScanner;
 pub use scanner::manager::ScannerManager;
+pub use scanner::path_traversal::PathTraversalScanner;
+pub use scanner::report::to_ndjson;
+pub use scanner::regex_scanner::{DetectorInfo, RegexScanner, RegexScannerConfig};
+pub use scanner::secret_scanner::{shannon_entropy, SecretScanner, DEFAULT_SECRET_MIN_ENTROPY};
+pub use scanner::weak_crypto::WeakCryptoScanner;
 
 // 规则系统
-pub use rules::{loader::load_rules_from_dir, model::Rule, scanner::RuleScanner};
+pub use rules::{
+    adhoc::{run_adhoc_query, QueryMatch},
+    lint::{lint_rule, RuleLint},
+    loader::{load_rules_from_dir, load_rules_from_url, load_rules_with_diagnostics, parse_rules},
+    model::{normalize_severity, Language, Rule, Severity},
+    scanner::{validate_rule, RuleScanner},
+};
 
 pub mod error {
     use thiserror::Error;
@@ -31,6 +57,9 @@ pub mod error {
 
         #[error("Scanner error: {0}")]
         Scanner(String),
+
+        #[error("Network error: {0}")]
+        Network(String),
     }
 
     pub type Result<T> = std::result::Result<T, CoreError>;
@@ -0,0 +1,118 @@
+// Ad-hoc query - 临时 Tree-sitter 查询
+// 不需要编写完整规则，直接在项目中执行一条查询
+
+use crate::rules::model::Language;
+use crate::rules::scanner::compile_query;
+use crate::scanner::is_supported_file;
+use serde::Serialize;
+use std::path::Path;
+use tree_sitter::{Parser, QueryCursor};
+
+/// 单个捕获结果
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryMatch {
+    pub file: String,
+    pub line_start: usize,
+    pub line_end: usize,
+    pub capture: String,
+    pub text: String,
+}
+
+/// 在目录中所有该语言的文件上执行查询，最多返回 `max_matches` 条结果
+///
+/// 查询编译失败时返回错误；单个文件读取或解析失败时跳过该文件。
+pub fn run_adhoc_query(
+    root: &Path,
+    language: &str,
+    query_str: &str,
+    max_matches: usize,
+) -> Result<Vec<QueryMatch>, String> {
+    let language = Language::from(language);
+    let (lang, query) = compile_query(&language, query_str)?;
+
+    let mut parser = Parser::new();
+    parser
+        .set_language(&lang)
+        .map_err(|e| format!("Failed to set parser language: {}", e))?;
+
+    let capture_names = query.capture_names();
+    let mut results = Vec::new();
+
+    for entry in ignore::Walk::new(root).flatten() {
+        if results.len() >= max_matches {
+            break;
+        }
+
+        let path = entry.path();
+        if !path.is_file() || !is_supported_file(path) {
+            continue;
+        }
+
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        if !language.matches_extension(&extension) {
+            continue;
+        }
+
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        let tree = match parser.parse(&content, None) {
+            Some(tree) => tree,
+            None => continue,
+        };
+
+        let file = path.to_string_lossy().to_string();
+        let mut cursor = QueryCursor::new();
+        'matches: for m in cursor.matches(&query, tree.root_node(), content.as_bytes()) {
+            for capture in m.captures {
+                if results.len() >= max_matches {
+                    break 'matches;
+                }
+
+                let node = capture.node;
+                results.push(QueryMatch {
+                    file: file.clone(),
+                    line_start: node.start_position().row + 1,
+                    line_end: node.end_position().row + 1,
+                    capture: capture_names[capture.index as usize].to_string(),
+                    text: content[node.byte_range()].to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queries_function_names_in_python_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("app.py"),
+            "def load():\n    pass\n\nclass Store:\n    def save(self):\n        pass\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("app.js"), "function ignored() {}\n").unwrap();
+        let query = "(function_definition name: (identifier) @name)";
+
+        let matches = run_adhoc_query(dir.path(), "python", query, 100).unwrap();
+        let names: Vec<(&str, usize, &str)> = matches
+            .iter()
+            .map(|m| (m.text.as_str(), m.line_start, m.capture.as_str()))
+            .collect();
+        assert_eq!(names, vec![("load", 1, "name"), ("save", 5, "name")]);
+        assert!(matches.iter().all(|m| m.file.ends_with("app.py")));
+
+        assert_eq!(run_adhoc_query(dir.path(), "python", query, 1).unwrap().len(), 1);
+        assert!(run_adhoc_query(dir.path(), "python", "(function_definition", 100).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+// Rule lint - 规则质量检查
+// 标记过于宽泛、无法产生有效结果或缺少关键信息的规则，结果仅作提示，不阻止保存
+
+use crate::rules::model::Rule;
+use crate::rules::scanner::compile_query;
+use serde::Serialize;
+
+/// 短于该长度且没有锚点的正则视为过于宽泛
+const SHORT_PATTERN_LEN: usize = 4;
+
+/// 单条检查结果
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleLint {
+    /// 出问题的字段（pattern / query / cwe）
+    pub field: String,
+    pub message: String,
+}
+
+impl RuleLint {
+    fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// 检查规则，返回所有警告
+pub fn lint_rule(rule: &Rule) -> Vec<RuleLint> {
+    let mut lints = Vec::new();
+
+    if rule.non_blank_pattern().is_none() && rule.non_blank_query().is_none() {
+        lints.push(RuleLint::new("pattern", "Rule has neither a non-empty pattern nor a query"));
+    }
+
+    if let Some(pattern) = &rule.pattern {
+        lint_pattern(pattern, &mut lints);
+    }
+
+    if let Some(query) = rule.non_blank_query() {
+        match compile_query(&rule.language, query) {
+            Ok((_, compiled)) => {
+                if compiled.capture_names().is_empty() {
+                    lints.push(RuleLint::new(
+                        "query",
+                        "Query has no captures, matches cannot be located",
+                    ));
+                }
+            }
+            Err(e) => lints.push(RuleLint::new("query", e)),
+        }
+    }
+
+    for (field, globs) in [("include_paths", &rule.include_paths), ("exclude_paths", &rule.exclude_paths)] {
+        for glob in globs.iter().flatten() {
+            if let Err(e) = globset::Glob::new(glob) {
+                lints.push(RuleLint::new(field, format!("Invalid path glob '{}': {}", glob, e)));
+            }
+        }
+    }
+
+    if rule.cwe.as_deref().map(str::trim).unwrap_or("").is_empty() {
+        lints.push(RuleLint::new("cwe", "Rule has no CWE, findings will be reported as 'Unknown'"));
+    }
+
+    lints
+}
+
+fn lint_pattern(pattern: &str, lints: &mut Vec<RuleLint>) {
+    let trimmed = pattern.trim();
+
+    if trimmed.is_empty() {
+        lints.push(RuleLint::new("pattern", "Pattern is empty and is ignored"));
+        return;
+    }
+
+    // 去掉行首尾锚点后仍然是“任意字符”的模式
+    let body = trimmed.trim_start_matches('^').trim_end_matches('$');
+    if matches!(body, ".*" | ".+" | "." | "(?s).*" | "(?s).+" | "[\\s\\S]*") {
+        lints.push(RuleLint::new(
+            "pattern",
+            format!("Pattern '{}' matches any text", pattern),
+        ));
+        return;
+    }
+
+    if trimmed.chars().count() == 1 {
+        lints.push(RuleLint::new(
+            "pattern",
+            format!("Single-character pattern '{}' is too broad", pattern),
+        ));
+        return;
+    }
+
+    let anchored = trimmed.starts_with('^') || trimmed.ends_with('$') || trimmed.contains("\\b");
+    if trimmed.chars().count() < SHORT_PATTERN_LEN && !anchored {
+        lints.push(RuleLint::new(
+            "pattern",
+            format!("Short pattern '{}' has no anchors and is likely to be noisy", pattern),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(extra: &str) -> Rule {
+        serde_yaml::from_str(&format!(
+            "id: \"r\"\nname: \"R\"\ndescription: \"d\"\nseverity: \"high\"\nlanguage: \"python\"\ncwe: \"CWE-94\"\n{}\n",
+            extra
+        ))
+        .unwrap()
+    }
+
+    fn fields(rule: &Rule) -> Vec<String> {
+        lint_rule(rule).into_iter().map(|lint| lint.field).collect()
+    }
+
+    #[test]
+    fn flags_patterns_matching_anything() {
+        for pattern in [".*", "^.*$", "(?s).+", "x", "ab"] {
+            let lints = lint_rule(&rule(&format!("pattern: '{}'", pattern)));
+            assert_eq!(lints.len(), 1, "{}", pattern);
+            assert_eq!(lints[0].field, "pattern");
+        }
+        assert!(fields(&rule(r"pattern: 'eval\('")).is_empty());
+        assert!(fields(&rule(r"pattern: '^ab'")).is_empty());
+    }
+
+    #[test]
+    fn flags_queries_without_captures() {
+        let lints = lint_rule(&rule("query: '(call function: (identifier))'"));
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].field, "query");
+        assert!(lints[0].message.contains("no captures"));
+
+        assert!(fields(&rule("query: '(call function: (identifier) @match)'")).is_empty());
+    }
+
+    #[test]
+    fn flags_missing_cwe_and_missing_pattern() {
+        let rule: Rule = serde_yaml::from_str(
+            "id: \"r\"\nname: \"R\"\ndescription: \"d\"\nseverity: \"high\"\nlanguage: \"python\"\n",
+        )
+        .unwrap();
+        assert_eq!(fields(&rule), ["pattern", "cwe"]);
+    }
+}
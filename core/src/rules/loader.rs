@@ -1,13 +1,25 @@
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
 use walkdir::WalkDir;
 use crate::rules::model::{Rule, RuleSet};
 
 pub fn load_rules_from_dir<P: AsRef<Path>>(path: P) -> Result<Vec<Rule>> {
+    let (rules, _diagnostics) = load_rules_with_diagnostics(path)?;
+    Ok(rules)
+}
+
+/// 加载规则并返回诊断信息（重复的规则ID、无法解析的文件等）
+///
+/// 文件按名称排序加载，同一ID只保留最先加载的规则。
+pub fn load_rules_with_diagnostics<P: AsRef<Path>>(path: P) -> Result<(Vec<Rule>, Vec<String>)> {
     let mut rules = Vec::new();
+    let mut diagnostics = Vec::new();
+    // rule_id -> 定义该规则的文件
+    let mut seen_ids: HashMap<String, PathBuf> = HashMap::new();
 
-    for entry in WalkDir::new(path) {
+    for entry in WalkDir::new(path).sort_by_file_name() {
         let entry = entry?;
         if entry.file_type().is_file() {
             let path = entry.path();
@@ -16,18 +28,179 @@ pub fn load_rules_from_dir<P: AsRef<Path>>(path: P) -> Result<Vec<Rule>> {
                     let content = fs::read_to_string(path)
                         .with_context(|| format!("Failed to read rule file: {:?}", path))?;
                     
-                    // Try to parse as RuleSet first, then as single Rule
-                    if let Ok(rule_set) = serde_yaml::from_str::<RuleSet>(&content) {
-                        rules.extend(rule_set.rules);
-                    } else if let Ok(rule) = serde_yaml::from_str::<Rule>(&content) {
+                    let file_rules = match parse_rules(&content) {
+                        Some(file_rules) => file_rules,
+                        None => {
+                            log::warn!("Failed to parse rule file: {:?}", path);
+                            diagnostics.push(format!("Failed to parse rule file: {}", path.display()));
+                            continue;
+                        }
+                    };
+
+                    for rule in file_rules {
+                        if let Some(first_path) = seen_ids.get(&rule.id) {
+                            let message = format!(
+                                "Duplicate rule id '{}' in {} (already defined in {}), ignoring",
+                                rule.id,
+                                path.display(),
+                                first_path.display()
+                            );
+                            log::warn!("{}", message);
+                            diagnostics.push(message);
+                            continue;
+                        }
+                        seen_ids.insert(rule.id.clone(), path.to_path_buf());
                         rules.push(rule);
-                    } else {
-                        eprintln!("Failed to parse rule file: {:?}", path);
                     }
                 }
             }
         }
     }
 
-    Ok(rules)
+    Ok((rules, diagnostics))
+}
+
+/// 解析规则文本：依次尝试 RuleSet、单条 Rule 和规则列表（JSON 也是合法的 YAML）
+pub fn parse_rules(content: &str) -> Option<Vec<Rule>> {
+    if let Ok(rule_set) = serde_yaml::from_str::<RuleSet>(content) {
+        Some(rule_set.rules)
+    } else if let Ok(rule) = serde_yaml::from_str::<Rule>(content) {
+        Some(vec![rule])
+    } else {
+        serde_yaml::from_str::<Vec<Rule>>(content).ok()
+    }
+}
+
+/// 远程规则包的最大大小
+const MAX_REMOTE_RULES_SIZE: usize = 5 * 1024 * 1024;
+
+/// 拉取远程规则包的超时时间
+const REMOTE_RULES_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// 从 URL 拉取规则包（YAML RuleSet 或 JSON 规则列表），解析方式与 `load_rules_from_dir` 相同
+pub async fn load_rules_from_url(url: &str) -> crate::error::Result<Vec<Rule>> {
+    use crate::error::CoreError;
+
+    let client = reqwest::Client::builder()
+        .timeout(REMOTE_RULES_TIMEOUT)
+        .build()
+        .map_err(|e| CoreError::Network(format!("Failed to create HTTP client: {}", e)))?;
+
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| CoreError::Network(format!("Failed to fetch {}: {}", url, e)))?;
+
+    // 只接受文本类内容，避免把 HTML 页面或二进制文件当作规则解析
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_lowercase();
+    let accepted = content_type.is_empty()
+        || ["yaml", "yml", "json", "text/plain", "application/octet-stream"]
+            .iter()
+            .any(|t| content_type.contains(t));
+    if !accepted {
+        return Err(CoreError::Network(format!(
+            "Unsupported content type for rule pack: {}",
+            content_type
+        )));
+    }
+
+    if response.content_length().unwrap_or(0) as usize > MAX_REMOTE_RULES_SIZE {
+        return Err(CoreError::Network(format!(
+            "Rule pack exceeds {} bytes",
+            MAX_REMOTE_RULES_SIZE
+        )));
+    }
+
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| CoreError::Network(format!("Failed to read response body: {}", e)))?;
+    if body.len() > MAX_REMOTE_RULES_SIZE {
+        return Err(CoreError::Network(format!(
+            "Rule pack exceeds {} bytes",
+            MAX_REMOTE_RULES_SIZE
+        )));
+    }
+
+    let content = String::from_utf8(body.to_vec())
+        .map_err(|_| CoreError::Parse("Rule pack is not valid UTF-8".to_string()))?;
+
+    parse_rules(&content)
+        .ok_or_else(|| CoreError::Parse(format!("Failed to parse rule pack from {}", url)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CoreError;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const RULE_PACK: &str = r#"name: "Shared"
+version: "1.0"
+rules:
+  - id: "shared-eval"
+    name: "Eval"
+    description: "eval call"
+    severity: "high"
+    language: "python"
+    pattern: "eval\\("
+"#;
+
+    #[test]
+    fn duplicate_rule_ids_keep_first_file_and_report_conflict() {
+        let dir = tempfile::tempdir().unwrap();
+        let exec_rule = RULE_PACK.replace("shared-eval", "shared-exec").replace("eval", "exec");
+        std::fs::write(dir.path().join("a.yaml"), RULE_PACK).unwrap();
+        std::fs::write(dir.path().join("b.yaml"), RULE_PACK.replace("eval call", "redefined eval")).unwrap();
+        std::fs::write(dir.path().join("c.yaml"), exec_rule).unwrap();
+
+        let (rules, diagnostics) = load_rules_with_diagnostics(dir.path()).unwrap();
+        let loaded: Vec<(&str, &str)> = rules.iter().map(|r| (r.id.as_str(), r.description.as_str())).collect();
+        assert_eq!(loaded, vec![("shared-eval", "eval call"), ("shared-exec", "exec call")]);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].contains("shared-eval"));
+        assert!(diagnostics[0].contains("b.yaml") && diagnostics[0].contains("a.yaml"));
+    }
+
+    async fn serve(server: &MockServer, route: &str, response: ResponseTemplate) {
+        Mock::given(method("GET")).and(path(route)).respond_with(response).mount(server).await;
+    }
+
+    #[tokio::test]
+    async fn loads_rule_pack_from_mock_server() {
+        let server = MockServer::start().await;
+        serve(&server, "/rules.yaml", ResponseTemplate::new(200).set_body_raw(RULE_PACK, "application/yaml")).await;
+        let json = r#"[{"id": "json-exec", "name": "Exec", "description": "exec call", "severity": "medium", "language": "python", "pattern": "exec\\("}]"#;
+        serve(&server, "/rules.json", ResponseTemplate::new(200).set_body_raw(json, "application/json")).await;
+
+        let rules = load_rules_from_url(&format!("{}/rules.yaml", server.uri())).await.unwrap();
+        assert_eq!(rules.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), vec!["shared-eval"]);
+        let rules = load_rules_from_url(&format!("{}/rules.json", server.uri())).await.unwrap();
+        assert_eq!(rules.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), vec!["json-exec"]);
+    }
+
+    #[tokio::test]
+    async fn rejects_failed_html_oversized_and_invalid_rule_packs() {
+        let server = MockServer::start().await;
+        serve(&server, "/missing.yaml", ResponseTemplate::new(404)).await;
+        serve(&server, "/page", ResponseTemplate::new(200).set_body_raw("<html></html>", "text/html")).await;
+        let oversized = "#".repeat(MAX_REMOTE_RULES_SIZE + 1);
+        serve(&server, "/big.yaml", ResponseTemplate::new(200).set_body_raw(oversized, "application/yaml")).await;
+        serve(&server, "/bad.yaml", ResponseTemplate::new(200).set_body_raw("not: [a rule", "application/yaml")).await;
+
+        let url = |route: &str| format!("{}{}", server.uri(), route);
+        assert!(matches!(load_rules_from_url(&url("/missing.yaml")).await, Err(CoreError::Network(_))));
+        assert!(matches!(load_rules_from_url(&url("/page")).await, Err(CoreError::Network(_))));
+        assert!(matches!(load_rules_from_url(&url("/big.yaml")).await, Err(CoreError::Network(_))));
+        assert!(matches!(load_rules_from_url(&url("/bad.yaml")).await, Err(CoreError::Parse(_))));
+        assert!(matches!(load_rules_from_url("http://127.0.0.1:1/rules.yaml").await, Err(CoreError::Network(_))));
+    }
 }
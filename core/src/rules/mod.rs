@@ -1,3 +1,5 @@
+pub mod adhoc;
+pub mod lint;
 pub mod model;
 pub mod loader;
 pub mod scanner;
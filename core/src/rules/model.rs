@@ -6,15 +6,44 @@ pub struct Rule {
     pub name: String,
     pub description: String,
     pub severity: Severity,
-    pub language: String,
+    pub language: Language,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pattern: Option<String>,
+    /// Tree-sitter 查询。发现定位到名为 `@match`（或 `@location`）的捕获节点；
+    /// 查询中没有这两个捕获时，定位到整个匹配覆盖的行范围
     #[serde(skip_serializing_if = "Option::is_none")]
     pub query: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub category: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cwe: Option<String>,
+    /// Tree-sitter 规则是否为每个捕获单独生成发现（默认每个匹配只报告一条，位置见 `query`）
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub report_all_captures: bool,
+    /// 执行优先级（默认 0），数值越大越先执行，相同优先级按 id 排序；
+    /// 规则顺序决定同一位置多条发现的先后，会影响抑制/排除规则与检测规则的相互作用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+    /// 只对匹配这些 glob 的文件路径生效（如 `**/controllers/**`、`src/api/**`），不填表示所有文件
+    ///
+    /// 与相对仓库根目录（未设置时为扫描根目录）的路径匹配，压缩包和 git 引用扫描中为条目路径
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_paths: Option<Vec<String>>,
+    /// 不对匹配这些 glob 的文件路径生效，优先于 include_paths
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclude_paths: Option<Vec<String>>,
+}
+
+impl Rule {
+    /// 去除空白后非空的 pattern；空 pattern 会在每个位置匹配，视为未设置
+    pub fn non_blank_pattern(&self) -> Option<&str> {
+        self.pattern.as_deref().filter(|pattern| !pattern.trim().is_empty())
+    }
+
+    /// 去除空白后非空的 query，空 query 视为未设置
+    pub fn non_blank_query(&self) -> Option<&str> {
+        self.query.as_deref().filter(|query| !query.trim().is_empty())
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
@@ -27,9 +56,291 @@ pub enum Severity {
     Info,
 }
 
+impl Severity {
+    /// 所有严重程度，从高到低
+    pub const ALL: [Severity; 5] = [
+        Severity::Critical,
+        Severity::High,
+        Severity::Medium,
+        Severity::Low,
+        Severity::Info,
+    ];
+
+    /// 规范的小写名称，与序列化结果一致，所有扫描器输出的 severity 都使用该形式
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Critical => "critical",
+            Severity::High => "high",
+            Severity::Medium => "medium",
+            Severity::Low => "low",
+            Severity::Info => "info",
+        }
+    }
+
+    /// 展示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Critical => "Critical",
+            Severity::High => "High",
+            Severity::Medium => "Medium",
+            Severity::Low => "Low",
+            Severity::Info => "Info",
+        }
+    }
+
+    /// 严重程度等级，越大越严重（critical=4 … info=0）
+    pub fn rank(&self) -> u32 {
+        match self {
+            Severity::Critical => 4,
+            Severity::High => 3,
+            Severity::Medium => 2,
+            Severity::Low => 1,
+            Severity::Info => 0,
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 将任意写法的严重程度统一为小写规范形式；无法识别的值仅转为小写
+pub fn normalize_severity(severity: &str) -> String {
+    severity
+        .parse::<Severity>()
+        .map(|s| s.as_str().to_string())
+        .unwrap_or_else(|_| severity.trim().to_lowercase())
+}
+
+impl std::str::FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "critical" => Ok(Severity::Critical),
+            "high" => Ok(Severity::High),
+            "medium" => Ok(Severity::Medium),
+            "low" => Ok(Severity::Low),
+            "info" => Ok(Severity::Info),
+            _ => Err(format!("Unknown severity: {}", s)),
+        }
+    }
+}
+
+/// 规则适用的语言
+///
+/// YAML 中按字符串书写，不区分大小写并接受常见别名（如 `py`、`js`、`c++`）；
+/// `all` / `*` 表示适用于所有文件，无法识别的名称保留为 [`Language::Other`]，按扩展名匹配
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Language {
+    Python,
+    JavaScript,
+    TypeScript,
+    Rust,
+    Go,
+    Java,
+    C,
+    Cpp,
+    Json,
+    Html,
+    Ruby,
+    All,
+    Other(String),
+}
+
+impl Language {
+    /// 规范的小写名称，与 YAML 中的写法一致
+    pub fn as_str(&self) -> &str {
+        match self {
+            Language::Python => "python",
+            Language::JavaScript => "javascript",
+            Language::TypeScript => "typescript",
+            Language::Rust => "rust",
+            Language::Go => "go",
+            Language::Java => "java",
+            Language::C => "c",
+            Language::Cpp => "cpp",
+            Language::Json => "json",
+            Language::Html => "html",
+            Language::Ruby => "ruby",
+            Language::All => "all",
+            Language::Other(name) => name,
+        }
+    }
+
+    /// 该语言源文件的扩展名（小写，不含 `.`）
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            Language::Python => &["py"],
+            Language::JavaScript => &["js", "jsx", "mjs", "cjs"],
+            Language::TypeScript => &["ts", "tsx"],
+            Language::Rust => &["rs"],
+            Language::Go => &["go"],
+            Language::Java => &["java"],
+            Language::C => &["c", "h"],
+            Language::Cpp => &["cpp", "hpp", "cc", "cxx"],
+            Language::Json => &["json"],
+            Language::Html => &["html", "htm"],
+            Language::Ruby => &["rb"],
+            Language::All | Language::Other(_) => &[],
+        }
+    }
+
+    /// 根据扩展名识别语言（不区分大小写）
+    pub fn from_extension(extension: &str) -> Option<Language> {
+        let extension = extension.trim_start_matches('.').to_lowercase();
+        [
+            Language::Python,
+            Language::JavaScript,
+            Language::TypeScript,
+            Language::Rust,
+            Language::Go,
+            Language::Java,
+            Language::C,
+            Language::Cpp,
+            Language::Json,
+            Language::Html,
+            Language::Ruby,
+        ]
+        .into_iter()
+        .find(|language| language.extensions().contains(&extension.as_str()))
+    }
+
+    /// 规则是否适用于该扩展名的文件；JavaScript 与 TypeScript 规则互相适用
+    pub fn matches_extension(&self, extension: &str) -> bool {
+        let extension = extension.to_lowercase();
+        match self {
+            Language::All => true,
+            Language::JavaScript | Language::TypeScript => {
+                Language::JavaScript.extensions().contains(&extension.as_str())
+                    || Language::TypeScript.extensions().contains(&extension.as_str())
+            }
+            Language::Other(name) => name.eq_ignore_ascii_case(&extension),
+            language => language.extensions().contains(&extension.as_str()),
+        }
+    }
+
+    /// 对应的 Tree-sitter 语法，不支持 AST 查询的语言返回 None
+    pub fn grammar(&self) -> Option<tree_sitter::Language> {
+        match self {
+            Language::Python => Some(tree_sitter_python::LANGUAGE.into()),
+            Language::JavaScript => Some(tree_sitter_javascript::LANGUAGE.into()),
+            Language::TypeScript => Some(tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into()),
+            Language::Rust => Some(tree_sitter_rust::LANGUAGE.into()),
+            Language::Go => Some(tree_sitter_go::LANGUAGE.into()),
+            Language::Java => Some(tree_sitter_java::LANGUAGE.into()),
+            Language::C => Some(tree_sitter_c::LANGUAGE.into()),
+            Language::Cpp => Some(tree_sitter_cpp::LANGUAGE.into()),
+            Language::Json => Some(tree_sitter_json::LANGUAGE.into()),
+            Language::Html => Some(tree_sitter_html::LANGUAGE.into()),
+            Language::Ruby => Some(tree_sitter_ruby::LANGUAGE.into()),
+            Language::All | Language::Other(_) => None,
+        }
+    }
+}
+
+impl From<&str> for Language {
+    fn from(s: &str) -> Self {
+        match s.trim().to_lowercase().as_str() {
+            "python" | "py" => Language::Python,
+            "javascript" | "js" | "jsx" => Language::JavaScript,
+            "typescript" | "ts" | "tsx" => Language::TypeScript,
+            "rust" | "rs" => Language::Rust,
+            "go" | "golang" => Language::Go,
+            "java" => Language::Java,
+            "c" => Language::C,
+            "cpp" | "c++" | "cxx" => Language::Cpp,
+            "json" => Language::Json,
+            "html" => Language::Html,
+            "ruby" | "rb" => Language::Ruby,
+            "all" | "*" => Language::All,
+            other => Language::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for Language {
+    fn from(s: String) -> Self {
+        Language::from(s.as_str())
+    }
+}
+
+impl From<Language> for String {
+    fn from(language: Language) -> Self {
+        language.as_str().to_string()
+    }
+}
+
+impl std::fmt::Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct RuleSet {
     pub name: String,
     pub version: String,
     pub rules: Vec<Rule>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_names_are_case_insensitive_with_aliases() {
+        for (name, expected) in [
+            ("Python", Language::Python),
+            ("py", Language::Python),
+            ("JS", Language::JavaScript),
+            ("tsx", Language::TypeScript),
+            ("golang", Language::Go),
+            ("C++", Language::Cpp),
+            ("*", Language::All),
+            ("Kotlin", Language::Other("kotlin".to_string())),
+        ] {
+            assert_eq!(Language::from(name), expected, "{}", name);
+        }
+
+        // YAML 中的字符串反序列化为枚举，序列化回规范名称
+        let language: Language = serde_yaml::from_str("\"PYTHON\"").unwrap();
+        assert_eq!(language, Language::Python);
+        assert_eq!(serde_yaml::to_string(&Language::Cpp).unwrap().trim(), "cpp");
+    }
+
+    #[test]
+    fn language_extension_mapping_round_trips() {
+        for language in [Language::Python, Language::Rust, Language::Java, Language::Ruby, Language::Cpp] {
+            for extension in language.extensions() {
+                assert_eq!(Language::from_extension(extension), Some(language.clone()));
+                assert!(language.matches_extension(extension));
+                assert!(language.grammar().is_some());
+            }
+        }
+        assert_eq!(Language::from_extension(".PY"), Some(Language::Python));
+        assert_eq!(Language::from_extension("kt"), None);
+
+        // JavaScript 与 TypeScript 规则互相适用；all 适用于任意文件；未知语言按扩展名匹配
+        assert!(Language::JavaScript.matches_extension("tsx"));
+        assert!(Language::TypeScript.matches_extension("mjs"));
+        assert!(!Language::Python.matches_extension("rb"));
+        assert!(Language::All.matches_extension("anything"));
+        assert!(Language::from("kt").matches_extension("KT"));
+        assert!(Language::All.grammar().is_none());
+    }
+
+    #[test]
+    fn severity_strings_normalize_to_lowercase() {
+        for (raw, expected) in [("High", "high"), ("CRITICAL", "critical"), (" low ", "low"), ("info", "info"), ("Severe", "severe")] {
+            assert_eq!(normalize_severity(raw), expected);
+        }
+        for severity in Severity::ALL {
+            assert_eq!(serde_json::to_value(&severity).unwrap(), severity.as_str());
+            assert_eq!(severity.as_str().parse::<Severity>().unwrap(), severity);
+        }
+    }
+}
@@ -1,10 +1,15 @@
-use crate::rules::model::Rule;
-use crate::scanner::{Finding, Scanner};
+use crate::rules::model::{Language as RuleLanguage, Rule};
+use crate::scanner::{line_number_at, normalize_finding_path, stable_finding_id, Finding, Scanner};
 use async_trait::async_trait;
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use regex::Regex;
-use std::path::PathBuf;
-use tree_sitter::{Language, Parser, Query, QueryCursor};
-use uuid::Uuid;
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tree_sitter::{Language, Parser, Query, QueryCursor, Tree};
+
+/// 指定 Tree-sitter 规则发现位置的捕获名（见 `Rule::query`）
+pub const LOCATION_CAPTURE_NAMES: &[&str] = &["match", "location"];
 
 pub enum RuleMatcher {
     Regex(Regex),
@@ -15,50 +20,86 @@ pub struct CompiledRule {
     pub rule: Rule,
     pub matcher: RuleMatcher,
     pub language: Option<Language>,
+    pub scope: PathScope,
+}
+
+/// 规则的文件路径范围，由 `Rule::include_paths` / `Rule::exclude_paths` 编译而来
+#[derive(Default)]
+pub struct PathScope {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl PathScope {
+    fn compile(rule: &Rule) -> Result<Self, String> {
+        Ok(Self {
+            include: compile_globs(rule.include_paths.as_deref())?,
+            exclude: compile_globs(rule.exclude_paths.as_deref())?,
+        })
+    }
+
+    /// 路径是否在范围内：不匹配任何排除模式，且（有包含模式时）至少匹配一个包含模式
+    pub fn contains(&self, path: &Path) -> bool {
+        if self.exclude.as_ref().is_some_and(|exclude| exclude.is_match(path)) {
+            return false;
+        }
+        self.include.as_ref().is_none_or(|include| include.is_match(path))
+    }
+}
+
+fn compile_globs(patterns: Option<&[String]>) -> Result<Option<GlobSet>, String> {
+    let Some(patterns) = patterns else {
+        return Ok(None);
+    };
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).map_err(|e| format!("Invalid path glob '{}': {}", pattern, e))?);
+    }
+    builder
+        .build()
+        .map(Some)
+        .map_err(|e| format!("Invalid path globs: {}", e))
 }
 
 pub struct RuleScanner {
     compiled_rules: Vec<CompiledRule>,
+    parse_timeout_micros: u64,
 }
 
 impl RuleScanner {
     pub fn new(rules: Vec<Rule>) -> Self {
         let mut compiled_rules = Vec::new();
         for rule in rules {
-            // Priority: Query (AST) > Pattern (Regex)
-            if let Some(query_str) = &rule.query {
-                if let Some(lang) = get_language_for_rule(&rule.language) {
-                    match Query::new(&lang, query_str) {
-                        Ok(query) => {
-                            compiled_rules.push(CompiledRule {
-                                rule: rule.clone(),
-                                matcher: RuleMatcher::TreeSitter(query),
-                                language: Some(lang),
-                            });
-                        }
-                        Err(e) => {
-                            eprintln!("Invalid Tree-sitter query for rule {}: {}", rule.id, e);
-                        }
-                    }
-                } else {
-                    eprintln!(
-                        "Unsupported language for Tree-sitter rule {}: {}",
-                        rule.id, rule.language
-                    );
-                }
-            } else if let Some(pattern) = &rule.pattern {
-                if let Ok(regex) = Regex::new(pattern) {
-                    compiled_rules.push(CompiledRule {
-                        rule: rule.clone(),
-                        matcher: RuleMatcher::Regex(regex),
-                        language: None,
-                    });
-                } else {
-                    eprintln!("Invalid regex pattern for rule {}: {}", rule.id, pattern);
-                }
+            match compile_rule(&rule) {
+                Ok(compiled) => compiled_rules.push(compiled),
+                Err(e) => log::warn!("Skipping rule {}: {}", rule.id, e),
             }
         }
-        Self { compiled_rules }
+
+        // 按优先级（高者在前）和 id 排序，不依赖规则目录的加载顺序
+        compiled_rules.sort_by(|a, b| {
+            b.rule
+                .priority
+                .unwrap_or(0)
+                .cmp(&a.rule.priority.unwrap_or(0))
+                .then_with(|| a.rule.id.cmp(&b.rule.id))
+        });
+
+        Self {
+            compiled_rules,
+            parse_timeout_micros: crate::ast::DEFAULT_PARSE_TIMEOUT_MICROS,
+        }
+    }
+
+    /// 设置 Tree-sitter 规则解析文件的超时时间（微秒），0 表示不限制
+    pub fn with_parse_timeout(mut self, timeout_micros: u64) -> Self {
+        self.parse_timeout_micros = timeout_micros;
+        self
+    }
+
+    /// 编译成功的规则数量（编译失败的规则在 [`RuleScanner::new`] 中被跳过）
+    pub fn rule_count(&self) -> usize {
+        self.compiled_rules.len()
     }
 }
 
@@ -69,74 +110,196 @@ impl Scanner for RuleScanner {
     }
 
     async fn scan_file(&self, path: &PathBuf, content: &str) -> Vec<Finding> {
-        let mut findings = Vec::new();
+        self.scan_file_in_scope(path, path, content)
+    }
+}
+
+impl RuleScanner {
+    /// 同 [`Scanner::scan_file`]，规则的路径范围按 `scope_path` 匹配（目录扫描时为相对扫描根目录的路径，
+    /// 与扫描位置无关）
+    pub fn scan_file_in_scope(&self, path: &PathBuf, scope_path: &Path, content: &str) -> Vec<Finding> {
+        self.scan_file_until(path, scope_path, content, None)
+    }
+
+    /// 同 [`RuleScanner::scan_file_in_scope`]，到达 `deadline` 后不再开始执行新的规则，
+    /// 返回的发现不完整（供扫描时间上限使用，调用方丢弃超时文件的结果）
+    pub fn scan_file_until(
+        &self,
+        path: &PathBuf,
+        scope_path: &Path,
+        content: &str,
+        deadline: Option<Instant>,
+    ) -> Vec<Finding> {
         let extension = path
             .extension()
             .and_then(|e| e.to_str())
             .unwrap_or("")
             .to_lowercase();
 
-        for compiled in &self.compiled_rules {
-            // Simple language check based on extension
-            if !rule_matches_extension(&compiled.rule.language, &extension) {
+        // 按扩展名和路径范围判断规则是否适用
+        let rules: Vec<&CompiledRule> = self
+            .compiled_rules
+            .iter()
+            .filter(|compiled| compiled.rule.language.matches_extension(&extension))
+            .filter(|compiled| compiled.scope.contains(scope_path))
+            .collect();
+
+        // 每种语言只解析一次，所有 Tree-sitter 规则共享只读的语法树
+        let mut trees: Vec<(&Language, Option<Tree>)> = Vec::new();
+        for lang in rules.iter().filter_map(|compiled| compiled.language.as_ref()) {
+            if trees.iter().any(|(parsed, _)| *parsed == lang) {
                 continue;
             }
+            trees.push((lang, self.parse(lang, path, content)));
+        }
 
-            match &compiled.matcher {
-                RuleMatcher::Regex(regex) => {
-                    for cap in regex.captures_iter(content) {
-                        if let Some(m) = cap.get(0) {
-                            let start_pos = m.start();
-                            let end_pos = m.end();
-
-                            // Convert byte offset to line number
-                            let line_start = content[..start_pos].matches('\n').count() + 1;
-                            let line_end = content[..end_pos].matches('\n').count() + 1;
-
-                            findings.push(create_finding(
-                                &compiled.rule,
-                                path,
-                                line_start,
-                                line_end,
-                                format!("RegexRule: {}", compiled.rule.id),
-                            ));
-                        }
-                    }
+        // 规则之间并行执行；有序 collect 保证结果仍按规则顺序（优先级、id）合并
+        rules
+            .par_iter()
+            .map(|compiled| {
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    return Vec::new();
                 }
-                RuleMatcher::TreeSitter(query) => {
-                    if let Some(lang) = &compiled.language {
-                        let mut parser = Parser::new();
-                        if parser.set_language(lang).is_ok() {
-                            if let Some(tree) = parser.parse(content, None) {
-                                let mut cursor = QueryCursor::new();
-                                let matches =
-                                    cursor.matches(query, tree.root_node(), content.as_bytes());
-
-                                for m in matches {
-                                    // Use the first capture for location
-                                    if let Some(capture) = m.captures.first() {
-                                        let node = capture.node;
-                                        let start_pos = node.start_position();
-                                        let end_pos = node.end_position();
-
-                                        findings.push(create_finding(
-                                            &compiled.rule,
-                                            path,
-                                            start_pos.row + 1,
-                                            end_pos.row + 1,
-                                            format!("ASTRule: {}", compiled.rule.id),
-                                        ));
-                                    }
-                                }
-                            }
-                        }
-                    }
+                let tree = compiled.language.as_ref().and_then(|lang| {
+                    trees
+                        .iter()
+                        .find(|(parsed, _)| *parsed == lang)
+                        .and_then(|(_, tree)| tree.as_ref())
+                });
+                evaluate_rule(compiled, path, content, tree)
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// 解析文件，超时或语言加载失败时返回 None，该语言的 Tree-sitter 规则被跳过
+    fn parse(&self, lang: &Language, path: &Path, content: &str) -> Option<Tree> {
+        let mut parser = Parser::new();
+        parser.set_timeout_micros(self.parse_timeout_micros);
+        parser.set_language(lang).ok()?;
+
+        // Tree-sitter 语法只把 LF 当作换行，单独的 CR 原位替换为 LF 后再解析，字节偏移不变
+        let tree = match lone_cr_as_lf(content) {
+            Some(normalized) => parser.parse(&normalized, None),
+            None => parser.parse(content, None),
+        };
+        if tree.is_none() {
+            log::warn!("Parsing {} for rules timed out, skipping", path.display());
+        }
+        tree
+    }
+}
+
+/// 把单独的 CR 替换为 LF（CRLF 保持不变），没有单独的 CR 时返回 None
+fn lone_cr_as_lf(content: &str) -> Option<String> {
+    let bytes = content.as_bytes();
+    let is_lone_cr = |i: usize| bytes[i] == b'\r' && bytes.get(i + 1) != Some(&b'\n');
+    if !(0..bytes.len()).any(is_lone_cr) {
+        return None;
+    }
+    let normalized = (0..bytes.len())
+        .map(|i| if is_lone_cr(i) { b'\n' } else { bytes[i] })
+        .collect();
+    // 只替换 ASCII 字节，结果仍是合法的 UTF-8
+    String::from_utf8(normalized).ok()
+}
+
+/// 对单个文件执行一条规则；Tree-sitter 规则使用预先解析好的语法树
+fn evaluate_rule(compiled: &CompiledRule, path: &PathBuf, content: &str, tree: Option<&Tree>) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    match &compiled.matcher {
+        RuleMatcher::Regex(regex) => {
+            for cap in regex.captures_iter(content) {
+                if let Some(m) = cap.get(0) {
+                    let start_pos = m.start();
+                    let end_pos = m.end();
+
+                    // Convert byte offset to line number
+                    let line_start = line_number_at(content, start_pos);
+                    let line_end = line_number_at(content, end_pos);
+
+                    findings.push(create_finding(
+                        &compiled.rule,
+                        path,
+                        line_start,
+                        line_end,
+                        format!("RegexRule: {}", compiled.rule.id),
+                    ));
                 }
             }
         }
+        RuleMatcher::TreeSitter(query) => {
+            let Some(tree) = tree else {
+                return findings;
+            };
 
-        findings
+            let mut cursor = QueryCursor::new();
+            let matches = cursor.matches(query, tree.root_node(), content.as_bytes());
+
+            let capture_names = query.capture_names();
+
+            for m in matches {
+                if compiled.rule.report_all_captures {
+                    // 每个捕获各报一条，以 `_` 开头的捕获视为辅助捕获，不单独报告
+                    for capture in m
+                        .captures
+                        .iter()
+                        .filter(|c| !capture_names[c.index as usize].starts_with('_'))
+                    {
+                        let node = capture.node;
+                        let mut finding = create_finding(
+                            &compiled.rule,
+                            path,
+                            line_number_at(content, node.start_byte()),
+                            line_number_at(content, node.end_byte()),
+                            format!("ASTRule: {}", compiled.rule.id),
+                        );
+                        finding.capture = Some(capture_names[capture.index as usize].to_string());
+                        findings.push(finding);
+                    }
+                    continue;
+                }
+
+                // 优先用 `@match` / `@location` 捕获定位，没有时使用整个匹配覆盖的范围
+                let location = m.captures.iter().find(|c| {
+                    LOCATION_CAPTURE_NAMES.contains(&capture_names[c.index as usize])
+                });
+                let (line_start, line_end, capture) = match location {
+                    Some(capture) => (
+                        line_number_at(content, capture.node.start_byte()),
+                        line_number_at(content, capture.node.end_byte()),
+                        Some(capture_names[capture.index as usize].to_string()),
+                    ),
+                    None => {
+                        // 行号按字节偏移计算，以支持只用 CR 换行的文件
+                        let Some(start) = m.captures.iter().map(|c| c.node.start_byte()).min() else {
+                            continue;
+                        };
+                        let end = m
+                            .captures
+                            .iter()
+                            .map(|c| c.node.end_byte())
+                            .max()
+                            .unwrap_or(start);
+                        (line_number_at(content, start), line_number_at(content, end), None)
+                    }
+                };
+
+                let mut finding = create_finding(
+                    &compiled.rule,
+                    path,
+                    line_start,
+                    line_end,
+                    format!("ASTRule: {}", compiled.rule.id),
+                );
+                finding.capture = capture;
+                findings.push(finding);
+            }
+        }
     }
+
+    findings
 }
 
 fn create_finding(
@@ -146,48 +309,404 @@ fn create_finding(
     line_end: usize,
     detector: String,
 ) -> Finding {
+    let file_path = normalize_finding_path(path);
+    let vuln_type = rule.cwe.clone().unwrap_or_else(|| "Unknown".to_string());
     Finding {
-        finding_id: Uuid::new_v4().to_string(),
-        file_path: path.to_string_lossy().to_string(),
+        finding_id: stable_finding_id(&file_path, line_start, &vuln_type, &detector),
+        file_path,
         line_start,
         line_end,
         detector,
-        vuln_type: rule.cwe.clone().unwrap_or_else(|| "Unknown".to_string()),
-        severity: format!("{:?}", rule.severity).to_lowercase(),
+        vuln_type,
+        severity: rule.severity.as_str().to_string(),
         description: rule.description.clone(),
+        capture: None,
         analysis_trail: None,
         llm_output: None,
+        suppressed: false,
+        author: None,
+        commit: None,
+        enclosing_symbol: None,
+        enclosing_symbol_kind: None,
+        original_severity: None,
     }
 }
 
-fn get_language_for_rule(language: &str) -> Option<Language> {
-    match language.to_lowercase().as_str() {
-        "python" => Some(tree_sitter_python::LANGUAGE.into()),
-        "javascript" => Some(tree_sitter_javascript::LANGUAGE.into()),
-        "typescript" => Some(tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into()),
-        "rust" => Some(tree_sitter_rust::LANGUAGE.into()),
-        "go" => Some(tree_sitter_go::LANGUAGE.into()),
-        "java" => Some(tree_sitter_java::LANGUAGE.into()),
-        "c" => Some(tree_sitter_c::LANGUAGE.into()),
-        "cpp" => Some(tree_sitter_cpp::LANGUAGE.into()),
-        "json" => Some(tree_sitter_json::LANGUAGE.into()),
-        "html" => Some(tree_sitter_html::LANGUAGE.into()),
-        _ => None,
+/// 编译单条规则：有 query 时编译 Tree-sitter 查询（优先），否则编译正则；同时编译路径范围。
+/// 只含空白的 pattern / query 视为未设置，两者都没有时返回错误
+pub fn compile_rule(rule: &Rule) -> Result<CompiledRule, String> {
+    let scope = PathScope::compile(rule)?;
+    if let Some(query_str) = rule.non_blank_query() {
+        let (lang, query) = compile_query(&rule.language, query_str)?;
+        Ok(CompiledRule {
+            rule: rule.clone(),
+            matcher: RuleMatcher::TreeSitter(query),
+            language: Some(lang),
+            scope,
+        })
+    } else if let Some(pattern) = rule.non_blank_pattern() {
+        let regex = Regex::new(pattern).map_err(|e| format!("Invalid regex pattern: {}", e))?;
+        Ok(CompiledRule {
+            rule: rule.clone(),
+            matcher: RuleMatcher::Regex(regex),
+            language: None,
+            scope,
+        })
+    } else {
+        Err("Rule has neither a non-empty pattern nor a query".to_string())
     }
 }
 
-fn rule_matches_extension(language: &str, extension: &str) -> bool {
-    match language.to_lowercase().as_str() {
-        "python" => extension == "py",
-        "javascript" | "typescript" => {
-            ["js", "jsx", "ts", "tsx", "mjs", "cjs"].contains(&extension)
+/// 检查规则能否编译（与扫描时相同的逻辑），返回编译错误
+pub fn validate_rule(rule: &Rule) -> Result<(), String> {
+    compile_rule(rule).map(|_| ())
+}
+
+/// 编译指定语言的 Tree-sitter 查询，语言不支持或查询语法错误时返回错误信息
+pub fn compile_query(language: &RuleLanguage, query_str: &str) -> Result<(Language, Query), String> {
+    let lang = language
+        .grammar()
+        .ok_or_else(|| format!("Unsupported language for Tree-sitter query: {}", language))?;
+    let query = Query::new(&lang, query_str)
+        .map_err(|e| format!("Invalid Tree-sitter query: {}", e))?;
+    Ok((lang, query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api_only_rule() -> Rule {
+        serde_yaml::from_str(
+            r#"
+id: "api-eval"
+name: "Eval in API"
+description: "eval in API handlers"
+severity: "high"
+language: "python"
+pattern: "eval\\("
+include_paths: ["**/api/**"]
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn path_scope_matches_scope_path() {
+        let scanner = RuleScanner::new(vec![api_only_rule()]);
+        let content = "eval(data)\n";
+
+        let api = scanner.scan_file_in_scope(&PathBuf::from("/srv/app/api/users.py"), Path::new("api/users.py"), content);
+        assert_eq!(api.len(), 1);
+        let nested = scanner.scan_file_in_scope(&PathBuf::from("src/api/v1/users.py"), Path::new("src/api/v1/users.py"), content);
+        assert_eq!(nested.len(), 1);
+
+        // 只看范围路径，扫描位置的上级目录名不影响匹配
+        let web = scanner.scan_file_in_scope(&PathBuf::from("/srv/api/app/web/views.py"), Path::new("web/views.py"), content);
+        assert!(web.is_empty());
+    }
+
+    /// 收集 warn 及以上级别日志的测试 logger（全局只能安装一次，其他测试的日志也会被收集）
+    struct CapturingLogger(std::sync::Mutex<Vec<String>>);
+
+    impl log::Log for CapturingLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Warn
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                self.0.lock().unwrap().push(format!("{} {}", record.level(), record.args()));
+            }
         }
-        "rust" => extension == "rs",
-        "go" => extension == "go",
-        "java" => extension == "java",
-        "c" => extension == "c" || extension == "h",
-        "cpp" => ["cpp", "hpp", "cc", "cxx"].contains(&extension),
-        "all" | "*" => true,
-        _ => language.eq_ignore_ascii_case(extension),
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CapturingLogger = CapturingLogger(std::sync::Mutex::new(Vec::new()));
+
+    fn captured_logs() -> Vec<String> {
+        static INSTALL: std::sync::Once = std::sync::Once::new();
+        INSTALL.call_once(|| {
+            log::set_logger(&LOGGER).unwrap();
+            log::set_max_level(log::LevelFilter::Warn);
+        });
+        LOGGER.0.lock().unwrap().clone()
+    }
+
+    #[test]
+    fn rule_compile_failure_is_logged_as_warning() {
+        captured_logs();
+        let broken: Rule = serde_yaml::from_str(
+            r#"
+id: "broken-regex"
+name: "Broken"
+description: "unbalanced group"
+severity: "low"
+language: "python"
+pattern: "eval(("
+"#,
+        )
+        .unwrap();
+        let scanner = RuleScanner::new(vec![broken, api_only_rule()]);
+        assert_eq!(scanner.rule_count(), 1);
+        assert!(captured_logs()
+            .iter()
+            .any(|line| line.starts_with("WARN Skipping rule broken-regex: Invalid regex pattern")));
+    }
+
+    fn many_rules() -> Vec<Rule> {
+        (0..48)
+            .map(|i| {
+                let matcher = if i % 2 == 0 {
+                    format!("pattern: \"call_{}\\\\(\"", i % 8)
+                } else {
+                    format!(
+                        "query: \"(call function: (identifier) @match (#eq? @match \\\"call_{}\\\"))\"",
+                        i % 8
+                    )
+                };
+                serde_yaml::from_str(&format!(
+                    "id: \"rule-{:02}\"\nname: \"Rule {}\"\ndescription: \"d\"\nseverity: \"medium\"\nlanguage: \"python\"\npriority: {}\n{}\n",
+                    i,
+                    i,
+                    i % 3,
+                    matcher
+                ))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn summary(findings: &[Finding]) -> Vec<(String, usize, usize, String)> {
+        findings
+            .iter()
+            .map(|f| (f.detector.clone(), f.line_start, f.line_end, f.finding_id.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn parallel_rule_evaluation_matches_sequential() {
+        let scanner = RuleScanner::new(many_rules());
+        assert_eq!(scanner.rule_count(), 48);
+        let path = PathBuf::from("app.py");
+        let content: String = (0..200).map(|i| format!("call_{}(x)\n", i % 8)).collect();
+
+        let lang = scanner.compiled_rules.iter().find_map(|c| c.language.clone()).unwrap();
+        let tree = scanner.parse(&lang, &path, &content);
+        let sequential: Vec<Finding> = scanner
+            .compiled_rules
+            .iter()
+            .flat_map(|compiled| evaluate_rule(compiled, &path, &content, tree.as_ref()))
+            .collect();
+        assert!(sequential.iter().any(|f| f.detector.starts_with("ASTRule")));
+        assert!(sequential.iter().any(|f| f.detector.starts_with("RegexRule")));
+
+        for _ in 0..5 {
+            let parallel = scanner.scan_file_in_scope(&path, &path, &content);
+            assert_eq!(summary(&parallel), summary(&sequential));
+        }
+    }
+
+    #[test]
+    fn parse_timeout_skips_tree_sitter_rules_only() {
+        let ast_rule: Rule = serde_yaml::from_str(
+            r#"
+id: "ast-eval"
+name: "Eval"
+description: "eval call"
+severity: "high"
+language: "python"
+query: "(call function: (identifier) @match (#eq? @match \"eval\"))"
+"#,
+        )
+        .unwrap();
+        let scanner = RuleScanner::new(vec![ast_rule, api_only_rule()]).with_parse_timeout(1);
+        let path = PathBuf::from("api/big.py");
+        let mut content: String = (0..50_000).map(|i| format!("x{} = compute(data)\n", i)).collect();
+        content.push_str("eval(data)\n");
+
+        let findings = scanner.scan_file_in_scope(&path, &path, &content);
+        let detectors: Vec<&str> = findings.iter().map(|f| f.detector.as_str()).collect();
+        assert_eq!(detectors, vec!["RegexRule: api-eval"]);
+    }
+
+    fn capture_rule(query: &str, report_all_captures: bool) -> Rule {
+        serde_yaml::from_str(&format!(
+            "id: \"eval-source\"\nname: \"Eval\"\ndescription: \"d\"\nseverity: \"high\"\nlanguage: \"python\"\nquery: '{}'\nreport_all_captures: {}\n",
+            query, report_all_captures
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn capture_names_are_reported_per_flag() {
+        let content = "x = 1\neval(\n    data)\n";
+        let path = PathBuf::from("app.py");
+        let two_captures = r#"(call function: (identifier) @sink (#eq? @sink "eval") arguments: (argument_list (identifier) @source))"#;
+        let report = |rule: Rule| {
+            let mut findings = RuleScanner::new(vec![rule]).scan_file_in_scope(&path, &path, content);
+            findings.sort_by_key(|f| f.line_start);
+            findings
+                .into_iter()
+                .map(|f| (f.capture, f.line_start, f.line_end))
+                .collect::<Vec<_>>()
+        };
+
+        // 默认每个匹配一条，没有 @match 时覆盖整个匹配范围
+        assert_eq!(report(capture_rule(two_captures, false)), vec![(None, 2, 3)]);
+        assert_eq!(
+            report(capture_rule(two_captures, true)),
+            vec![(Some("sink".to_string()), 2, 2), (Some("source".to_string()), 3, 3)]
+        );
+
+        let located = two_captures.replace("@source", "@match");
+        assert_eq!(report(capture_rule(&located, false)), vec![(Some("match".to_string()), 3, 3)]);
+
+        // `_` 开头的辅助捕获不单独报告
+        let helper = two_captures.replace("@sink", "@_sink");
+        assert_eq!(report(capture_rule(&helper, true)), vec![(Some("source".to_string()), 3, 3)]);
+    }
+
+    #[test]
+    fn findings_locate_to_match_or_location_capture() {
+        // 第一个捕获 @context 覆盖整个函数，@match 只是其中的 eval 调用
+        let content = "def handler(data):\n    x = 1\n    eval(data)\n";
+        let path = PathBuf::from("app.py");
+        let query = r#"(function_definition body: (block (expression_statement (call function: (identifier) @name (#eq? @name "eval")) @match))) @context"#;
+        let locate = |query: &str| {
+            RuleScanner::new(vec![capture_rule(query, false)])
+                .scan_file_in_scope(&path, &path, content)
+                .into_iter()
+                .map(|f| (f.capture, f.line_start, f.line_end))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(locate(query), vec![(Some("match".to_string()), 3, 3)]);
+        assert_eq!(
+            locate(&query.replace("@match", "@location")),
+            vec![(Some("location".to_string()), 3, 3)]
+        );
+        // 没有定位捕获时使用整个匹配
+        assert_eq!(locate(&query.replace("@match", "@call")), vec![(None, 1, 3)]);
+    }
+
+    #[test]
+    fn bundled_deserialization_pack_flags_unsafe_yaml_and_pickle() {
+        let rules = crate::rules::loader::parse_rules(include_str!("../../../rules/insecure-deserialization.yaml")).unwrap();
+        let scanner = RuleScanner::new(rules);
+        let path = PathBuf::from("app.py");
+        let flagged = |content: &str| {
+            scanner
+                .scan_file_in_scope(&path, &path, content)
+                .into_iter()
+                .map(|f| f.detector)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(flagged("import yaml\nyaml.load(data)\n"), ["ASTRule: deserialization-python-yaml-load"]);
+        assert_eq!(flagged("import pickle\npickle.loads(data)\n"), ["ASTRule: deserialization-python-pickle"]);
+        assert!(flagged("import yaml\nyaml.safe_load(data)\n").is_empty());
+        assert!(flagged("import yaml\nyaml.load(data, Loader=yaml.SafeLoader)\n").is_empty());
+    }
+
+    #[test]
+    fn rules_are_evaluated_by_priority_then_id() {
+        let rule = |id: &str, priority: Option<i32>| -> Rule {
+            let priority = priority.map(|p| format!("priority: {}\n", p)).unwrap_or_default();
+            serde_yaml::from_str(&format!(
+                "id: \"{}\"\nname: \"R\"\ndescription: \"d\"\nseverity: \"low\"\nlanguage: \"python\"\npattern: \"eval\"\n{}",
+                id, priority
+            ))
+            .unwrap()
+        };
+        let rules = vec![
+            rule("c-default", None),
+            rule("b-zero", Some(0)),
+            rule("z-high", Some(10)),
+            rule("a-default", None),
+            rule("y-negative", Some(-5)),
+        ];
+        let path = PathBuf::from("app.py");
+        let order = |rules: Vec<Rule>| {
+            RuleScanner::new(rules)
+                .scan_file_in_scope(&path, &path, "eval(x)\n")
+                .into_iter()
+                .map(|f| f.detector)
+                .collect::<Vec<_>>()
+        };
+
+        let expected = [
+            "RegexRule: z-high",
+            "RegexRule: a-default",
+            "RegexRule: b-zero",
+            "RegexRule: c-default",
+            "RegexRule: y-negative",
+        ];
+        assert_eq!(order(rules.clone()), expected);
+        // 与加载顺序无关
+        assert_eq!(order(rules.into_iter().rev().collect()), expected);
+    }
+
+    #[test]
+    fn lone_cr_line_endings_report_the_same_lines_as_lf() {
+        let ast_rule: Rule = serde_yaml::from_str(
+            r#"
+id: "ast-eval"
+name: "Eval"
+description: "eval call"
+severity: "high"
+language: "python"
+query: "(call function: (identifier) @match (#eq? @match \"eval\"))"
+"#,
+        )
+        .unwrap();
+        let scanner = RuleScanner::new(vec![ast_rule, api_only_rule()]);
+        let path = PathBuf::from("api/app.py");
+        let lines = |line_ending: &str| {
+            let content = ["import os", "", "x = load()", "eval(x)", "y = 1", "eval(y)", ""].join(line_ending);
+            let mut found: Vec<(String, usize, usize)> = scanner
+                .scan_file_in_scope(&path, &path, &content)
+                .into_iter()
+                .map(|f| (f.detector, f.line_start, f.line_end))
+                .collect();
+            found.sort();
+            found
+        };
+
+        let expected = vec![
+            ("ASTRule: ast-eval".to_string(), 4, 4),
+            ("ASTRule: ast-eval".to_string(), 6, 6),
+            ("RegexRule: api-eval".to_string(), 4, 4),
+            ("RegexRule: api-eval".to_string(), 6, 6),
+        ];
+        assert_eq!(lines("\n"), expected);
+        assert_eq!(lines("\r\n"), expected);
+        assert_eq!(lines("\r"), expected);
+    }
+
+    #[test]
+    fn blank_patterns_and_queries_are_skipped() {
+        let rule = |id: &str, matcher: &str| -> Rule {
+            serde_yaml::from_str(&format!(
+                "id: \"{}\"\nname: \"n\"\ndescription: \"d\"\nseverity: \"high\"\nlanguage: \"python\"\n{}\n",
+                id, matcher
+            ))
+            .unwrap()
+        };
+        let scanner = RuleScanner::new(vec![
+            rule("empty-pattern", "pattern: \"\""),
+            rule("blank-pattern", "pattern: \"   \""),
+            rule("blank-query", "query: \" \""),
+            // 空 query 不会遮蔽有效的 pattern
+            rule("blank-query-with-pattern", "query: \"\"\npattern: \"eval\\\\(\""),
+        ]);
+        assert_eq!(scanner.rule_count(), 1);
+
+        let path = PathBuf::from("app.py");
+        let findings = scanner.scan_file_in_scope(&path, &path, "x = 1\neval(data)\n");
+        let found: Vec<(&str, usize)> = findings.iter().map(|f| (f.detector.as_str(), f.line_start)).collect();
+        assert_eq!(found, vec![("RegexRule: blank-query-with-pattern", 2)]);
+        assert!(compile_rule(&rule("empty", "pattern: \"\"")).is_err());
     }
 }
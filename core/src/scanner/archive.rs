@@ -0,0 +1,171 @@
+// Archive scanning - 在内存中扫描压缩包，无需解压到磁盘
+
+use super::{
+    apply_file_options, assign_finding_ids, content_hash, effective_ruleset_hash, is_supported_file, scan_file_before,
+    scan_deadline, sort_findings, DefaultScanners, FileHash, Finding, ScanOptions, ScanReport, ScannedFile,
+    StageTimings,
+};
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+
+/// 单个压缩包条目的最大扫描大小（超过则跳过）
+const MAX_ENTRY_SIZE: u64 = 10 * 1024 * 1024;
+
+/// 扫描 ZIP 压缩包中的所有支持文件
+///
+/// 条目内容直接读入内存，并以条目名（如 `src/app.py`）作为 Finding 的文件路径。
+pub async fn scan_archive(zip_bytes: &[u8]) -> Result<Vec<Finding>, String> {
+    Ok(scan_archive_report(zip_bytes, &ScanOptions::default()).await?.findings)
+}
+
+/// 按扫描选项扫描 ZIP 压缩包，报告中的 files 为实际扫描的条目
+///
+/// 与目录扫描相同地应用规则、扩展名过滤、内置扫描器开关、单文件上限、严重程度改写、
+/// 仅提示规则和时间上限；repo_root、结果缓存、增量扫描和 git blame 对压缩包没有意义，被忽略。
+pub async fn scan_archive_report(zip_bytes: &[u8], options: &ScanOptions) -> Result<ScanReport, String> {
+    let started = std::time::Instant::now();
+    let mut timings = StageTimings::default();
+    let scanners = std::sync::Arc::new(DefaultScanners::for_options(options)?);
+    let ruleset_hash = effective_ruleset_hash(scanners.ruleset_hash(), options);
+    let mut symbol_parser = options.enclosing_symbols.then(crate::ast::ASTParser::new);
+    let deadline = scan_deadline(options);
+
+    let read_started = std::time::Instant::now();
+    let entries = read_archive_entries(zip_bytes)?;
+    timings.read += read_started.elapsed();
+
+    let mut findings = Vec::new();
+    let mut files = Vec::new();
+    let mut timed_out = false;
+    for (index, (name, content)) in entries.iter().enumerate() {
+        let path = PathBuf::from(name);
+        if !options.should_scan(&path) {
+            continue;
+        }
+        if deadline.is_some_and(|deadline| tokio::time::Instant::now() >= deadline) {
+            timed_out = true;
+            log::warn!("Archive scan exceeded time limit after {} of {} entries", index, entries.len());
+            break;
+        }
+
+        let Some(mut file_findings) =
+            scan_file_before(&scanners, deadline, &path, &path, content, &mut timings).await
+        else {
+            timed_out = true;
+            log::warn!("Archive scan exceeded time limit while scanning {}", name);
+            break;
+        };
+        apply_file_options(&mut file_findings, &path, content, options, symbol_parser.as_mut());
+        findings.append(&mut file_findings);
+        files.push(ScannedFile {
+            file_path: name.clone(),
+            hash: FileHash {
+                content_hash: content_hash(content),
+                ruleset_hash: ruleset_hash.clone(),
+            },
+            rescanned: true,
+        });
+    }
+
+    sort_findings(&mut findings);
+    assign_finding_ids(&mut findings, options.finding_id_scope.as_deref());
+    Ok(ScanReport {
+        findings,
+        files,
+        timed_out,
+        metrics: timings.into_metrics(started.elapsed()),
+    })
+}
+
+/// 读取压缩包中所有可扫描的文本条目：(条目名, 内容)
+fn read_archive_entries(zip_bytes: &[u8]) -> Result<Vec<(String, String)>, String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(zip_bytes))
+        .map_err(|e| format!("Failed to open zip archive: {}", e))?;
+
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let mut file = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to read zip entry {}: {}", i, e))?;
+
+        if file.is_dir() {
+            continue;
+        }
+
+        let name = file.name().replace('\\', "/");
+        if !is_supported_file(Path::new(&name)) {
+            continue;
+        }
+
+        if file.size() > MAX_ENTRY_SIZE {
+            log::warn!("Skipping oversized archive entry {} ({} bytes)", name, file.size());
+            continue;
+        }
+
+        // 声明的大小可能不可信，读取时再限制一次
+        let mut content = String::new();
+        if let Err(e) = (&mut file).take(MAX_ENTRY_SIZE).read_to_string(&mut content) {
+            log::warn!("Skipping unreadable archive entry {}: {}", name, e);
+            continue;
+        }
+
+        entries.push((name, content));
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn zip_of(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            writer
+                .start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[tokio::test]
+    async fn archive_report_counts_scanned_entries_and_applies_options() {
+        let zip = zip_of(&[
+            ("src/b.py", "# TODO: one\n# TODO: two\n# TODO: three\n"),
+            ("src/a.py", "import hashlib\nhashlib.md5(data)\n# TODO: later\n"),
+            ("src/clean.py", "print('ok')\n"),
+            ("README.txt", "TODO: not scanned\n"),
+        ]);
+        let options = ScanOptions {
+            max_findings_per_file: Some(2),
+            ignore_vuln_types: vec!["Weak Crypto".to_string()],
+            rules: Some(std::sync::Arc::new(crate::scanner::LoadedRules::load_from_dir(Path::new("no-such-rules")))),
+            ..Default::default()
+        };
+
+        let report = scan_archive_report(&zip, &options).await.unwrap();
+
+        let files: Vec<&str> = report.files.iter().map(|f| f.file_path.as_str()).collect();
+        assert_eq!(files, vec!["src/b.py", "src/a.py", "src/clean.py"]);
+        assert!(!report.findings.iter().any(|f| f.vuln_type == "Weak Crypto"));
+
+        // 单文件上限为 2，第三条 TODO 被截断为一条提示发现
+        let b_detectors: Vec<&str> = report
+            .findings
+            .iter()
+            .filter(|f| f.file_path == "src/b.py")
+            .map(|f| f.detector.as_str())
+            .collect();
+        assert_eq!(b_detectors.len(), 3);
+        assert_eq!(b_detectors.iter().filter(|d| **d == "ScanLimiter").count(), 1);
+
+        // 输出按路径和行号排序
+        let positions: Vec<(&str, usize)> = report.findings.iter().map(|f| (f.file_path.as_str(), f.line_start)).collect();
+        let mut sorted = positions.clone();
+        sorted.sort();
+        assert_eq!(positions, sorted);
+    }
+}
@@ -0,0 +1,87 @@
+// Git blame - 为发现附加最后修改该行的作者和提交
+
+use super::Finding;
+use std::collections::HashMap;
+use std::path::Path;
+use tokio::process::Command;
+
+/// 某一行的 blame 信息：(提交, 作者)
+type BlameLine = (String, String);
+
+/// 判断目录是否位于 git 工作区中
+pub(crate) async fn is_git_work_tree(path: &Path) -> bool {
+    Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .output()
+        .await
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// 对单个文件执行一次 `git blame`，为该文件的所有发现填充 author / commit
+///
+/// 文件未被跟踪或 git 执行失败时保持发现不变
+pub(crate) async fn attach_blame(file: &Path, findings: &mut [Finding]) {
+    if findings.is_empty() {
+        return;
+    }
+
+    let Some(lines) = blame_file(file).await else {
+        return;
+    };
+
+    for finding in findings {
+        if let Some((commit, author)) = lines.get(&finding.line_start) {
+            finding.commit = Some(commit.clone());
+            finding.author = Some(author.clone());
+        }
+    }
+}
+
+/// 解析 `git blame --line-porcelain` 输出：最终行号 -> (提交, 作者)
+async fn blame_file(file: &Path) -> Option<HashMap<usize, BlameLine>> {
+    let dir = file.parent()?;
+    let name = file.file_name()?;
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["blame", "--line-porcelain", "--"])
+        .arg(name)
+        .output()
+        .await
+        .ok()?;
+
+    if !output.status.success() {
+        log::debug!(
+            "git blame failed for {}: {}",
+            file.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return None;
+    }
+
+    let mut lines = HashMap::new();
+    let mut current: Option<(String, usize)> = None;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(author) = line.strip_prefix("author ") {
+            if let Some((commit, line_number)) = current.take() {
+                lines.insert(line_number, (commit, author.to_string()));
+            }
+        } else if !line.starts_with('\t') {
+            // 每行的头部：<sha> <原行号> <最终行号> [<行数>]
+            let mut parts = line.split(' ');
+            if let (Some(sha), Some(_), Some(final_line)) = (parts.next(), parts.next(), parts.next()) {
+                if sha.len() == 40 && sha.chars().all(|c| c.is_ascii_hexdigit()) {
+                    if let Ok(final_line) = final_line.parse() {
+                        current = Some((sha.to_string(), final_line));
+                    }
+                }
+            }
+        }
+    }
+
+    Some(lines)
+}
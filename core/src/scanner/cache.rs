@@ -0,0 +1,99 @@
+// Findings cache - 按文件内容哈希缓存扫描结果
+//
+// 缓存键为 (内容哈希, 规则集哈希)：文件内容或规则集（内置检测器、YAML 规则、熵阈值）
+// 任一变化都会使缓存失效。缓存文件与 AST 缓存存放在同一仓库目录下。
+
+use super::{normalize_finding_path, Finding};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const FINDINGS_CACHE_FILE: &str = "findings_cache.json";
+
+pub(crate) struct FindingsCache {
+    cache_file: PathBuf,
+    entries: HashMap<String, Vec<Finding>>,
+    /// 本次扫描用到的键，保存时只保留这些条目，避免缓存无限增长
+    used: HashSet<String>,
+    hits: usize,
+    misses: usize,
+}
+
+impl FindingsCache {
+    /// 加载 `cache_dir` 下扫描根目录对应的缓存，文件不存在或损坏时从空缓存开始
+    pub(crate) fn load(cache_dir: &Path, scan_root: &str) -> Self {
+        let cache_file = cache_dir
+            .join(crate::ast::cache::repository_cache_key(scan_root))
+            .join(FINDINGS_CACHE_FILE);
+
+        let entries = match fs::read_to_string(&cache_file) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                log::warn!("Ignoring corrupt findings cache {}: {}", cache_file.display(), e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+
+        Self {
+            cache_file,
+            entries,
+            used: HashSet::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// `content_hash` 为 [`super::content_hash`] 的结果
+    pub(crate) fn key(content_hash: &str, ruleset_hash: &str) -> String {
+        format!("{}:{}", content_hash, ruleset_hash)
+    }
+
+    /// 命中时返回缓存的发现，file_path 改为当前路径（finding_id 由调用方按最终路径重新计算）
+    pub(crate) fn get(&mut self, key: &str, path: &Path) -> Option<Vec<Finding>> {
+        self.used.insert(key.to_string());
+        let Some(cached) = self.entries.get(key) else {
+            self.misses += 1;
+            return None;
+        };
+
+        self.hits += 1;
+        let file_path = normalize_finding_path(path);
+        Some(
+            cached
+                .iter()
+                .cloned()
+                .map(|mut finding| {
+                    finding.file_path = file_path.clone();
+                    finding
+                })
+                .collect(),
+        )
+    }
+
+    pub(crate) fn insert(&mut self, key: String, findings: &[Finding]) {
+        self.used.insert(key.clone());
+        self.entries.insert(key, findings.to_vec());
+    }
+
+    /// `prune` 为 true 时只保留本次扫描用到的条目
+    pub(crate) fn save(mut self, prune: bool) {
+        log::info!("Findings cache: {} hits, {} misses", self.hits, self.misses);
+
+        if prune {
+            let used = std::mem::take(&mut self.used);
+            self.entries.retain(|key, _| used.contains(key));
+        }
+
+        let result = self
+            .cache_file
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .map_err(|e| e.to_string())
+            .and_then(|_| serde_json::to_string(&self.entries).map_err(|e| e.to_string()))
+            .and_then(|json| fs::write(&self.cache_file, json).map_err(|e| e.to_string()));
+
+        if let Err(e) = result {
+            log::warn!("Failed to save findings cache {}: {}", self.cache_file.display(), e);
+        }
+    }
+}
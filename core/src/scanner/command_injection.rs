@@ -0,0 +1,350 @@
+// Command injection - 跨语言的命令执行调用点检测
+//
+// 对 AST 解析器支持的语言，基于解析出的 MethodCall 符号匹配调用目标，避免命中注释和字符串；
+// 解析器不支持的语言（Go、C/C++）或解析失败时回退到正则匹配。
+
+use super::regex_scanner::DetectorInfo;
+use super::{line_number_at, normalize_finding_path, stable_finding_id, Finding, Scanner};
+use crate::ast::{ASTParser, SymbolKind};
+use crate::rules::model::Severity;
+use async_trait::async_trait;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const VULN_TYPE: &str = "Command Injection";
+const SEVERITY: Severity = Severity::High;
+const CWE: &str = "CWE-78";
+
+/// 某种语言的命令执行调用目标
+struct SinkSet {
+    language: &'static str,
+    extensions: &'static [&'static str],
+    /// 调用目标的源码写法（如 `os.system`、`std::process::Command::new`）
+    sinks: &'static [&'static str],
+    /// 是否有 AST 解析器支持，否则只使用正则
+    parsed: bool,
+}
+
+const NODE_CHILD_PROCESS_FUNCTIONS: &[&str] =
+    &["exec", "execSync", "execFile", "execFileSync", "spawn", "spawnSync"];
+
+const SINK_SETS: &[SinkSet] = &[
+    SinkSet {
+        language: "Python",
+        extensions: &["py"],
+        sinks: &[
+            "os.system",
+            "os.popen",
+            "os.execl",
+            "os.execlp",
+            "os.execv",
+            "os.execvp",
+            "subprocess.run",
+            "subprocess.call",
+            "subprocess.check_call",
+            "subprocess.check_output",
+            "subprocess.Popen",
+            "subprocess.getoutput",
+            "subprocess.getstatusoutput",
+        ],
+        parsed: true,
+    },
+    SinkSet {
+        language: "JavaScript",
+        extensions: &["js", "jsx", "ts", "tsx"],
+        sinks: &[
+            "child_process.exec",
+            "child_process.execSync",
+            "child_process.execFile",
+            "child_process.execFileSync",
+            "child_process.spawn",
+            "child_process.spawnSync",
+        ],
+        parsed: true,
+    },
+    SinkSet {
+        language: "Java",
+        extensions: &["java"],
+        sinks: &["Runtime.getRuntime().exec"],
+        parsed: true,
+    },
+    SinkSet {
+        language: "Rust",
+        extensions: &["rs"],
+        sinks: &["std::process::Command::new", "process::Command::new", "Command::new"],
+        parsed: true,
+    },
+    SinkSet {
+        language: "Ruby",
+        extensions: &["rb"],
+        sinks: &[
+            "system",
+            "exec",
+            "spawn",
+            "Kernel.system",
+            "Kernel.exec",
+            "IO.popen",
+            "Open3.capture2",
+            "Open3.capture2e",
+            "Open3.capture3",
+            "Open3.popen2",
+            "Open3.popen3",
+        ],
+        parsed: true,
+    },
+    SinkSet {
+        language: "Go",
+        extensions: &["go"],
+        sinks: &["exec.Command", "exec.CommandContext"],
+        parsed: false,
+    },
+    SinkSet {
+        language: "C/C++",
+        extensions: &["c", "h", "cpp", "hpp", "cc"],
+        sinks: &["system", "popen", "execl", "execlp", "execv", "execvp", "execvpe"],
+        parsed: false,
+    },
+];
+
+/// 统一成员访问写法，便于比较不同语言的调用目标
+pub(super) fn normalize_callee(text: &str) -> String {
+    text.replace("?.", ".").replace("::", ".")
+}
+
+/// 调用代码是否以 `callee` 开头且紧跟参数列表
+fn calls(code: &str, callee: &str, allow_bare_args: bool) -> bool {
+    let code = normalize_callee(code);
+    let Some(rest) = code.strip_prefix(&normalize_callee(callee)) else {
+        return false;
+    };
+    if rest.trim_start().starts_with('(') {
+        return true;
+    }
+    // Ruby 允许省略括号，如 `system "ls"`
+    allow_bare_args && rest.starts_with([' ', '\t'])
+}
+
+/// 命令执行调用点扫描器（CWE-78）
+pub struct CommandInjectionScanner {
+    parser: Mutex<ASTParser>,
+    /// 每种语言一个正则，用于未解析语言及解析失败时的回退
+    fallback: Vec<Regex>,
+    /// Node 中 `require('child_process')` / `import ... from 'child_process'` 的别名
+    node_alias: Regex,
+}
+
+impl CommandInjectionScanner {
+    pub fn new() -> Self {
+        let fallback = SINK_SETS
+            .iter()
+            .map(|set| {
+                let alternatives: Vec<String> = set.sinks.iter().map(|sink| regex::escape(sink)).collect();
+                Regex::new(&format!(r"(?m)(?:^|[^\w.:])({})[ \t]*\(", alternatives.join("|")))
+                    .expect("builtin command sink pattern must compile")
+            })
+            .collect();
+
+        Self {
+            parser: Mutex::new(ASTParser::new()),
+            fallback,
+            node_alias: Regex::new(
+                r#"(?:(?:const|let|var)\s+(\w+)\s*=\s*require\(\s*['"](?:node:)?child_process['"]\s*\)|import\s+(?:\*\s+as\s+)?(\w+)\s+from\s+['"](?:node:)?child_process['"])"#,
+            )
+            .expect("builtin child_process alias pattern must compile"),
+        }
+    }
+
+    /// 列出各语言的命令执行调用目标
+    pub fn describe(&self) -> Vec<DetectorInfo> {
+        SINK_SETS
+            .iter()
+            .zip(&self.fallback)
+            .map(|(set, regex)| DetectorInfo {
+                vuln_type: VULN_TYPE.to_string(),
+                severity: SEVERITY.as_str().to_string(),
+                pattern: regex.as_str().to_string(),
+                description: format!("{} command execution sinks ({}): {}", set.language, CWE, set.sinks.join(", ")),
+            })
+            .collect()
+    }
+
+    /// 基于 AST 的 MethodCall 符号查找调用点，返回 (行号, 结束行号, 调用目标)；解析失败时返回 None
+    fn scan_parsed(&self, path: &Path, content: &str, set: &SinkSet) -> Option<Vec<(usize, usize, String)>> {
+        let symbols = {
+            let mut parser = self.parser.lock().unwrap_or_else(|e| e.into_inner());
+            parser.parse_file(path, content).ok()?
+        };
+
+        let is_node = set.language == "JavaScript";
+        let is_ruby = set.language == "Ruby";
+
+        // Node 中 child_process 的解构导入和别名
+        let mut node_callees: Vec<String> = Vec::new();
+        if is_node && content.contains("child_process") {
+            for caps in self.node_alias.captures_iter(content) {
+                if let Some(alias) = caps.get(1).or_else(|| caps.get(2)) {
+                    for function in NODE_CHILD_PROCESS_FUNCTIONS {
+                        node_callees.push(format!("{}.{}", alias.as_str(), function));
+                    }
+                }
+            }
+            node_callees.extend(NODE_CHILD_PROCESS_FUNCTIONS.iter().map(|f| f.to_string()));
+        }
+
+        // 链式调用（如 `Command::new("sh").arg(..).output()`）的外层调用代码同样以调用目标开头，
+        // 按 (起始行, 调用目标) 去重，保留范围最小的内层调用
+        let mut hits: Vec<(usize, usize, String)> = Vec::new();
+        for symbol in symbols.iter().filter(|s| s.kind == SymbolKind::MethodCall) {
+            let sink = set
+                .sinks
+                .iter()
+                .map(|sink| sink.to_string())
+                .chain(node_callees.iter().cloned())
+                .find(|sink| calls(&symbol.code, sink, is_ruby));
+
+            if let Some(sink) = sink {
+                let sink = if is_node && !sink.starts_with("child_process.") {
+                    let function = sink.rsplit('.').next().unwrap_or(&sink);
+                    format!("child_process.{}", function)
+                } else {
+                    sink
+                };
+                let (line_start, line_end) = (symbol.start_line as usize, symbol.end_line as usize);
+                match hits.iter_mut().find(|(start, _, existing)| *start == line_start && *existing == sink) {
+                    Some(hit) => hit.1 = hit.1.min(line_end),
+                    None => hits.push((line_start, line_end, sink)),
+                }
+            }
+        }
+        Some(hits)
+    }
+
+    fn scan_regex(&self, content: &str, regex: &Regex) -> Vec<(usize, usize, String)> {
+        regex
+            .captures_iter(content)
+            .filter_map(|caps| caps.get(1))
+            .map(|sink| {
+                let line = line_number_at(content, sink.start());
+                (line, line, sink.as_str().to_string())
+            })
+            .collect()
+    }
+}
+
+impl Default for CommandInjectionScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Scanner for CommandInjectionScanner {
+    fn name(&self) -> String {
+        "CommandInjectionScanner".to_string()
+    }
+
+    async fn scan_file(&self, path: &PathBuf, content: &str) -> Vec<Finding> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+
+        let Some((set, regex)) = SINK_SETS
+            .iter()
+            .zip(&self.fallback)
+            .find(|(set, _)| set.extensions.contains(&extension.as_str()))
+        else {
+            return Vec::new();
+        };
+
+        let hits = if set.parsed {
+            self.scan_parsed(path, content, set)
+                .unwrap_or_else(|| self.scan_regex(content, regex))
+        } else {
+            self.scan_regex(content, regex)
+        };
+
+        hits.into_iter()
+            .map(|(line_start, line_end, sink)| Finding {
+                finding_id: stable_finding_id(&normalize_finding_path(path), line_start, VULN_TYPE, &self.name()),
+                file_path: normalize_finding_path(path),
+                line_start,
+                line_end,
+                detector: self.name(),
+                vuln_type: VULN_TYPE.to_string(),
+                severity: SEVERITY.as_str().to_string(),
+                description: format!(
+                    "{} command execution sink `{}` at line {} ({}); make sure arguments are not user-controlled",
+                    set.language, sink, line_start, CWE
+                ),
+                capture: None,
+                analysis_trail: None,
+                llm_output: None,
+                suppressed: false,
+                author: None,
+                commit: None,
+                enclosing_symbol: None,
+                enclosing_symbol_kind: None,
+                original_severity: None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn sinks(name: &str, content: &str) -> Vec<(usize, String)> {
+        CommandInjectionScanner::new()
+            .scan_file(&PathBuf::from(name), content)
+            .await
+            .into_iter()
+            .map(|f| {
+                assert_eq!(f.vuln_type, VULN_TYPE);
+                let sink = f.description.split('`').nth(1).unwrap().to_string();
+                (f.line_start, sink)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn detects_python_sinks_but_not_comments_or_strings() {
+        let content = "\
+import os
+import subprocess
+# os.system(\"ls\") is dangerous
+os.system(cmd)
+msg = \"os.system(x)\"
+subprocess.run(args, shell=True)
+";
+        assert_eq!(
+            sinks("app.py", content).await,
+            vec![(4, "os.system".to_string()), (6, "subprocess.run".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn detects_node_child_process_calls_through_aliases() {
+        let content = "\
+const cp = require('child_process');
+const { exec } = require('child_process');
+cp.exec(cmd);
+exec(userInput);
+// child_process.exec(cmd)
+";
+        assert_eq!(
+            sinks("server.js", content).await,
+            vec![(3, "child_process.exec".to_string()), (4, "child_process.exec".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_regex_for_unparsed_languages() {
+        let content = "package main\n\nfunc run(c string) {\n\texec.Command(\"sh\", \"-c\", c)\n}\n";
+        assert_eq!(sinks("main.go", content).await, vec![(4, "exec.Command".to_string())]);
+        assert!(sinks("notes.txt", "os.system(cmd)\n").await.is_empty());
+    }
+}
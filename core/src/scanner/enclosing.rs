@@ -0,0 +1,35 @@
+// Enclosing symbol - 为发现附加所在的函数/方法
+
+use super::Finding;
+use crate::ast::query::innermost_symbol;
+use crate::ast::ASTParser;
+use std::path::Path;
+
+/// 解析文件并为每个发现填充起始行所在的最内层定义
+///
+/// 解析器不支持该文件类型或解析失败时保持发现不变
+pub(crate) fn attach_enclosing_symbols(
+    parser: &mut ASTParser,
+    file: &Path,
+    content: &str,
+    findings: &mut [Finding],
+) {
+    if findings.is_empty() {
+        return;
+    }
+
+    let symbols = match parser.parse_file(file, content) {
+        Ok(symbols) => symbols,
+        Err(e) => {
+            log::debug!("Cannot resolve enclosing symbols for {}: {}", file.display(), e);
+            return;
+        }
+    };
+
+    for finding in findings {
+        if let Some(symbol) = innermost_symbol(&symbols, finding.line_start as u32) {
+            finding.enclosing_symbol = Some(symbol.qualified_name());
+            finding.enclosing_symbol_kind = Some(symbol.kind_to_string());
+        }
+    }
+}
@@ -0,0 +1,259 @@
+// Git ref scanning - 直接从 git 对象库读取某个提交的文件并扫描，不检出、不改动工作区
+
+use super::{
+    apply_file_options, assign_finding_ids, content_hash, effective_ruleset_hash, looks_binary, scan_file_before,
+    scan_deadline, sort_findings, DefaultScanners, FileHash, Finding, ScanEvent, ScanOptions, ScanReport,
+    ScannedFile, StageTimings,
+};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+
+/// 单个文件的最大扫描大小（超过则跳过）
+const MAX_BLOB_SIZE: usize = 10 * 1024 * 1024;
+
+/// 符号链接在树中的文件模式，其 blob 内容是链接目标而不是文件内容
+const SYMLINK_FILE_MODE: i32 = 0o120000;
+
+/// 扫描仓库中某个提交（分支、标签或 SHA）的全部支持文件
+///
+/// 文件内容通过 libgit2 从对象库读入内存，并以仓库内路径（如 `src/app.py`）
+/// 作为 Finding 的文件路径。ref 无法解析为提交时返回错误。
+pub async fn scan_git_ref(repo_path: &str, ref_or_sha: &str) -> Result<Vec<Finding>, String> {
+    Ok(scan_git_ref_report(repo_path, ref_or_sha, &ScanOptions::default()).await?.findings)
+}
+
+/// 按扫描选项扫描某个提交，报告中的 files 为实际扫描的文件
+///
+/// 与目录扫描相同地应用规则、扩展名过滤、内置扫描器开关、单文件上限、严重程度改写、
+/// 仅提示规则和时间上限；repo_root、结果缓存、增量扫描和 git blame 对提交中的文件没有意义，被忽略。
+pub async fn scan_git_ref_report(
+    repo_path: &str,
+    ref_or_sha: &str,
+    options: &ScanOptions,
+) -> Result<ScanReport, String> {
+    scan_git_ref_with(repo_path, ref_or_sha, options, |_| ControlFlow::Continue(())).await
+}
+
+/// 同 [`scan_git_ref_report`]，扫描过程中通过 channel 发送进度（每个文件一次）和发现；
+/// 接收端关闭后停止扫描剩余文件，返回已完成部分的结果
+pub async fn scan_git_ref_stream(
+    repo_path: &str,
+    ref_or_sha: &str,
+    options: &ScanOptions,
+    events: tokio::sync::mpsc::UnboundedSender<ScanEvent>,
+) -> Result<ScanReport, String> {
+    scan_git_ref_with(repo_path, ref_or_sha, options, |event| match events.send(event) {
+        Ok(()) => ControlFlow::Continue(()),
+        Err(_) => ControlFlow::Break(()),
+    })
+    .await
+}
+
+async fn scan_git_ref_with(
+    repo_path: &str,
+    ref_or_sha: &str,
+    options: &ScanOptions,
+    mut on_event: impl FnMut(ScanEvent) -> ControlFlow<()>,
+) -> Result<ScanReport, String> {
+    let started = std::time::Instant::now();
+    let mut timings = StageTimings::default();
+    let scanners = std::sync::Arc::new(DefaultScanners::for_options(options)?);
+    let ruleset_hash = effective_ruleset_hash(scanners.ruleset_hash(), options);
+    let mut symbol_parser = options.enclosing_symbols.then(crate::ast::ASTParser::new);
+    let deadline = scan_deadline(options);
+
+    // libgit2 的调用是阻塞的，放到阻塞线程池中执行
+    let read_started = std::time::Instant::now();
+    let repo = PathBuf::from(repo_path);
+    let ref_or_sha = ref_or_sha.to_string();
+    let scan_options = options.clone();
+    let entries = tokio::task::spawn_blocking(move || read_commit_files(&repo, &ref_or_sha, &scan_options))
+        .await
+        .map_err(|e| format!("Failed to read git objects: {}", e))??;
+    timings.read += read_started.elapsed();
+
+    let total = entries.len();
+    let mut findings = Vec::new();
+    let mut files = Vec::new();
+    let mut timed_out = false;
+    if on_event(ScanEvent::Progress { done: 0, total }).is_continue() {
+        for (index, (name, content)) in entries.iter().enumerate() {
+            if deadline.is_some_and(|deadline| tokio::time::Instant::now() >= deadline) {
+                timed_out = true;
+                log::warn!("Git ref scan exceeded time limit after {} of {} files", index, total);
+                let _ = on_event(ScanEvent::TimedOut { done: index, total });
+                break;
+            }
+
+            let path = PathBuf::from(name);
+            let Some(mut file_findings) =
+                scan_file_before(&scanners, deadline, &path, &path, content, &mut timings).await
+            else {
+                timed_out = true;
+                log::warn!("Git ref scan exceeded time limit while scanning {}", name);
+                let _ = on_event(ScanEvent::TimedOut { done: index, total });
+                break;
+            };
+            apply_file_options(&mut file_findings, &path, content, options, symbol_parser.as_mut());
+            assign_finding_ids(&mut file_findings, options.finding_id_scope.as_deref());
+            files.push(ScannedFile {
+                file_path: name.clone(),
+                hash: FileHash {
+                    content_hash: content_hash(content),
+                    ruleset_hash: ruleset_hash.clone(),
+                },
+                rescanned: true,
+            });
+
+            let mut flow = ControlFlow::Continue(());
+            for finding in &file_findings {
+                flow = on_event(ScanEvent::Finding(Box::new(finding.clone())));
+                if flow.is_break() {
+                    break;
+                }
+            }
+            findings.append(&mut file_findings);
+            if flow.is_break() || on_event(ScanEvent::Progress { done: index + 1, total }).is_break() {
+                log::info!("Git ref scan cancelled after {} of {} files", index + 1, total);
+                break;
+            }
+        }
+    }
+
+    sort_findings(&mut findings);
+    Ok(ScanReport {
+        findings,
+        files,
+        timed_out,
+        metrics: timings.into_metrics(started.elapsed()),
+    })
+}
+
+/// 读取提交树中所有可扫描的文本文件：(仓库内路径, 内容)
+fn read_commit_files(repo: &Path, ref_or_sha: &str, options: &ScanOptions) -> Result<Vec<(String, String)>, String> {
+    let ref_or_sha = ref_or_sha.trim();
+    if ref_or_sha.is_empty() {
+        return Err("Invalid git ref: empty".to_string());
+    }
+
+    let repository = git2::Repository::open(repo)
+        .map_err(|_| format!("Not a git repository: {}", repo.display()))?;
+    let commit = repository
+        .revparse_single(ref_or_sha)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|_| format!("Unknown git ref or not a commit: {}", ref_or_sha))?;
+    let tree = commit
+        .tree()
+        .map_err(|e| format!("Failed to read tree of {}: {}", commit.id(), e))?;
+
+    // 先收集路径，遍历回调中不处理错误
+    let mut blobs = Vec::new();
+    tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+        if entry.kind() == Some(git2::ObjectType::Blob) && entry.filemode() != SYMLINK_FILE_MODE {
+            if let Some(name) = entry.name() {
+                let name = format!("{}{}", dir, name);
+                if options.should_scan(Path::new(&name)) {
+                    blobs.push((name, entry.id()));
+                }
+            }
+        }
+        git2::TreeWalkResult::Ok
+    })
+    .map_err(|e| format!("Failed to walk tree of {}: {}", commit.id(), e))?;
+
+    let mut entries = Vec::new();
+    for (name, id) in blobs {
+        let blob = repository
+            .find_blob(id)
+            .map_err(|e| format!("Failed to read {} at {}: {}", name, commit.id(), e))?;
+        if blob.size() > MAX_BLOB_SIZE {
+            log::warn!("Skipping oversized file {} at {} ({} bytes)", name, commit.id(), blob.size());
+            continue;
+        }
+        if looks_binary(blob.content()) {
+            continue;
+        }
+        match std::str::from_utf8(blob.content()) {
+            Ok(content) => entries.push((name, content.to_string())),
+            Err(_) => log::warn!("Skipping non UTF-8 file {} at {}", name, commit.id()),
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit_file(repo: &git2::Repository, name: &str, content: &str, message: &str) {
+        let workdir = repo.workdir().unwrap();
+        std::fs::write(workdir.join(name), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(name)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)
+            .unwrap();
+    }
+
+    fn options() -> ScanOptions {
+        ScanOptions {
+            rules: Some(std::sync::Arc::new(crate::scanner::LoadedRules::load_from_dir(Path::new("no-such-rules")))),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn scans_files_as_of_each_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        commit_file(&repo, "app.py", "import hashlib\nhashlib.md5(data)\n", "first");
+        let first = repo.head().unwrap().peel_to_commit().unwrap().id().to_string();
+        commit_file(&repo, "app.py", "import hashlib\nhashlib.sha256(data)\n", "second");
+        // 未提交的修改不影响扫描
+        std::fs::write(dir.path().join("app.py"), "hashlib.md5(data)\n").unwrap();
+
+        let root = dir.path().to_string_lossy().to_string();
+        let old = scan_git_ref_report(&root, &first, &options()).await.unwrap();
+        assert_eq!(old.files.len(), 1);
+        assert!(old.findings.iter().any(|f| f.detector == "WeakCryptoScanner" && f.line_start == 2));
+
+        let head = scan_git_ref_report(&root, "HEAD", &options()).await.unwrap();
+        assert_eq!(head.files.len(), 1);
+        assert!(!head.findings.iter().any(|f| f.detector == "WeakCryptoScanner"));
+
+        let previous = scan_git_ref_report(&root, "HEAD~1", &options()).await.unwrap();
+        let ids = |report: &ScanReport| report.findings.iter().map(|f| f.finding_id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&previous), ids(&old));
+
+        assert!(scan_git_ref(&root, "no-such-branch").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn stream_reports_progress_per_file_and_stops_when_receiver_closes() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        commit_file(&repo, "a.py", "# TODO: a\n", "a");
+        commit_file(&repo, "b.py", "# TODO: b\n", "b");
+        let root = dir.path().to_string_lossy().to_string();
+
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let report = scan_git_ref_stream(&root, "HEAD", &options(), sender).await.unwrap();
+        assert_eq!(report.files.len(), 2);
+        let mut progress = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            if let ScanEvent::Progress { done, total } = event {
+                progress.push((done, total));
+            }
+        }
+        assert_eq!(progress, vec![(0, 2), (1, 2), (2, 2)]);
+
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        drop(receiver);
+        let cancelled = scan_git_ref_stream(&root, "HEAD", &options(), sender).await.unwrap();
+        assert!(cancelled.files.is_empty());
+    }
+}
@@ -1,19 +1,28 @@
-use super::{Finding, Scanner};
+use super::{assign_finding_ids, looks_binary, sort_findings, Finding, Scanner, DEFAULT_MAX_WALK_DEPTH};
 use std::path::PathBuf;
 use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct ScannerManager {
     scanners: Vec<Arc<dyn Scanner>>,
+    /// scan_directory 遍历的最大目录深度
+    max_depth: usize,
 }
 
 impl ScannerManager {
     pub fn new() -> Self {
         Self {
             scanners: Vec::new(),
+            max_depth: DEFAULT_MAX_WALK_DEPTH,
         }
     }
 
+    /// 设置 scan_directory 遍历的最大目录深度（根目录下的文件深度为 1）
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
     pub fn register_scanner<S: Scanner + 'static>(&mut self, scanner: S) {
         self.scanners.push(Arc::new(scanner));
     }
@@ -24,11 +33,16 @@ impl ScannerManager {
             let findings = scanner.scan_file(path, content).await;
             all_findings.extend(findings);
         }
+        for finding in &mut all_findings {
+            finding.severity = crate::rules::model::normalize_severity(&finding.severity);
+        }
         all_findings
     }
 
     pub async fn scan_directory(&self, root_path: &str) -> Vec<Finding> {
-        let walker = ignore::WalkBuilder::new(root_path).build();
+        let walker = ignore::WalkBuilder::new(root_path)
+            .max_depth(Some(self.max_depth))
+            .build();
         let mut set = tokio::task::JoinSet::new();
 
         for result in walker {
@@ -38,10 +52,17 @@ impl ScannerManager {
                     let manager = self.clone();
 
                     set.spawn(async move {
-                        if let Ok(content) = tokio::fs::read_to_string(&path).await {
-                            manager.scan_file(&path, &content).await
-                        } else {
-                            Vec::new()
+                        let content = match tokio::fs::read(&path).await {
+                            Ok(bytes) if looks_binary(&bytes) => {
+                                log::debug!("Skipping binary file {}", path.display());
+                                None
+                            }
+                            Ok(bytes) => String::from_utf8(bytes).ok(),
+                            Err(_) => None,
+                        };
+                        match content {
+                            Some(content) => manager.scan_file(&path, &content).await,
+                            None => Vec::new(),
                         }
                     });
                 }
@@ -54,6 +75,76 @@ impl ScannerManager {
                 all_findings.extend(findings);
             }
         }
+
+        // JoinSet 的完成顺序不确定，排序后输出才稳定
+        sort_findings(&mut all_findings);
+        assign_finding_ids(&mut all_findings, None);
         all_findings
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::regex_scanner::RegexScanner;
+
+    fn positions(findings: &[Finding]) -> Vec<(String, usize, usize, String)> {
+        findings
+            .iter()
+            .map(|f| (f.file_path.clone(), f.line_start, f.line_end, f.vuln_type.clone()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn directory_scan_order_is_deterministic() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in (0..24).rev() {
+            let sub = dir.path().join(format!("pkg{}", i % 3));
+            std::fs::create_dir_all(&sub).unwrap();
+            std::fs::write(
+                sub.join(format!("mod{:02}.py", i)),
+                "# TODO: first\nimport hashlib\n# TODO: second\n",
+            )
+            .unwrap();
+        }
+        let mut manager = ScannerManager::new();
+        manager.register_scanner(RegexScanner::new());
+        let root = dir.path().to_string_lossy().to_string();
+
+        let first = positions(&manager.scan_directory(&root).await);
+        assert_eq!(first.len(), 48);
+        let mut sorted = first.clone();
+        sorted.sort();
+        assert_eq!(first, sorted);
+        for _ in 0..3 {
+            assert_eq!(positions(&manager.scan_directory(&root).await), first);
+        }
+    }
+
+    /// 以非规范写法输出严重程度的扫描器
+    struct ShoutingScanner;
+
+    #[async_trait::async_trait]
+    impl Scanner for ShoutingScanner {
+        fn name(&self) -> String {
+            "ShoutingScanner".to_string()
+        }
+
+        async fn scan_file(&self, path: &PathBuf, _content: &str) -> Vec<Finding> {
+            let mut finding = RegexScanner::new().scan_file(path, "# TODO: x\n").await.remove(0);
+            finding.severity = "HIGH".to_string();
+            vec![finding]
+        }
+    }
+
+    #[tokio::test]
+    async fn scanner_severities_are_normalized_to_lowercase() {
+        let mut manager = ScannerManager::new();
+        manager.register_scanner(RegexScanner::new());
+        manager.register_scanner(ShoutingScanner);
+
+        let findings = manager.scan_file(&PathBuf::from("app.py"), "# TODO: x\n").await;
+        let severities: Vec<&str> = findings.iter().map(|f| f.severity.as_str()).collect();
+        assert_eq!(severities, ["low", "high"]);
+    }
+}
@@ -0,0 +1,509 @@
+// Path traversal - 函数内的用户输入与文件路径操作共现检测
+//
+// 基于 AST 解析出的 MethodCall 符号查找文件路径操作（sink），并在 sink 所在的函数内判断其参数是否来自
+// 用户输入（source）：参数直接包含 source，或引用了函数内由 source（逐行传递）赋值的变量。
+// 只做函数内的文本层面分析，不跟踪跨函数调用和净化函数；解析失败或不在函数内的调用不报告。
+
+use super::command_injection::normalize_callee;
+use super::regex_scanner::DetectorInfo;
+use super::{normalize_finding_path, stable_finding_id, Finding, Scanner};
+use crate::ast::{route_info, ASTParser, Symbol, SymbolKind};
+use crate::rules::model::Severity;
+use async_trait::async_trait;
+use regex::Regex;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const VULN_TYPE: &str = "Path Traversal";
+const SEVERITY: Severity = Severity::High;
+const CWE: &str = "CWE-22";
+
+/// 某种语言的用户输入来源和文件路径操作
+struct SourceSinkSet {
+    language: &'static str,
+    extensions: &'static [&'static str],
+    /// 用户输入表达式的正则（如 `request.args`、`req.query`）
+    sources: &'static str,
+    /// 文件路径操作的调用目标（如 `open`、`fs.readFile`）
+    sinks: &'static [&'static str],
+    /// 以构造函数形式出现的 sink（如 Java 的 `new File(...)`），解析器不产生对应的调用符号，按文本匹配
+    constructors: &'static [&'static str],
+}
+
+const SOURCE_SINK_SETS: &[SourceSinkSet] = &[
+    SourceSinkSet {
+        language: "Python",
+        extensions: &["py"],
+        sources: r"\brequest\.(?:args|form|values|files|GET|POST|data|json|get_json|query_params|path_params|cookies|headers)\b|\binput\s*\(|\bsys\.argv\b",
+        sinks: &[
+            "open",
+            "io.open",
+            "os.open",
+            "codecs.open",
+            "os.path.join",
+            "os.remove",
+            "os.unlink",
+            "os.listdir",
+            "shutil.rmtree",
+            "shutil.copy",
+            "shutil.copyfile",
+            "shutil.move",
+            "pathlib.Path",
+            "Path",
+            "send_file",
+            "send_from_directory",
+            "flask.send_file",
+            "FileResponse",
+        ],
+        constructors: &[],
+    },
+    SourceSinkSet {
+        language: "JavaScript",
+        extensions: &["js", "jsx", "ts", "tsx"],
+        sources: r"\b(?:req|request)\.(?:query|params|body|headers|cookies|files)\b|\bctx\.(?:query|params|request\.body)\b|\bprocess\.argv\b",
+        sinks: &[
+            "fs.readFile",
+            "fs.readFileSync",
+            "fs.writeFile",
+            "fs.writeFileSync",
+            "fs.appendFile",
+            "fs.appendFileSync",
+            "fs.createReadStream",
+            "fs.createWriteStream",
+            "fs.unlink",
+            "fs.unlinkSync",
+            "fs.readdir",
+            "fs.readdirSync",
+            "fs.promises.readFile",
+            "fs.promises.writeFile",
+            "fsPromises.readFile",
+            "fsPromises.writeFile",
+            "path.join",
+            "path.resolve",
+            "res.sendFile",
+            "res.download",
+        ],
+        constructors: &[],
+    },
+    SourceSinkSet {
+        language: "Java",
+        extensions: &["java"],
+        sources: r"\.(?:getParameter|getParameterValues|getHeader|getPathInfo|getQueryString|getInputStream|getReader)\s*\(",
+        sinks: &[
+            "Paths.get",
+            "Path.of",
+            "Files.readAllBytes",
+            "Files.readAllLines",
+            "Files.readString",
+            "Files.lines",
+            "Files.newInputStream",
+            "Files.newOutputStream",
+            "Files.newBufferedReader",
+            "Files.write",
+            "Files.writeString",
+            "Files.delete",
+            "Files.copy",
+            "Files.move",
+        ],
+        constructors: &["File", "FileInputStream", "FileOutputStream", "FileReader", "FileWriter", "RandomAccessFile"],
+    },
+];
+
+/// 调用代码是否以 `callee` 开头且紧跟参数列表，返回参数部分的文本
+fn call_args<'a>(code: &'a str, callee: &str) -> Option<&'a str> {
+    let normalized = normalize_callee(code);
+    let rest = normalized.strip_prefix(&normalize_callee(callee))?;
+    if !rest.trim_start().starts_with('(') {
+        return None;
+    }
+    code.split_once('(').map(|(_, args)| args)
+}
+
+/// 一次命中：sink 的位置、调用目标和作为依据的 source 或变量
+struct Hit {
+    line_start: usize,
+    line_end: usize,
+    sink: String,
+    evidence: String,
+}
+
+/// 路径遍历扫描器（CWE-22）：报告参数来自同一函数内用户输入的文件路径操作
+pub struct PathTraversalScanner {
+    parser: Mutex<ASTParser>,
+    /// 与 SOURCE_SINK_SETS 一一对应的 source 正则
+    sources: Vec<Regex>,
+    /// 与 SOURCE_SINK_SETS 一一对应的构造函数 sink 正则（没有构造函数 sink 时为 None）
+    constructors: Vec<Option<Regex>>,
+    /// 单个变量赋值：`x = ...`、`const x = ...`、`String x = ...`、`x: str = ...`
+    assignment: Regex,
+    /// JS/TS 解构赋值：`const { a, b: c } = ...`
+    destructuring: Regex,
+    identifier: Regex,
+    /// Flask `<name>` / `<path:name>` 和 FastAPI `{name}` 形式的路由参数
+    route_param: Regex,
+    /// Spring / JAX-RS 中以注解绑定请求参数的方法参数
+    annotated_param: Regex,
+}
+
+impl PathTraversalScanner {
+    pub fn new() -> Self {
+        let sources = SOURCE_SINK_SETS
+            .iter()
+            .map(|set| Regex::new(set.sources).expect("builtin path traversal source pattern must compile"))
+            .collect();
+        let constructors = SOURCE_SINK_SETS
+            .iter()
+            .map(|set| {
+                (!set.constructors.is_empty()).then(|| {
+                    Regex::new(&format!(r"\bnew\s+(?:java\.io\.)?({})\s*\(", set.constructors.join("|")))
+                        .expect("builtin path traversal constructor pattern must compile")
+                })
+            })
+            .collect();
+
+        Self {
+            parser: Mutex::new(ASTParser::new()),
+            sources,
+            constructors,
+            assignment: Regex::new(
+                r"^\s*(?:(?:const|let|var|final)\s+)?(?:[\w.<>\[\]]+\s+)?([A-Za-z_$][\w$]*)\s*(?::\s*[\w.\[\], ]+)?=([^=].*)$",
+            )
+            .expect("builtin assignment pattern must compile"),
+            destructuring: Regex::new(r"^\s*(?:const|let|var)\s*\{([^}]*)\}\s*=([^=].*)$")
+                .expect("builtin destructuring pattern must compile"),
+            identifier: Regex::new(r"[A-Za-z_$][\w$]*").expect("builtin identifier pattern must compile"),
+            route_param: Regex::new(r"<(?:\w+:)?(\w+)>|\{(\w+)(?::[^}]*)?\}")
+                .expect("builtin route parameter pattern must compile"),
+            annotated_param: Regex::new(
+                r"@(?:RequestParam|PathVariable|RequestHeader|RequestBody|PathParam|QueryParam|FormParam)\b(?:\s*\([^)]*\))?\s+(?:final\s+)?[\w.<>\[\], ]+?\s+(\w+)\s*[,)]",
+            )
+            .expect("builtin annotated parameter pattern must compile"),
+        }
+    }
+
+    /// 列出各语言的 source 和 sink
+    pub fn describe(&self) -> Vec<DetectorInfo> {
+        SOURCE_SINK_SETS
+            .iter()
+            .map(|set| DetectorInfo {
+                vuln_type: VULN_TYPE.to_string(),
+                severity: SEVERITY.as_str().to_string(),
+                pattern: set.sources.to_string(),
+                description: format!(
+                    "{} file path sinks receiving user input in the same function ({}): {}",
+                    set.language,
+                    CWE,
+                    set.sinks
+                        .iter()
+                        .chain(set.constructors)
+                        .copied()
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            })
+            .collect()
+    }
+
+    /// sink 所在的作用域 (起始行, 结束行)：包含它的最内层函数/方法；
+    /// JS/TS 的匿名回调（如 `app.get('/x', (req, res) => {...})`）不产生函数符号，
+    /// 此时取包含它的最内层多行调用
+    fn scope_of(symbols: &[Symbol], line_start: u32, line_end: u32, is_js: bool) -> Option<(u32, u32)> {
+        let contains = |symbol: &&Symbol| symbol.start_line <= line_start && line_end <= symbol.end_line;
+        symbols
+            .iter()
+            .filter(|symbol| matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method))
+            .filter(contains)
+            .min_by_key(|symbol| symbol.end_line - symbol.start_line)
+            .or_else(|| {
+                symbols
+                    .iter()
+                    .filter(|_| is_js)
+                    .filter(|symbol| symbol.kind == SymbolKind::MethodCall && symbol.start_line < line_start)
+                    .filter(contains)
+                    .min_by_key(|symbol| symbol.end_line - symbol.start_line)
+            })
+            .map(|symbol| (symbol.start_line, symbol.end_line))
+    }
+
+    /// 作用域内来自用户输入的变量：路由/注解绑定的参数，以及按行顺序由 source 或已污染变量赋值的变量
+    fn tainted_variables(
+        &self,
+        lines: &[&str],
+        scope: (u32, u32),
+        function: Option<&Symbol>,
+        source: &Regex,
+    ) -> HashSet<String> {
+        let mut tainted = HashSet::new();
+
+        if let Some(path) = function.and_then(route_info).and_then(|route| route.path) {
+            for caps in self.route_param.captures_iter(&path) {
+                if let Some(name) = caps.get(1).or_else(|| caps.get(2)) {
+                    tainted.insert(name.as_str().to_string());
+                }
+            }
+        }
+
+        let start = scope.0.saturating_sub(1) as usize;
+        let end = (scope.1 as usize).min(lines.len());
+        let body = lines.get(start..end).unwrap_or_default();
+
+        // 方法签名（到第一个 `{` 为止）中以注解绑定的请求参数
+        let signature: String = body
+            .iter()
+            .take_while(|line| !line.contains('{'))
+            .chain(body.iter().find(|line| line.contains('{')))
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" ");
+        for caps in self.annotated_param.captures_iter(&signature) {
+            tainted.insert(caps[1].to_string());
+        }
+
+        for line in body {
+            if let Some(caps) = self.destructuring.captures(line) {
+                if self.is_tainted(&caps[2], source, &tainted).is_some() {
+                    for binding in caps[1].split(',') {
+                        // `{ a: b }` 绑定到 b，`{ a = 1 }` 绑定到 a
+                        let name = binding.rsplit(':').next().unwrap_or(binding);
+                        let name = name.split('=').next().unwrap_or(name).trim();
+                        if !name.is_empty() {
+                            tainted.insert(name.to_string());
+                        }
+                    }
+                }
+            } else if let Some(caps) = self.assignment.captures(line) {
+                if self.is_tainted(&caps[2], source, &tainted).is_some() {
+                    tainted.insert(caps[1].to_string());
+                }
+            }
+        }
+        tainted
+    }
+
+    /// 表达式是否包含 source 或已污染的变量，返回作为依据的文本
+    fn is_tainted(&self, expression: &str, source: &Regex, tainted: &HashSet<String>) -> Option<String> {
+        if let Some(found) = source.find(expression) {
+            return Some(format!("`{}`", found.as_str().trim_end_matches(['(', ' '])));
+        }
+        self.identifier
+            .find_iter(expression)
+            .find(|ident| tainted.contains(ident.as_str()))
+            .map(|ident| format!("variable `{}`", ident.as_str()))
+    }
+
+    /// 解析文件并查找参数来自用户输入的 sink；解析失败时返回空
+    fn scan_parsed(&self, path: &Path, content: &str, set_index: usize) -> Vec<Hit> {
+        let set = &SOURCE_SINK_SETS[set_index];
+        let source = &self.sources[set_index];
+        let symbols = {
+            let mut parser = self.parser.lock().unwrap_or_else(|e| e.into_inner());
+            match parser.parse_file(path, content) {
+                Ok(symbols) => symbols,
+                Err(e) => {
+                    log::debug!("Skipping path traversal scan of {}: {}", path.display(), e);
+                    return Vec::new();
+                }
+            }
+        };
+        let lines: Vec<&str> = content.lines().collect();
+        let is_js = set.language == "JavaScript";
+
+        // (起始行, 结束行, 调用目标, 参数文本)
+        let mut sinks: Vec<(u32, u32, String, String)> = Vec::new();
+        for symbol in symbols.iter().filter(|s| s.kind == SymbolKind::MethodCall) {
+            let Some((sink, args)) = set
+                .sinks
+                .iter()
+                .find_map(|sink| call_args(&symbol.code, sink).map(|args| (sink, args)))
+            else {
+                continue;
+            };
+            // 链式调用（如 `open(p).read()`）的外层调用同样以 sink 开头，保留范围最小的内层调用
+            match sinks
+                .iter_mut()
+                .find(|(start, _, existing, _)| *start == symbol.start_line && existing == sink)
+            {
+                Some(existing) if symbol.end_line < existing.1 => {
+                    existing.1 = symbol.end_line;
+                    existing.3 = args.to_string();
+                }
+                Some(_) => {}
+                None => sinks.push((symbol.start_line, symbol.end_line, sink.to_string(), args.to_string())),
+            }
+        }
+        if let Some(constructor) = &self.constructors[set_index] {
+            for (index, line) in lines.iter().enumerate() {
+                for caps in constructor.captures_iter(line) {
+                    let whole = caps.get(0).map_or(0, |m| m.end());
+                    let line_number = index as u32 + 1;
+                    sinks.push((line_number, line_number, format!("new {}", &caps[1]), line[whole..].to_string()));
+                }
+            }
+        }
+
+        let mut hits = Vec::new();
+        for (line_start, line_end, sink, args) in sinks {
+            let Some(scope) = Self::scope_of(&symbols, line_start, line_end, is_js) else {
+                continue;
+            };
+            let function = symbols.iter().find(|symbol| {
+                matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method)
+                    && (symbol.start_line, symbol.end_line) == scope
+            });
+            let tainted = self.tainted_variables(&lines, scope, function, source);
+            if let Some(evidence) = self.is_tainted(&args, source, &tainted) {
+                hits.push(Hit {
+                    line_start: line_start as usize,
+                    line_end: line_end as usize,
+                    sink,
+                    evidence,
+                });
+            }
+        }
+        hits.sort_by_key(|hit| (hit.line_start, hit.line_end));
+        hits
+    }
+}
+
+impl Default for PathTraversalScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Scanner for PathTraversalScanner {
+    fn name(&self) -> String {
+        "PathTraversalScanner".to_string()
+    }
+
+    async fn scan_file(&self, path: &PathBuf, content: &str) -> Vec<Finding> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+
+        let Some(set_index) = SOURCE_SINK_SETS
+            .iter()
+            .position(|set| set.extensions.contains(&extension.as_str()))
+        else {
+            return Vec::new();
+        };
+        let set = &SOURCE_SINK_SETS[set_index];
+
+        self.scan_parsed(path, content, set_index)
+            .into_iter()
+            .map(|hit| Finding {
+                finding_id: stable_finding_id(&normalize_finding_path(path), hit.line_start, VULN_TYPE, &self.name()),
+                file_path: normalize_finding_path(path),
+                line_start: hit.line_start,
+                line_end: hit.line_end,
+                detector: self.name(),
+                vuln_type: VULN_TYPE.to_string(),
+                severity: SEVERITY.as_str().to_string(),
+                description: format!(
+                    "{} file path sink `{}` at line {} uses user input {} from the same function ({}); \
+                     normalize the path and check it stays inside the allowed directory",
+                    set.language, hit.sink, hit.line_start, hit.evidence, CWE
+                ),
+                capture: None,
+                analysis_trail: None,
+                llm_output: None,
+                suppressed: false,
+                author: None,
+                commit: None,
+                enclosing_symbol: None,
+                enclosing_symbol_kind: None,
+                original_severity: None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn sinks(name: &str, content: &str) -> Vec<(usize, String)> {
+        PathTraversalScanner::new()
+            .scan_file(&PathBuf::from(name), content)
+            .await
+            .into_iter()
+            .map(|f| {
+                assert_eq!(f.vuln_type, VULN_TYPE);
+                assert!(f.description.contains(CWE));
+                let sink = f.description.split('`').nth(1).unwrap().to_string();
+                (f.line_start, sink)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn python_request_path_reaching_open_is_flagged() {
+        let content = "\
+import os
+from flask import request
+
+def download():
+    name = request.args['path']
+    full = os.path.join(BASE_DIR, name)
+    with open(full) as f:
+        return f.read()
+
+def readme():
+    with open('docs/README.md') as f:
+        return f.read()
+";
+        assert_eq!(
+            sinks("app.py", content).await,
+            vec![(6, "os.path.join".to_string()), (7, "open".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn hardcoded_paths_and_other_functions_are_not_flagged() {
+        let content = "\
+from flask import request
+
+def handler():
+    name = request.args['path']
+    return name
+
+def report():
+    name = 'report.txt'
+    return open(name).read()
+";
+        assert!(sinks("app.py", content).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn route_parameters_express_and_java_sources_are_tracked() {
+        let flask = "\
+@app.route('/files/<path:filename>')
+def serve(filename):
+    return send_file(filename)
+";
+        assert_eq!(sinks("app.py", flask).await, vec![(3, "send_file".to_string())]);
+
+        let express = "\
+app.get('/file', (req, res) => {
+  const { name } = req.query;
+  fs.readFile(path.join(ROOT, 'static.txt'), () => {});
+  res.sendFile(name);
+});
+";
+        assert_eq!(sinks("server.js", express).await, vec![(4, "res.sendFile".to_string())]);
+
+        let java = "\
+class FileController {
+    void read(HttpServletRequest request) {
+        String name = request.getParameter(\"name\");
+        File file = new File(name);
+    }
+}
+";
+        assert_eq!(sinks("FileController.java", java).await, vec![(4, "new File".to_string())]);
+    }
+}
@@ -0,0 +1,67 @@
+// Report - 扫描结果的输出格式
+
+use super::Finding;
+
+/// 将发现序列化为 NDJSON：每行一个 JSON 对象并以换行结尾，供日志管道等逐行处理
+///
+/// 字符串中的换行在 JSON 中被转义，因此每个发现恰好占一行
+pub fn to_ndjson(findings: &[Finding]) -> String {
+    let mut output = String::new();
+    for finding in findings {
+        match serde_json::to_string(finding) {
+            Ok(line) => {
+                output.push_str(&line);
+                output.push('\n');
+            }
+            Err(e) => log::warn!("Skipping finding {} in NDJSON output: {}", finding.finding_id, e),
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(line: usize, description: &str) -> Finding {
+        Finding {
+            finding_id: format!("f{}", line),
+            file_path: "app.py".to_string(),
+            line_start: line,
+            line_end: line,
+            detector: "RegexScanner".to_string(),
+            vuln_type: "TODO Comment".to_string(),
+            severity: "low".to_string(),
+            description: description.to_string(),
+            capture: None,
+            analysis_trail: None,
+            llm_output: None,
+            suppressed: false,
+            author: None,
+            commit: None,
+            enclosing_symbol: None,
+            enclosing_symbol_kind: None,
+            original_severity: None,
+        }
+    }
+
+    #[test]
+    fn each_ndjson_line_is_one_finding_object() {
+        let findings = vec![finding(1, "first"), finding(7, "spans\nseveral\r\nlines")];
+        let output = to_ndjson(&findings);
+
+        assert!(output.ends_with('\n'));
+        let parsed: Vec<Finding> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let summary = |findings: &[Finding]| {
+            findings
+                .iter()
+                .map(|f| (f.finding_id.clone(), f.line_start, f.description.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(summary(&parsed), summary(&findings));
+        assert_eq!(to_ndjson(&[]), "");
+    }
+}
@@ -0,0 +1,104 @@
+// Inline suppression - 通过代码注释抑制发现
+//
+// 支持的写法（注释符号不限）：
+//   foo(password)  // ctxaudit-ignore
+//   foo(password)  // ctxaudit-ignore: rule-id, other-rule
+//   // ctxaudit-ignore-next-line: rule-id
+//   foo(password)
+// 不指定规则时抑制该行的所有发现。
+
+use super::Finding;
+
+const IGNORE_DIRECTIVE: &str = "ctxaudit-ignore";
+const IGNORE_NEXT_LINE_DIRECTIVE: &str = "ctxaudit-ignore-next-line";
+
+/// 解析后的抑制指令
+struct Directive {
+    next_line: bool,
+    /// 为空表示抑制全部规则
+    rules: Vec<String>,
+}
+
+impl Directive {
+    fn parse(line: &str) -> Option<Self> {
+        let start = line.find(IGNORE_DIRECTIVE)?;
+        let rest = &line[start..];
+
+        let (next_line, rest) = match rest.strip_prefix(IGNORE_NEXT_LINE_DIRECTIVE) {
+            Some(rest) => (true, rest),
+            None => (false, &rest[IGNORE_DIRECTIVE.len()..]),
+        };
+
+        // 指令后必须是结束、空白或 `:`，避免匹配 `ctxaudit-ignored` 之类的文本
+        let rules = match rest.chars().next() {
+            None => Vec::new(),
+            Some(':') => rest[1..]
+                .split(',')
+                .map(|r| r.trim_end_matches("*/").trim_end_matches("-->").trim())
+                .filter(|r| !r.is_empty())
+                .map(|r| r.to_string())
+                .collect(),
+            Some(c) if c.is_whitespace() => Vec::new(),
+            Some(_) => return None,
+        };
+
+        Some(Self { next_line, rules })
+    }
+
+    fn matches(&self, finding: &Finding) -> bool {
+        if self.rules.is_empty() {
+            return true;
+        }
+
+        // 规则发现的 detector 形如 `ASTRule: <id>`，内置扫描器则按检测器名或漏洞类型匹配
+        let rule_id = finding
+            .detector
+            .split_once(':')
+            .map(|(_, id)| id.trim())
+            .unwrap_or(&finding.detector);
+
+        self.rules.iter().any(|rule| {
+            rule == rule_id
+                || rule.eq_ignore_ascii_case(&finding.detector)
+                || rule.eq_ignore_ascii_case(&finding.vuln_type)
+        })
+    }
+}
+
+/// 仅包含注释的行（上一行的 `ctxaudit-ignore` 也作用于下一行）
+fn is_comment_only(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    ["//", "#", "/*", "*", "--", "<!--"]
+        .iter()
+        .any(|prefix| trimmed.starts_with(prefix))
+}
+
+/// 根据文件内容中的抑制注释标记发现的 `suppressed` 字段
+///
+/// 检查发现起始行（任意指令）以及上一行（`ctxaudit-ignore-next-line`，
+/// 或独占一行注释的 `ctxaudit-ignore`）
+pub(crate) fn apply_suppressions(findings: &mut [Finding], content: &str) {
+    if !content.contains(IGNORE_DIRECTIVE) {
+        return;
+    }
+
+    let lines = super::split_lines(content);
+    let line_at = |number: usize| -> Option<&str> {
+        number.checked_sub(1).and_then(|i| lines.get(i)).copied()
+    };
+
+    for finding in findings.iter_mut() {
+        let same_line = line_at(finding.line_start)
+            .and_then(Directive::parse)
+            .filter(|d| !d.next_line);
+
+        let previous_line = line_at(finding.line_start.saturating_sub(1))
+            .and_then(|line| {
+                Directive::parse(line).filter(|d| d.next_line || is_comment_only(line))
+            });
+
+        if same_line.iter().chain(previous_line.iter()).any(|d| d.matches(finding)) {
+            finding.suppressed = true;
+        }
+    }
+}
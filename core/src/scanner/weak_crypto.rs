@@ -0,0 +1,466 @@
+// Weak crypto - 跨语言的弱加密原语检测
+//
+// 与命令执行扫描器相同：对 AST 解析器支持的语言基于 MethodCall 符号匹配调用目标，
+// 解析器不支持的语言（Go、C/C++）或解析失败时回退到正则匹配。
+// 部分调用（如 `MessageDigest.getInstance`）本身无害，需参数指定弱算法时才报告。
+
+use super::command_injection::normalize_callee;
+use super::regex_scanner::DetectorInfo;
+use super::{line_number_at, normalize_finding_path, stable_finding_id, Finding, Scanner};
+use crate::ast::{ASTParser, SymbolKind};
+use crate::rules::model::Severity;
+use async_trait::async_trait;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const VULN_TYPE: &str = "Weak Cryptography";
+
+/// 弱原语的类别，决定 CWE 和严重程度
+#[derive(Clone, Copy)]
+enum WeakKind {
+    /// 已被攻破的摘要算法（MD5、SHA-1）
+    Hash,
+    /// 已被攻破的加密算法或不安全的模式（DES、RC4、ECB）
+    Cipher,
+    /// 非密码学安全的随机数
+    Random,
+}
+
+impl WeakKind {
+    fn cwe(self) -> &'static str {
+        match self {
+            WeakKind::Hash => "CWE-328",
+            WeakKind::Cipher => "CWE-327",
+            WeakKind::Random => "CWE-330",
+        }
+    }
+
+    /// 随机数是否用于安全场景无法静态判断，严重程度最低
+    fn severity(self) -> Severity {
+        match self {
+            WeakKind::Hash => Severity::Medium,
+            WeakKind::Cipher => Severity::High,
+            WeakKind::Random => Severity::Low,
+        }
+    }
+
+    fn advice(self) -> &'static str {
+        match self {
+            WeakKind::Hash => "use SHA-256 or stronger (or a password hash such as bcrypt/argon2 for passwords)",
+            WeakKind::Cipher => "use AES-GCM or ChaCha20-Poly1305",
+            WeakKind::Random => "use a CSPRNG (secrets / crypto.getRandomValues / SecureRandom) for tokens and keys",
+        }
+    }
+}
+
+struct WeakPrimitive {
+    /// 调用目标的源码写法（如 `hashlib.md5`）
+    callee: &'static str,
+    /// 参数列表（从左括号开始）需匹配的正则，不区分大小写；None 表示调用本身即为弱原语
+    args: Option<&'static str>,
+    algorithm: &'static str,
+    kind: WeakKind,
+}
+
+const fn weak(callee: &'static str, algorithm: &'static str, kind: WeakKind) -> WeakPrimitive {
+    WeakPrimitive { callee, args: None, algorithm, kind }
+}
+
+const fn weak_with_args(
+    callee: &'static str,
+    args: &'static str,
+    algorithm: &'static str,
+    kind: WeakKind,
+) -> WeakPrimitive {
+    WeakPrimitive { callee, args: Some(args), algorithm, kind }
+}
+
+/// 某种语言的弱原语
+struct PrimitiveSet {
+    language: &'static str,
+    extensions: &'static [&'static str],
+    primitives: &'static [WeakPrimitive],
+    /// 是否有 AST 解析器支持，否则只使用正则
+    parsed: bool,
+}
+
+const PRIMITIVE_SETS: &[PrimitiveSet] = &[
+    PrimitiveSet {
+        language: "Python",
+        extensions: &["py"],
+        primitives: &[
+            weak("hashlib.md5", "MD5", WeakKind::Hash),
+            weak("hashlib.sha1", "SHA-1", WeakKind::Hash),
+            weak_with_args("hashlib.new", r#"^\s*\(\s*['"](md5|sha1)['"]"#, "MD5/SHA-1", WeakKind::Hash),
+            weak("DES.new", "DES", WeakKind::Cipher),
+            weak("DES3.new", "3DES", WeakKind::Cipher),
+            weak("ARC4.new", "RC4", WeakKind::Cipher),
+            weak("Blowfish.new", "Blowfish", WeakKind::Cipher),
+            weak_with_args("AES.new", r"MODE_ECB", "AES-ECB", WeakKind::Cipher),
+            weak("random.random", "random module", WeakKind::Random),
+            weak("random.randint", "random module", WeakKind::Random),
+            weak("random.randrange", "random module", WeakKind::Random),
+            weak("random.choice", "random module", WeakKind::Random),
+            weak("random.getrandbits", "random module", WeakKind::Random),
+        ],
+        parsed: true,
+    },
+    PrimitiveSet {
+        language: "JavaScript",
+        extensions: &["js", "jsx", "ts", "tsx"],
+        primitives: &[
+            weak_with_args("crypto.createHash", r#"^\s*\(\s*['"`](md5|sha1)['"`]"#, "MD5/SHA-1", WeakKind::Hash),
+            weak_with_args(
+                "crypto.createCipheriv",
+                r#"^\s*\(\s*['"`][^'"`]*(des|rc4|rc2|bf|ecb)"#,
+                "DES/RC4/ECB",
+                WeakKind::Cipher,
+            ),
+            weak_with_args(
+                "crypto.createCipher",
+                r#"^\s*\(\s*['"`][^'"`]*(des|rc4|rc2|bf|ecb)"#,
+                "DES/RC4/ECB",
+                WeakKind::Cipher,
+            ),
+            weak("Math.random", "Math.random", WeakKind::Random),
+        ],
+        parsed: true,
+    },
+    PrimitiveSet {
+        language: "Java",
+        extensions: &["java"],
+        primitives: &[
+            weak_with_args("MessageDigest.getInstance", r#"^\s*\(\s*"(md2|md5|sha-?1)""#, "MD5/SHA-1", WeakKind::Hash),
+            // 只写 "AES" 时默认使用 ECB 模式
+            weak_with_args(
+                "Cipher.getInstance",
+                r#"^\s*\(\s*"((des|desede|rc2|rc4|arcfour|blowfish)\b|[^"]*/ecb/|aes")"#,
+                "DES/RC4/ECB",
+                WeakKind::Cipher,
+            ),
+            weak("Math.random", "Math.random", WeakKind::Random),
+        ],
+        parsed: true,
+    },
+    PrimitiveSet {
+        language: "Rust",
+        extensions: &["rs"],
+        primitives: &[
+            weak("md5::compute", "MD5", WeakKind::Hash),
+            weak("Md5::new", "MD5", WeakKind::Hash),
+            weak("Md5::digest", "MD5", WeakKind::Hash),
+            weak("Sha1::new", "SHA-1", WeakKind::Hash),
+            weak("Sha1::digest", "SHA-1", WeakKind::Hash),
+        ],
+        parsed: true,
+    },
+    PrimitiveSet {
+        language: "Ruby",
+        extensions: &["rb"],
+        primitives: &[
+            weak("Digest::MD5.hexdigest", "MD5", WeakKind::Hash),
+            weak("Digest::MD5.digest", "MD5", WeakKind::Hash),
+            weak("Digest::MD5.new", "MD5", WeakKind::Hash),
+            weak("Digest::SHA1.hexdigest", "SHA-1", WeakKind::Hash),
+            weak("Digest::SHA1.digest", "SHA-1", WeakKind::Hash),
+            weak("Digest::SHA1.new", "SHA-1", WeakKind::Hash),
+            weak_with_args("OpenSSL::Cipher.new", r#"^\s*\(?\s*['"](des|rc4|rc2|bf)|ecb"#, "DES/RC4/ECB", WeakKind::Cipher),
+        ],
+        parsed: true,
+    },
+    PrimitiveSet {
+        language: "Go",
+        extensions: &["go"],
+        primitives: &[
+            weak("md5.New", "MD5", WeakKind::Hash),
+            weak("md5.Sum", "MD5", WeakKind::Hash),
+            weak("sha1.New", "SHA-1", WeakKind::Hash),
+            weak("sha1.Sum", "SHA-1", WeakKind::Hash),
+            weak("des.NewCipher", "DES", WeakKind::Cipher),
+            weak("des.NewTripleDESCipher", "3DES", WeakKind::Cipher),
+            weak("rc4.NewCipher", "RC4", WeakKind::Cipher),
+        ],
+        parsed: false,
+    },
+    PrimitiveSet {
+        language: "C/C++",
+        extensions: &["c", "h", "cpp", "hpp", "cc"],
+        primitives: &[
+            weak("MD5_Init", "MD5", WeakKind::Hash),
+            weak("SHA1_Init", "SHA-1", WeakKind::Hash),
+            weak("DES_set_key", "DES", WeakKind::Cipher),
+            weak("DES_ecb_encrypt", "DES-ECB", WeakKind::Cipher),
+            weak("RC4_set_key", "RC4", WeakKind::Cipher),
+            weak("EVP_md5", "MD5", WeakKind::Hash),
+            weak("EVP_sha1", "SHA-1", WeakKind::Hash),
+            weak("EVP_des_ecb", "DES-ECB", WeakKind::Cipher),
+            weak("EVP_rc4", "RC4", WeakKind::Cipher),
+        ],
+        parsed: false,
+    },
+];
+
+/// 调用代码以 `callee` 开头时返回其后的参数部分（从左括号开始）
+fn call_arguments(code: &str, callee: &str, allow_bare_args: bool) -> Option<String> {
+    let code = normalize_callee(code);
+    let rest = code.strip_prefix(&normalize_callee(callee))?;
+    // Ruby 允许省略括号，如 `Digest::MD5.hexdigest data`
+    if rest.trim_start().starts_with('(') || (allow_bare_args && rest.starts_with([' ', '\t'])) {
+        Some(rest.to_string())
+    } else {
+        None
+    }
+}
+
+/// 弱加密原语扫描器（CWE-327 / CWE-328 / CWE-330）
+pub struct WeakCryptoScanner {
+    parser: Mutex<ASTParser>,
+    /// 每种语言一个正则，用于未解析语言及解析失败时的回退
+    fallback: Vec<Regex>,
+    /// 与 PRIMITIVE_SETS 中的原语一一对应的参数正则
+    args: Vec<Vec<Option<Regex>>>,
+}
+
+impl WeakCryptoScanner {
+    pub fn new() -> Self {
+        let fallback = PRIMITIVE_SETS
+            .iter()
+            .map(|set| {
+                let alternatives: Vec<String> =
+                    set.primitives.iter().map(|p| regex::escape(p.callee)).collect();
+                Regex::new(&format!(r"(?m)(?:^|[^\w.:])({})[ \t]*\(", alternatives.join("|")))
+                    .expect("builtin weak crypto pattern must compile")
+            })
+            .collect();
+
+        let args = PRIMITIVE_SETS
+            .iter()
+            .map(|set| {
+                set.primitives
+                    .iter()
+                    .map(|p| {
+                        p.args.map(|args| {
+                            Regex::new(&format!("(?i){}", args))
+                                .expect("builtin weak crypto argument pattern must compile")
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            parser: Mutex::new(ASTParser::new()),
+            fallback,
+            args,
+        }
+    }
+
+    /// 列出各语言的弱原语
+    pub fn describe(&self) -> Vec<DetectorInfo> {
+        PRIMITIVE_SETS
+            .iter()
+            .zip(&self.fallback)
+            .map(|(set, regex)| {
+                let callees: Vec<&str> = set.primitives.iter().map(|p| p.callee).collect();
+                // 报告该语言中最高的严重程度
+                let severity = set
+                    .primitives
+                    .iter()
+                    .map(|p| p.kind.severity())
+                    .max_by_key(|severity| severity.rank())
+                    .unwrap_or(Severity::Low);
+                DetectorInfo {
+                    vuln_type: VULN_TYPE.to_string(),
+                    severity: severity.as_str().to_string(),
+                    pattern: regex.as_str().to_string(),
+                    description: format!(
+                        "{} weak crypto primitives (CWE-327/328/330): {}",
+                        set.language,
+                        callees.join(", ")
+                    ),
+                }
+            })
+            .collect()
+    }
+
+    /// 第一个与调用代码匹配的原语（参数也需匹配）
+    fn match_primitive(&self, set_index: usize, code: &str, allow_bare_args: bool) -> Option<usize> {
+        let set = &PRIMITIVE_SETS[set_index];
+        set.primitives.iter().enumerate().find_map(|(i, primitive)| {
+            let rest = call_arguments(code, primitive.callee, allow_bare_args)?;
+            match &self.args[set_index][i] {
+                Some(args) => args.is_match(&rest).then_some(i),
+                None => Some(i),
+            }
+        })
+    }
+
+    /// 基于 AST 的 MethodCall 符号查找调用点，返回 (行号, 结束行号, 原语下标)；解析失败时返回 None
+    fn scan_parsed(&self, path: &Path, content: &str, set_index: usize) -> Option<Vec<(usize, usize, usize)>> {
+        let symbols = {
+            let mut parser = self.parser.lock().unwrap_or_else(|e| e.into_inner());
+            parser.parse_file(path, content).ok()?
+        };
+        let is_ruby = PRIMITIVE_SETS[set_index].language == "Ruby";
+
+        // 链式调用（如 `hashlib.md5(x).hexdigest()`）的外层调用代码同样以调用目标开头，
+        // 按 (起始行, 原语) 去重，保留范围最小的内层调用
+        let mut hits: Vec<(usize, usize, usize)> = Vec::new();
+        for symbol in symbols.iter().filter(|s| s.kind == SymbolKind::MethodCall) {
+            if let Some(primitive) = self.match_primitive(set_index, &symbol.code, is_ruby) {
+                let (line_start, line_end) = (symbol.start_line as usize, symbol.end_line as usize);
+                match hits.iter_mut().find(|(start, _, existing)| *start == line_start && *existing == primitive) {
+                    Some(hit) => hit.1 = hit.1.min(line_end),
+                    None => hits.push((line_start, line_end, primitive)),
+                }
+            }
+        }
+        Some(hits)
+    }
+
+    fn scan_regex(&self, content: &str, set_index: usize) -> Vec<(usize, usize, usize)> {
+        self.fallback[set_index]
+            .captures_iter(content)
+            .filter_map(|caps| caps.get(1))
+            .filter_map(|callee| {
+                // 参数只在调用所在行内检查
+                let line_end = content[callee.start()..]
+                    .find('\n')
+                    .map_or(content.len(), |i| callee.start() + i);
+                let primitive = self.match_primitive(set_index, &content[callee.start()..line_end], false)?;
+                let line = line_number_at(content, callee.start());
+                Some((line, line, primitive))
+            })
+            .collect()
+    }
+}
+
+impl Default for WeakCryptoScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Scanner for WeakCryptoScanner {
+    fn name(&self) -> String {
+        "WeakCryptoScanner".to_string()
+    }
+
+    async fn scan_file(&self, path: &PathBuf, content: &str) -> Vec<Finding> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+
+        let Some(set_index) = PRIMITIVE_SETS
+            .iter()
+            .position(|set| set.extensions.contains(&extension.as_str()))
+        else {
+            return Vec::new();
+        };
+        let set = &PRIMITIVE_SETS[set_index];
+
+        let hits = if set.parsed {
+            self.scan_parsed(path, content, set_index)
+                .unwrap_or_else(|| self.scan_regex(content, set_index))
+        } else {
+            self.scan_regex(content, set_index)
+        };
+
+        hits.into_iter()
+            .map(|(line_start, line_end, primitive)| {
+                let primitive = &set.primitives[primitive];
+                Finding {
+                    finding_id: stable_finding_id(&normalize_finding_path(path), line_start, VULN_TYPE, &self.name()),
+                    file_path: normalize_finding_path(path),
+                    line_start,
+                    line_end,
+                    detector: self.name(),
+                    vuln_type: VULN_TYPE.to_string(),
+                    severity: primitive.kind.severity().as_str().to_string(),
+                    description: format!(
+                        "{} weak crypto primitive `{}` ({}) at line {} ({}); {}",
+                        set.language,
+                        primitive.callee,
+                        primitive.algorithm,
+                        line_start,
+                        primitive.kind.cwe(),
+                        primitive.kind.advice()
+                    ),
+                    capture: None,
+                    analysis_trail: None,
+                    llm_output: None,
+                    suppressed: false,
+                    author: None,
+                    commit: None,
+                    enclosing_symbol: None,
+                    enclosing_symbol_kind: None,
+                    original_severity: None,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn primitives(name: &str, content: &str) -> Vec<(usize, String, String)> {
+        WeakCryptoScanner::new()
+            .scan_file(&PathBuf::from(name), content)
+            .await
+            .into_iter()
+            .map(|f| {
+                assert_eq!(f.vuln_type, VULN_TYPE);
+                let callee = f.description.split('`').nth(1).unwrap().to_string();
+                (f.line_start, callee, f.severity)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn flags_md5_but_not_sha256() {
+        let content = "\
+import hashlib
+weak = hashlib.md5(data)
+strong = hashlib.sha256(data)
+# hashlib.md5(data) in a comment
+legacy = hashlib.new('sha1')
+";
+        assert_eq!(
+            primitives("app.py", content).await,
+            vec![
+                (2, "hashlib.md5".to_string(), "medium".to_string()),
+                (5, "hashlib.new".to_string(), "medium".to_string()),
+            ]
+        );
+        assert!(primitives("app.py", "hashlib.sha256(data)\n").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn flags_weak_ciphers_and_insecure_randomness() {
+        let java = "\
+class Crypto {
+    void run() {
+        Cipher a = Cipher.getInstance(\"AES/ECB/PKCS5Padding\");
+        Cipher b = Cipher.getInstance(\"AES/GCM/NoPadding\");
+        double token = Math.random();
+    }
+}
+";
+        assert_eq!(
+            primitives("Crypto.java", java).await,
+            vec![
+                (3, "Cipher.getInstance".to_string(), "high".to_string()),
+                (5, "Math.random".to_string(), "low".to_string()),
+            ]
+        );
+        // 没有 AST 解析的语言使用正则
+        let go = "package main\n\nfunc main() {\n    h := md5.New()\n    s := sha256.New()\n}\n";
+        assert_eq!(primitives("main.go", go).await, vec![(4, "md5.New".to_string(), "medium".to_string())]);
+    }
+}
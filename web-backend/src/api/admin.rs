@@ -0,0 +1,459 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::state::AppState;
+
+pub fn configure_admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/dedupe_findings", web::post().to(dedupe_findings));
+}
+
+#[derive(Deserialize)]
+pub struct DedupeFindingsQuery {
+    /// 额外合并同一文件中同一漏洞类型、行范围重叠的发现（如正则规则命中第 10 行、
+    /// tree-sitter 规则命中第 8-12 行），比精确去重更激进，默认关闭
+    #[serde(default)]
+    pub merge_overlapping: bool,
+}
+
+#[derive(Serialize)]
+pub struct DedupeFindingsResult {
+    /// 含重复记录的分组数
+    pub duplicate_groups: usize,
+    /// 删除的记录数
+    pub removed: usize,
+    /// 行范围重叠而合并的分组数（仅 merge_overlapping）
+    pub overlap_groups: usize,
+    /// 重叠合并删除的记录数，已计入 removed
+    pub overlap_removed: usize,
+    /// finding_id 改写为稳定 ID 的记录数
+    pub migrated_ids: usize,
+}
+
+/// 用于判断重复的字段：(project_id, file_path, line_start, vuln_type, detector)
+type DedupeKey = (Option<i64>, Option<String>, Option<i64>, Option<String>, Option<String>);
+
+/// 重叠合并的分组字段：(project_id, file_path, vuln_type)
+type OverlapKey = (Option<i64>, Option<String>, Option<String>);
+
+#[derive(sqlx::FromRow)]
+struct FindingRecord {
+    id: i64,
+    finding_id: String,
+    status: Option<String>,
+    scan_id: Option<i64>,
+    created_at: Option<String>,
+    project_id: Option<i64>,
+    file_path: Option<String>,
+    line_start: Option<i64>,
+    line_end: Option<i64>,
+    vuln_type: Option<String>,
+    detector: Option<String>,
+    merged_detectors: Option<String>,
+}
+
+impl FindingRecord {
+    fn dedupe_key(&self) -> DedupeKey {
+        (
+            self.project_id,
+            self.file_path.clone(),
+            self.line_start,
+            self.vuln_type.clone(),
+            self.detector.clone(),
+        )
+    }
+
+    fn overlap_key(&self) -> OverlapKey {
+        (self.project_id, self.file_path.clone(), self.vuln_type.clone())
+    }
+
+    /// 行范围 (起始行, 结束行)，缺少结束行时视为单行
+    fn line_range(&self) -> (i64, i64) {
+        let start = self.line_start.unwrap_or(0);
+        (start, self.line_end.unwrap_or(start).max(start))
+    }
+
+    /// 该记录涉及的检测器：之前合并记录的 merged_detectors，否则为自身的 detector
+    fn detectors(&self) -> Vec<String> {
+        self.merged_detectors
+            .as_deref()
+            .and_then(|v| serde_json::from_str::<Vec<String>>(v).ok())
+            .filter(|v| !v.is_empty())
+            .or_else(|| self.detector.clone().map(|d| vec![d]))
+            .unwrap_or_default()
+    }
+
+    /// 当前扫描为该记录生成的稳定 ID（与扫描时的 finding_id_scope 一致，按项目 ID 区分）；
+    /// 缺少计算所需的字段时为 None
+    fn stable_id(&self) -> Option<String> {
+        Some(deepaudit_core::scoped_stable_finding_id(
+            &self.project_id?.to_string(),
+            self.file_path.as_deref()?,
+            usize::try_from(self.line_start?).ok()?,
+            self.vuln_type.as_deref()?,
+            self.detector.as_deref()?,
+        ))
+    }
+
+    /// 是否经过审查（status 不是默认的 `new`）
+    fn reviewed(&self) -> bool {
+        self.status.as_deref().is_some_and(|s| s != "new")
+    }
+}
+
+/// 合并旧版本随机 finding_id 造成的重复发现（每次重新扫描都会插入一份）
+///
+/// 按 (project_id, file_path, line_start, vuln_type, detector) 分组，每组保留一条：
+/// 优先保留已审查（status 不是默认的 `new`）的记录，其次是最新的记录。
+/// 被删除记录的标签合并到保留的记录上，保留记录的 scan_id 更新为组内最新的扫描，
+/// 以便增量扫描继续复用。去重后每条记录的 finding_id 改写为当前扫描产生的稳定 ID，
+/// 之后的重新扫描直接更新这些记录而不是再插入一份。
+///
+/// `?merge_overlapping=true` 时再按 (project_id, file_path, vuln_type) 分组，把行范围重叠的
+/// 发现合并为一条：保留记录的选择同上，行范围扩大为组内最宽的范围，detector 改为组内
+/// 精度最高的检测器（见 [`deepaudit_core::detector_precision`]），全部检测器记录在
+/// merged_detectors 中。注意重新扫描时各检测器的原始发现仍会按原样写入。
+pub async fn dedupe_findings(
+    state: web::Data<AppState>,
+    query: web::Query<DedupeFindingsQuery>,
+) -> impl Responder {
+    match run_dedupe(&state, query.merge_overlapping).await {
+        Ok(result) => {
+            tracing::info!(
+                "Deduplicated findings: removed {} rows in {} groups ({} rows in {} overlap groups)",
+                result.removed,
+                result.duplicate_groups,
+                result.overlap_removed,
+                result.overlap_groups
+            );
+            HttpResponse::Ok().json(result)
+        }
+        Err(e) => {
+            tracing::error!("Failed to deduplicate findings: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to deduplicate findings: {}", e)
+            }))
+        }
+    }
+}
+
+const FINDING_RECORD_QUERY: &str = "SELECT id, finding_id, status, scan_id, created_at, project_id,
+        file_path, line_start, line_end, vuln_type, detector, merged_detectors
+     FROM findings";
+
+async fn run_dedupe(state: &AppState, merge_overlapping: bool) -> Result<DedupeFindingsResult, sqlx::Error> {
+    let mut tx = state.db.begin().await?;
+
+    let rows = sqlx::query_as::<_, FindingRecord>(FINDING_RECORD_QUERY)
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let mut groups: HashMap<DedupeKey, Vec<FindingRecord>> = HashMap::new();
+    for row in rows {
+        groups.entry(row.dedupe_key()).or_default().push(row);
+    }
+
+    let mut result = DedupeFindingsResult {
+        duplicate_groups: 0,
+        removed: 0,
+        overlap_groups: 0,
+        overlap_removed: 0,
+        migrated_ids: 0,
+    };
+    for group in groups.into_values().filter(|g| g.len() > 1) {
+        result.duplicate_groups += 1;
+
+        let (keeper, duplicates) = split_keeper(group);
+        let latest_scan_id = duplicates.iter().filter_map(|row| row.scan_id).chain(keeper.scan_id).max();
+        absorb_duplicates(&mut tx, &keeper, &duplicates).await?;
+        result.removed += duplicates.len();
+
+        if latest_scan_id != keeper.scan_id {
+            sqlx::query("UPDATE findings SET scan_id = ? WHERE id = ?")
+                .bind(latest_scan_id)
+                .bind(keeper.id)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    migrate_finding_ids(&mut tx, &mut result).await?;
+
+    if merge_overlapping {
+        merge_overlapping_findings(&mut tx, &mut result).await?;
+    }
+
+    tx.commit().await?;
+    Ok(result)
+}
+
+/// 把旧版本的随机 finding_id 改写为稳定 ID（在精确去重之后执行，此时每个稳定 ID 至多对应一条记录）
+async fn migrate_finding_ids(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    result: &mut DedupeFindingsResult,
+) -> Result<(), sqlx::Error> {
+    // finding_tags 通过外键引用 finding_id，两边的改写需推迟到提交时再检查外键
+    sqlx::query("PRAGMA defer_foreign_keys = ON")
+        .execute(&mut **tx)
+        .await?;
+
+    let rows = sqlx::query_as::<_, FindingRecord>(FINDING_RECORD_QUERY)
+        .fetch_all(&mut **tx)
+        .await?;
+    for row in rows {
+        let Some(stable_id) = row.stable_id().filter(|id| *id != row.finding_id) else {
+            continue;
+        };
+        sqlx::query("UPDATE findings SET finding_id = ? WHERE id = ?")
+            .bind(&stable_id)
+            .bind(row.id)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query("UPDATE finding_tags SET finding_id = ? WHERE finding_id = ?")
+            .bind(&stable_id)
+            .bind(&row.finding_id)
+            .execute(&mut **tx)
+            .await?;
+        result.migrated_ids += 1;
+    }
+
+    Ok(())
+}
+
+/// 按行范围重叠合并同一文件中同一漏洞类型的发现（在精确去重之后执行）
+async fn merge_overlapping_findings(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    result: &mut DedupeFindingsResult,
+) -> Result<(), sqlx::Error> {
+    let rows = sqlx::query_as::<_, FindingRecord>(FINDING_RECORD_QUERY)
+        .fetch_all(&mut **tx)
+        .await?;
+
+    let mut groups: HashMap<OverlapKey, Vec<FindingRecord>> = HashMap::new();
+    for row in rows {
+        groups.entry(row.overlap_key()).or_default().push(row);
+    }
+
+    for mut group in groups.into_values().filter(|g| g.len() > 1) {
+        // 按起始行排序后扫描，范围与当前簇相交（含相邻行重叠）即并入该簇
+        group.sort_by_key(|row| row.line_range());
+        let mut clusters: Vec<Vec<FindingRecord>> = Vec::new();
+        let mut cluster_end = i64::MIN;
+        for row in group {
+            let (start, end) = row.line_range();
+            match clusters.last_mut() {
+                Some(cluster) if start <= cluster_end => {
+                    cluster.push(row);
+                    cluster_end = cluster_end.max(end);
+                }
+                _ => {
+                    clusters.push(vec![row]);
+                    cluster_end = end;
+                }
+            }
+        }
+
+        for cluster in clusters.into_iter().filter(|c| c.len() > 1) {
+            result.overlap_groups += 1;
+
+            let line_start = cluster.iter().map(|row| row.line_range().0).min();
+            let line_end = cluster.iter().map(|row| row.line_range().1).max();
+            let latest_scan_id = cluster.iter().filter_map(|row| row.scan_id).max();
+            // 精度相同时取排序靠前（起始行更小）的检测器
+            let detector = cluster
+                .iter()
+                .filter_map(|row| row.detector.clone())
+                .rev()
+                .max_by_key(|d| deepaudit_core::detector_precision(d));
+            let mut detectors: Vec<String> = Vec::new();
+            for d in cluster.iter().flat_map(|row| row.detectors()) {
+                if !detectors.contains(&d) {
+                    detectors.push(d);
+                }
+            }
+            let merged_detectors = serde_json::to_string(&detectors).unwrap_or_default();
+
+            let (keeper, duplicates) = split_keeper(cluster);
+            absorb_duplicates(tx, &keeper, &duplicates).await?;
+            result.removed += duplicates.len();
+            result.overlap_removed += duplicates.len();
+
+            sqlx::query(
+                "UPDATE findings
+                 SET line_start = ?, line_end = ?, detector = COALESCE(?, detector),
+                     merged_detectors = ?, scan_id = ?
+                 WHERE id = ?"
+            )
+            .bind(line_start)
+            .bind(line_end)
+            .bind(detector)
+            .bind(merged_detectors)
+            .bind(latest_scan_id)
+            .bind(keeper.id)
+            .execute(&mut **tx)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// 选出组内保留的记录：已审查的优先，其次是最新创建的（按创建时间和 id）
+fn split_keeper(mut group: Vec<FindingRecord>) -> (FindingRecord, Vec<FindingRecord>) {
+    group.sort_by(|a, b| {
+        b.reviewed()
+            .cmp(&a.reviewed())
+            .then_with(|| b.created_at.cmp(&a.created_at))
+            .then_with(|| b.id.cmp(&a.id))
+    });
+    let keeper = group.remove(0);
+    (keeper, group)
+}
+
+/// 把重复记录的标签并入保留的记录，然后删除重复记录
+async fn absorb_duplicates(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    keeper: &FindingRecord,
+    duplicates: &[FindingRecord],
+) -> Result<(), sqlx::Error> {
+    for duplicate in duplicates {
+        sqlx::query(
+            "INSERT OR IGNORE INTO finding_tags (finding_id, tag, created_at)
+             SELECT ?, tag, created_at FROM finding_tags WHERE finding_id = ?"
+        )
+        .bind(&keeper.finding_id)
+        .bind(&duplicate.finding_id)
+        .execute(&mut **tx)
+        .await?;
+        sqlx::query("DELETE FROM finding_tags WHERE finding_id = ?")
+            .bind(&duplicate.finding_id)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query("DELETE FROM findings WHERE id = ?")
+            .bind(duplicate.id)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn insert_finding(state: &AppState, finding_id: &str, status: &str, scan_id: i64) {
+        sqlx::query(
+            "INSERT INTO findings (project_id, finding_id, file_path, line_start, line_end, detector, vuln_type, severity, description, status, scan_id)
+             VALUES (1, ?, 'src/app.py', 2, 2, 'WeakCryptoScanner', 'Weak Crypto', 'medium', 'MD5', ?, ?)"
+        )
+        .bind(finding_id)
+        .bind(status)
+        .bind(scan_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+    }
+
+    #[actix_web::test]
+    async fn dedupe_collapses_duplicates_and_keeps_triage_status() {
+        let (state, _dir) = AppState::for_tests().await;
+        sqlx::query("INSERT INTO projects (id, uuid, name, path) VALUES (1, 'p1', 'demo', '/tmp/demo')")
+            .execute(&state.db)
+            .await
+            .unwrap();
+        // 旧版本每次扫描插入一份随机 ID 的记录，其中一份已被确认
+        insert_finding(&state, "random-1", "new", 1).await;
+        insert_finding(&state, "random-2", "confirmed", 2).await;
+        insert_finding(&state, "random-3", "new", 3).await;
+        sqlx::query("INSERT INTO finding_tags (finding_id, tag) VALUES ('random-3', 'payments')")
+            .execute(&state.db)
+            .await
+            .unwrap();
+
+        let result = run_dedupe(&state, false).await.unwrap();
+        assert_eq!(result.duplicate_groups, 1);
+        assert_eq!(result.removed, 2);
+        assert_eq!(result.migrated_ids, 1);
+
+        let rows: Vec<(String, String, i64)> = sqlx::query_as("SELECT finding_id, status, scan_id FROM findings")
+            .fetch_all(&state.db)
+            .await
+            .unwrap();
+        let stable_id = deepaudit_core::scoped_stable_finding_id("1", "src/app.py", 2, "Weak Crypto", "WeakCryptoScanner");
+        assert_eq!(rows, vec![(stable_id.clone(), "confirmed".to_string(), 3)]);
+
+        let tags: Vec<(String, String)> = sqlx::query_as("SELECT finding_id, tag FROM finding_tags")
+            .fetch_all(&state.db)
+            .await
+            .unwrap();
+        assert_eq!(tags, vec![(stable_id, "payments".to_string())]);
+
+        // 再次去重没有可处理的记录
+        let again = run_dedupe(&state, false).await.unwrap();
+        assert_eq!((again.removed, again.migrated_ids), (0, 0));
+    }
+
+    #[actix_web::test]
+    async fn overlap_merge_combines_overlapping_ranges_of_the_same_vuln_type() {
+        let (state, _dir) = AppState::for_tests().await;
+        sqlx::query("INSERT INTO projects (id, uuid, name, path) VALUES (1, 'p1', 'demo', '/tmp/demo')")
+            .execute(&state.db)
+            .await
+            .unwrap();
+        for (finding_id, line_start, line_end, detector, vuln_type) in [
+            ("regex", 10, 10, "RegexRule: sql-concat", "SQL Injection"),
+            ("ast", 8, 12, "ASTRule: sql-execute", "SQL Injection"),
+            ("tail", 12, 14, "SqlInjectionScanner", "SQL Injection"),
+            ("elsewhere", 30, 30, "RegexRule: sql-concat", "SQL Injection"),
+            ("other-type", 10, 10, "WeakCryptoScanner", "Weak Crypto"),
+        ] {
+            sqlx::query(
+                "INSERT INTO findings (project_id, finding_id, file_path, line_start, line_end, detector, vuln_type, severity, description, status, scan_id)
+                 VALUES (1, ?, 'src/db.py', ?, ?, ?, ?, 'high', 'query', 'new', 1)"
+            )
+            .bind(finding_id)
+            .bind(line_start)
+            .bind(line_end)
+            .bind(detector)
+            .bind(vuln_type)
+            .execute(&state.db)
+            .await
+            .unwrap();
+        }
+        let count = || async {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM findings")
+                .fetch_one(&state.db)
+                .await
+                .unwrap()
+        };
+
+        // 未开启时只做精确去重
+        let exact = run_dedupe(&state, false).await.unwrap();
+        assert_eq!((exact.removed, exact.overlap_groups), (0, 0));
+        assert_eq!(count().await, 5);
+
+        let merged = run_dedupe(&state, true).await.unwrap();
+        assert_eq!((merged.overlap_groups, merged.overlap_removed, merged.removed), (1, 2, 2));
+        assert_eq!(count().await, 3);
+
+        let row: (i64, i64, String, String) = sqlx::query_as(
+            "SELECT line_start, line_end, detector, merged_detectors FROM findings
+             WHERE vuln_type = 'SQL Injection' AND line_start < 30"
+        )
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+        assert_eq!((row.0, row.1), (8, 14));
+        assert_eq!(row.2, "ASTRule: sql-execute");
+        let detectors: Vec<String> = serde_json::from_str(&row.3).unwrap();
+        assert_eq!(detectors, ["ASTRule: sql-execute", "RegexRule: sql-concat", "SqlInjectionScanner"]);
+
+        let untouched: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT line_start, vuln_type FROM findings WHERE merged_detectors IS NULL ORDER BY line_start"
+        )
+        .fetch_all(&state.db)
+        .await
+        .unwrap();
+        assert_eq!(untouched, [(10, "Weak Crypto".to_string()), (30, "SQL Injection".to_string())]);
+    }
+}
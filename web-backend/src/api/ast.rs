@@ -1,12 +1,21 @@
-use actix_web::{web, HttpResponse, Responder};
+use actix_multipart::Multipart;
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use futures_util::TryStreamExt;
 use serde::{Deserialize, Serialize};
 use crate::state::AppState;
+use crate::upload::read_field_to_vec;
 use uuid::Uuid;
 
 #[derive(Serialize, Deserialize)]
 pub struct BuildIndexRequest {
     pub project_path: String,
+    /// 与 project_path 一起索引的其他根目录，合并到同一个索引中
+    #[serde(default)]
+    pub project_paths: Option<Vec<String>>,
     pub project_id: Option<i64>,  // 新增：项目ID，用于保存到数据库
+    /// 遍历的最大目录深度，不填使用默认值
+    #[serde(default)]
+    pub max_depth: Option<usize>,
 }
 
 #[derive(Serialize)]
@@ -27,6 +36,9 @@ pub struct GetCallGraphRequest {
     pub max_depth: Option<usize>,
     pub project_id: Option<i64>,  // 新增：项目ID，用于保存图谱
     pub save_graph: Option<bool>,  // 新增：是否保存图谱到数据库
+    /// 图的粒度："function"（默认）、"file" 或 "module"，后两者把调用边按文件/目录合并并带上 weight
+    #[serde(default)]
+    pub granularity: deepaudit_core::CallGraphGranularity,
 }
 
 #[derive(Serialize)]
@@ -35,6 +47,42 @@ pub struct Symbol {
     pub kind: String,
     pub file_path: String,
     pub line: usize,
+    /// 模糊搜索的匹配度（0~1）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+}
+
+// 新增：按类型列出符号的结果
+#[derive(Serialize)]
+pub struct SymbolListItem {
+    pub name: String,
+    pub kind: String,
+    pub file_path: String,
+    pub line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner_class: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<String>,
+    /// 装饰器（Python）或注解（Java），不含 `@`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub decorators: Vec<String>,
+}
+
+impl From<&deepaudit_core::Symbol> for SymbolListItem {
+    fn from(s: &deepaudit_core::Symbol) -> Self {
+        SymbolListItem {
+            name: s.name.clone(),
+            kind: s.kind_to_string(),
+            file_path: s.file_path.clone(),
+            line: s.start_line as usize,
+            owner_class: s.metadata.get("ownerClass")
+                .or_else(|| s.metadata.get("callerClass"))
+                .and_then(|v| v.as_str())
+                .map(|v| v.to_string()),
+            visibility: s.visibility().map(|v| v.to_string()),
+            decorators: s.decorators().into_iter().map(str::to_string).collect(),
+        }
+    }
 }
 
 // 新增：历史查询请求
@@ -54,6 +102,90 @@ pub struct AstIndexHistory {
     pub created_at: String,
 }
 
+// 新增：文件符号/漏洞统计
+#[derive(Serialize)]
+pub struct FileSymbolSummary {
+    pub file_path: String,
+    pub symbol_count: usize,
+    pub finding_count: i64,
+    /// clean / has_errors / failed，未记录时省略
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parse_health: Option<deepaudit_core::ParseHealth>,
+}
+
+// 新增：临时 Tree-sitter 查询请求
+#[derive(Serialize, Deserialize)]
+pub struct AdhocQueryRequest {
+    pub language: String,
+    pub query: String,
+    pub project_path: String,
+}
+
+// 新增：比较两个项目的符号清单
+#[derive(Serialize, Deserialize)]
+pub struct DiffProjectsRequest {
+    pub project_a: String,
+    pub project_b: String,
+}
+
+// 新增：解析未保存的编辑器缓冲区
+#[derive(Deserialize)]
+pub struct ParseBufferRequest {
+    pub content: String,
+    /// 语言名称（如 "python"、"ts"），与 filename 二选一
+    pub language: Option<String>,
+    /// 缓冲区对应的文件名，按扩展名选择语法，优先于 language
+    pub filename: Option<String>,
+}
+
+/// 解析缓冲区请求体的大小上限
+const MAX_PARSE_BUFFER_BYTES: usize = 8 * 1024 * 1024;
+
+/// 临时查询最多返回的匹配数量
+const MAX_ADHOC_QUERY_MATCHES: usize = 1000;
+
+/// 模糊搜索默认返回的结果数量
+const DEFAULT_FUZZY_SEARCH_LIMIT: usize = 50;
+
+/// symbol_source 单次最多返回的行数，超出部分截断
+const MAX_SYMBOL_SOURCE_LINES: usize = 2000;
+
+// 新增：读取符号所在的完整源码（索引中的 code 字段会被截断）
+#[derive(Deserialize)]
+pub struct SymbolSourceQuery {
+    pub file_path: String,
+    /// 起始行（从 1 开始，包含）
+    pub start_line: usize,
+    /// 结束行（包含）
+    pub end_line: usize,
+}
+
+#[derive(Deserialize)]
+pub struct DependencyGraphQuery {
+    /// 只保留模块名包含该子串的依赖（不区分大小写），如 `pickle`
+    pub filter: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct RenameImpactQuery {
+    pub name: String,
+    /// 拟使用的新名称，用于检查冲突
+    pub new_name: Option<String>,
+    /// 项目ID，提供时确保加载该项目的索引，否则使用当前加载的索引
+    pub project_id: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct SymbolSource {
+    pub file_path: String,
+    pub start_line: usize,
+    /// 实际返回的最后一行（超出文件末尾或行数上限时小于请求的 end_line）
+    pub end_line: usize,
+    pub source: String,
+    /// 是否因行数上限被截断
+    pub truncated: bool,
+}
+
 #[derive(Serialize)]
 pub struct CodeGraphHistory {
     pub id: i64,
@@ -68,9 +200,26 @@ pub fn configure_ast_routes(cfg: &mut web::ServiceConfig) {
     cfg
         .route("/build_index", web::post().to(build_index))
         .route("/search_symbol/{name}", web::get().to(search_symbol))
+        .route("/list/{kind}", web::get().to(list_symbols_by_kind))
+        .route("/unused/{project_id}", web::get().to(list_unused_functions))
+        .route("/routes/{project_id}", web::get().to(list_route_handlers))
+        .route("/entrypoints/{project_id}", web::get().to(list_entrypoints))
+        .route("/dependency_graph/{project_id}", web::get().to(get_dependency_graph))
+        .route("/rename_impact", web::get().to(get_rename_impact))
         .route("/get_call_graph", web::post().to(get_call_graph))
         .route("/get_code_structure/{file_path}", web::get().to(get_code_structure))
+        .route("/symbol_source", web::get().to(get_symbol_source))
         .route("/get_knowledge_graph", web::post().to(get_knowledge_graph))
+        .route("/files/{project_id}", web::get().to(get_project_files))
+        .route("/query", web::post().to(run_adhoc_query))
+        .service(
+            web::resource("/parse")
+                .app_data(web::JsonConfig::default().limit(MAX_PARSE_BUFFER_BYTES))
+                .route(web::post().to(parse_buffer)),
+        )
+        .route("/diff_projects", web::post().to(diff_projects))
+        .route("/cache/{project_id}/export", web::get().to(export_ast_cache))
+        .route("/cache/{project_id}/import", web::post().to(import_ast_cache))
         // 新增：历史查询端点
         .route("/history/indices/{project_id}", web::get().to(get_index_history))
         .route("/history/graphs/{project_id}", web::get().to(get_graph_history));
@@ -102,7 +251,17 @@ pub async fn build_index(
     }
 
     // 扫描项目（如果有缓存，这将是增量更新）
-    let files_processed = match engine.scan_project(&req.project_path) {
+    let mut roots = vec![req.project_path.as_str()];
+    for path in req.project_paths.iter().flatten() {
+        if !roots.contains(&path.as_str()) {
+            roots.push(path);
+        }
+    }
+    let scanned = match req.max_depth {
+        Some(max_depth) => engine.scan_projects_with_max_depth(&roots, max_depth),
+        None => engine.scan_projects(&roots),
+    };
+    let files_processed = match scanned {
         Ok(count) => count,
         Err(e) => {
             return HttpResponse::InternalServerError().json(serde_json::json!({
@@ -120,12 +279,14 @@ pub async fn build_index(
         }
     };
 
+    let parse_health = engine.get_file_parse_health().unwrap_or_default();
+
     drop(engine);
 
     // 如果提供了 project_id，保存到数据库
     let mut index_id = None;
     if let Some(project_id) = req.project_id {
-        match save_ast_index_to_db(&state, project_id, &req.project_path, files_processed, &symbols).await {
+        match save_ast_index_to_db(&state, project_id, &req.project_path, files_processed, &symbols, &parse_health).await {
             Ok(id) => {
                 index_id = Some(id);
                 tracing::info!("Saved AST index to database: id={}", id);
@@ -159,8 +320,8 @@ async fn load_ast_index_from_db(
     tracing::info!("Loading AST index from database for project {}", project_id);
 
     // 查询最近的索引
-    let row = match sqlx::query_as::<_, (i64, String, String)>(
-        "SELECT id, index_version, index_data
+    let row = match sqlx::query_as::<_, (i64, String, String, Option<String>)>(
+        "SELECT id, index_version, index_data, parse_health
          FROM ast_indices
          WHERE project_id = ?
          ORDER BY created_at DESC
@@ -177,18 +338,18 @@ async fn load_ast_index_from_db(
         }
     };
 
-    let (id, version, index_data_json) = row;
+    let (id, version, index_data_json, parse_health_json) = row;
     tracing::info!("Found AST index {} (version {}) in database", id, version);
 
-    // 从 JSON 反序列化符号
+    // 从 JSON 反序列化符号，失败时回退到 symbols 表
     let symbols: Vec<deepaudit_core::Symbol> = match serde_json::from_str::<Vec<deepaudit_core::Symbol>>(&index_data_json) {
         Ok(s) => {
             tracing::info!("Deserialized {} symbols from database", s.len());
             s
         }
         Err(e) => {
-            tracing::error!("Failed to deserialize symbols from database: {}", e);
-            return Err(e.into());
+            tracing::warn!("Failed to deserialize index_data ({}), reading symbols table instead", e);
+            load_symbols_from_db(state, id).await?
         }
     };
 
@@ -196,6 +357,17 @@ async fn load_ast_index_from_db(
         tracing::warn!("Database returned empty symbol list for project {}", project_id);
     }
 
+    // 各文件的解析状况（旧索引没有该列）
+    let parse_health: std::collections::HashMap<String, deepaudit_core::ParseHealth> = parse_health_json
+        .and_then(|json| match serde_json::from_str(&json) {
+            Ok(health) => Some(health),
+            Err(e) => {
+                tracing::warn!("Failed to deserialize parse health ({}), ignoring", e);
+                None
+            }
+        })
+        .unwrap_or_default();
+
     // 构建文件索引
     let mut index = std::collections::HashMap::new();
     let mut class_map = std::collections::HashMap::new();
@@ -209,6 +381,11 @@ async fn load_ast_index_from_db(
 
     tracing::info!("Grouped symbols into {} files", file_symbols.len());
 
+    // 解析失败或没有符号的文件也需要保留解析状况
+    for file_path in parse_health.keys() {
+        file_symbols.entry(file_path.clone()).or_default();
+    }
+
     // 为每个文件创建 FileIndex
     for (file_path, symbols) in file_symbols {
         // 获取文件的修改时间
@@ -221,7 +398,8 @@ async fn load_ast_index_from_db(
             Err(_) => 0,
         };
 
-        index.insert(file_path.clone(), deepaudit_core::FileIndex { mtime, symbols });
+        let parse_health = parse_health.get(&file_path).copied();
+        index.insert(file_path.clone(), deepaudit_core::FileIndex { mtime, symbols, parse_health });
 
         // 构建类映射
         for symbol in index.get(&file_path).unwrap().symbols.iter() {
@@ -240,6 +418,72 @@ async fn load_ast_index_from_db(
     }))
 }
 
+/// 从 symbols 表的一行重建 Symbol
+///
+/// `symbol_type` 为保存时 `SymbolKind` 的 Debug 名称，`metadata` 为 JSON，`parent_name` 为逗号分隔的父类列表。
+/// 代码片段不入库，重建后 `code` 为空。
+fn symbol_from_db_row(row: &sqlx::sqlite::SqliteRow) -> Result<deepaudit_core::Symbol, String> {
+    use sqlx::Row;
+
+    let get_err = |e: sqlx::Error| format!("Invalid symbol row: {}", e);
+    let name: String = row.try_get("symbol_name").map_err(get_err)?;
+    let symbol_type: String = row.try_get("symbol_type").map_err(get_err)?;
+    let file_path: String = row.try_get("file_path").map_err(get_err)?;
+    let line_number: Option<i64> = row.try_get("line_number").map_err(get_err)?;
+    let end_line: Option<i64> = row.try_get("end_line").map_err(get_err)?;
+    let parent_name: Option<String> = row.try_get("parent_name").map_err(get_err)?;
+    let metadata_json: Option<String> = row.try_get("metadata").map_err(get_err)?;
+
+    let kind = symbol_type.parse::<deepaudit_core::SymbolKind>()?;
+
+    let metadata: std::collections::HashMap<String, serde_json::Value> = match metadata_json {
+        Some(json) if !json.is_empty() => serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid symbol metadata: {}", e))?,
+        _ => std::collections::HashMap::new(),
+    };
+
+    let parent_classes: Vec<String> = parent_name
+        .map(|names| {
+            names
+                .split(',')
+                .filter(|n| !n.is_empty())
+                .map(|n| n.to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let start_line = line_number.unwrap_or(0) as u32;
+    Ok(
+        deepaudit_core::Symbol::new(name, kind, file_path, start_line, String::new())
+            .with_end_line(end_line.map(|l| l as u32).unwrap_or(start_line))
+            .with_parent_classes(parent_classes)
+            .with_metadata(metadata),
+    )
+}
+
+/// 读取某次 AST 索引在 symbols 表中保存的全部符号
+async fn load_symbols_from_db(
+    state: &AppState,
+    ast_index_id: i64,
+) -> Result<Vec<deepaudit_core::Symbol>, Box<dyn std::error::Error>> {
+    let rows = sqlx::query(
+        "SELECT symbol_name, symbol_type, file_path, line_number, end_line, parent_name, metadata
+         FROM symbols
+         WHERE ast_index_id = ?
+         ORDER BY id"
+    )
+    .bind(ast_index_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let symbols = rows
+        .iter()
+        .map(symbol_from_db_row)
+        .collect::<Result<Vec<_>, _>>()?;
+    tracing::info!("Loaded {} symbols from symbols table (index {})", symbols.len(), ast_index_id);
+    Ok(symbols)
+}
+
 /// 保存 AST 索引到数据库
 async fn save_ast_index_to_db(
     state: &AppState,
@@ -247,6 +491,7 @@ async fn save_ast_index_to_db(
     project_path: &str,
     files_processed: usize,
     symbols: &[deepaudit_core::Symbol],
+    parse_health: &std::collections::HashMap<String, deepaudit_core::ParseHealth>,
 ) -> Result<i64, Box<dyn std::error::Error>> {
     let mut tx = state.db.begin().await?;
 
@@ -255,11 +500,16 @@ async fn save_ast_index_to_db(
 
     // 序列化符号数据
     let index_data = serde_json::to_string(symbols)?;
+    let parse_health_json = if parse_health.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_string(parse_health)?)
+    };
 
     // 1. 插入 ast_indices 记录
     let idx = sqlx::query_scalar::<_, i64>(
-        "INSERT INTO ast_indices (project_id, index_version, total_symbols, total_files, index_data)
-         VALUES (?, ?, ?, ?, ?)
+        "INSERT INTO ast_indices (project_id, index_version, total_symbols, total_files, index_data, parse_health)
+         VALUES (?, ?, ?, ?, ?, ?)
          RETURNING id"
     )
     .bind(project_id)
@@ -267,6 +517,7 @@ async fn save_ast_index_to_db(
     .bind(symbols.len() as i64)
     .bind(files_processed as i64)
     .bind(&index_data)
+    .bind(&parse_health_json)
     .fetch_one(&mut *tx)
     .await?;
 
@@ -323,7 +574,23 @@ pub async fn search_symbol(
 
     let mut engine = state.ast_engine.lock().await;
 
-    let results = match engine.search_symbols(&name) {
+    // ?fuzzy=true 时按匹配度排序返回前 limit 个结果
+    let fuzzy = query.get("fuzzy").map(|v| v == "true" || v == "1").unwrap_or(false);
+    let results = if fuzzy {
+        let limit = query
+            .get("limit")
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_FUZZY_SEARCH_LIMIT);
+        engine
+            .fuzzy_search(&name, limit)
+            .map(|results| results.into_iter().map(|(s, score)| (s, Some(score))).collect::<Vec<_>>())
+    } else {
+        engine
+            .search_symbols(&name)
+            .map(|results| results.into_iter().map(|s| (s, None)).collect::<Vec<_>>())
+    };
+
+    let results = match results {
         Ok(results) => results,
         Err(_) => {
             // 没有缓存，返回空结果
@@ -334,17 +601,240 @@ pub async fn search_symbol(
 
     let symbols: Vec<Symbol> = results
         .iter()
-        .map(|s| Symbol {
+        .map(|(s, score)| Symbol {
             name: s.name.clone(),
             kind: format!("{:?}", s.kind),
             file_path: s.file_path.clone(),
             line: s.line as usize,
+            score: *score,
         })
         .collect();
 
     HttpResponse::Ok().json(symbols)
 }
 
+/// 列出项目中某一类型的全部符号（class / function / method / interface / struct / enum / import），
+/// 支持 `?visibility=public|private|protected|internal` 过滤
+pub async fn list_symbols_by_kind(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> impl Responder {
+    let kind_str = path.into_inner();
+    let kind = match kind_str.parse::<deepaudit_core::SymbolKind>() {
+        Ok(deepaudit_core::SymbolKind::MethodCall) | Err(_) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("Unsupported symbol kind: {}", kind_str)
+            }));
+        }
+        Ok(kind) => kind,
+    };
+
+    // 如果提供了项目信息，确保缓存已加载
+    if let (Some(project_id_str), Some(project_path)) = (query.get("project_id"), query.get("project_path")) {
+        if let Ok(project_id) = project_id_str.parse::<i64>() {
+            let _ = ensure_cache_loaded(&state, project_id, project_path).await;
+        }
+    }
+
+    let engine = state.ast_engine.lock().await;
+
+    // 可选的可见性过滤，如 ?visibility=public
+    let visibility = query.get("visibility").map(|v| v.to_lowercase());
+
+    let results = match engine.list_by_kind(kind, visibility.as_deref()) {
+        Ok(results) => results,
+        Err(_) => {
+            tracing::info!("No AST cache loaded, returning empty symbol list");
+            return HttpResponse::Ok().json(vec![] as Vec<SymbolListItem>);
+        }
+    };
+
+    let symbols: Vec<SymbolListItem> = results.iter().map(SymbolListItem::from).collect();
+
+    HttpResponse::Ok().json(symbols)
+}
+
+/// 列出项目中没有调用点的函数和方法，作为死代码审查的线索（按名称匹配的启发式结果，
+/// 反射、动态分派、框架入口和导出 API 会被误报）
+pub async fn list_unused_functions(
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let project_id = path.into_inner();
+    let Some(project_path) = get_project_path(&state, project_id).await else {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("Project {} not found", project_id)
+        }));
+    };
+
+    if let Err(e) = ensure_cache_loaded(&state, project_id, &project_path).await {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("No AST index for project {}, build one first: {}", project_id, e)
+        }));
+    }
+
+    let engine = state.ast_engine.lock().await;
+    match engine.find_unused_functions() {
+        Ok(results) => {
+            let symbols: Vec<SymbolListItem> = results.iter().map(SymbolListItem::from).collect();
+            HttpResponse::Ok().json(symbols)
+        }
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to query unused functions: {}", e)
+        })),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RouteHandlersQuery {
+    /// 匹配装饰器/注解（不含 `@`）的正则表达式，缺省匹配常见 Web 框架的路由装饰器
+    pub pattern: Option<String>,
+}
+
+/// 列出项目中带有路由装饰器/注解的函数和方法（HTTP 入口），可通过 `?pattern=` 自定义匹配
+pub async fn list_route_handlers(
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+    query: web::Query<RouteHandlersQuery>,
+) -> impl Responder {
+    let project_id = path.into_inner();
+    let Some(project_path) = get_project_path(&state, project_id).await else {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("Project {} not found", project_id)
+        }));
+    };
+
+    if let Err(e) = ensure_cache_loaded(&state, project_id, &project_path).await {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("No AST index for project {}, build one first: {}", project_id, e)
+        }));
+    }
+
+    let engine = state.ast_engine.lock().await;
+    match engine.find_route_handlers(query.pattern.as_deref()) {
+        Ok(results) => {
+            let symbols: Vec<SymbolListItem> = results.iter().map(SymbolListItem::from).collect();
+            HttpResponse::Ok().json(symbols)
+        }
+        Err(e) if e.starts_with("Invalid decorator pattern") => {
+            HttpResponse::BadRequest().json(serde_json::json!({ "error": e }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to query route handlers: {}", e)
+        })),
+    }
+}
+
+/// Web 框架入口：符号信息加上识别出的框架、HTTP 方法和路径
+#[derive(Serialize)]
+pub struct EntrypointItem {
+    #[serde(flatten)]
+    pub symbol: SymbolListItem,
+    #[serde(flatten)]
+    pub route: deepaudit_core::RouteInfo,
+}
+
+/// 列出项目的 Web 框架入口（Flask/FastAPI/DRF 路由函数、Spring 映射方法、Express 路由注册），
+/// 即审计时的攻击面清单
+pub async fn list_entrypoints(
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let project_id = path.into_inner();
+    let Some(project_path) = get_project_path(&state, project_id).await else {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("Project {} not found", project_id)
+        }));
+    };
+
+    if let Err(e) = ensure_cache_loaded(&state, project_id, &project_path).await {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("No AST index for project {}, build one first: {}", project_id, e)
+        }));
+    }
+
+    let engine = state.ast_engine.lock().await;
+    match engine.find_entrypoints() {
+        Ok(results) => {
+            let entrypoints: Vec<EntrypointItem> = results
+                .iter()
+                .filter_map(|symbol| {
+                    Some(EntrypointItem {
+                        route: deepaudit_core::route_info(symbol)?,
+                        symbol: SymbolListItem::from(symbol),
+                    })
+                })
+                .collect();
+            HttpResponse::Ok().json(entrypoints)
+        }
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to query entrypoints: {}", e)
+        })),
+    }
+}
+
+/// 项目的文件 -> 模块依赖图（由索引中的导入符号构建）
+pub async fn get_dependency_graph(
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+    query: web::Query<DependencyGraphQuery>,
+) -> impl Responder {
+    let project_id = path.into_inner();
+    let Some(project_path) = get_project_path(&state, project_id).await else {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("Project {} not found", project_id)
+        }));
+    };
+
+    if let Err(e) = ensure_cache_loaded(&state, project_id, &project_path).await {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("No AST index for project {}, build one first: {}", project_id, e)
+        }));
+    }
+
+    let engine = state.ast_engine.lock().await;
+    match engine.get_dependency_graph(query.filter.as_deref()) {
+        Ok(graph) => HttpResponse::Ok().json(graph),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to build dependency graph: {}", e)
+        })),
+    }
+}
+
+/// 重命名符号前的影响分析（只读）：定义、调用点以及与新名称冲突的符号
+pub async fn get_rename_impact(
+    state: web::Data<AppState>,
+    query: web::Query<RenameImpactQuery>,
+) -> impl Responder {
+    if query.name.trim().is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "name is required"
+        }));
+    }
+
+    if let Some(project_id) = query.project_id {
+        let Some(project_path) = get_project_path(&state, project_id).await else {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": format!("Project {} not found", project_id)
+            }));
+        };
+        if let Err(e) = ensure_cache_loaded(&state, project_id, &project_path).await {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": format!("No AST index for project {}, build one first: {}", project_id, e)
+            }));
+        }
+    }
+
+    let engine = state.ast_engine.lock().await;
+    match engine.rename_impact(&query.name, query.new_name.as_deref()) {
+        Ok(impact) => HttpResponse::Ok().json(impact),
+        Err(e) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("No AST index loaded: {}", e)
+        })),
+    }
+}
+
 pub async fn get_call_graph(
     state: web::Data<AppState>,
     req: web::Json<GetCallGraphRequest>,
@@ -352,7 +842,7 @@ pub async fn get_call_graph(
     let mut engine = state.ast_engine.lock().await;
 
     let max_depth = req.max_depth.unwrap_or(3);
-    let call_graph = match engine.get_call_graph(&req.entry_function, max_depth) {
+    let call_graph = match engine.get_call_graph_with_granularity(&req.entry_function, max_depth, req.granularity) {
         Ok(graph) => graph,
         Err(_) => {
             // 没有缓存，返回空图
@@ -370,7 +860,12 @@ pub async fn get_call_graph(
     let mut graph_id = None;
     if req.save_graph.unwrap_or(false) {
         if let Some(project_id) = req.project_id {
-            match save_code_graph_to_db(&state, project_id, "call_graph", Some(&req.entry_function), &call_graph).await {
+            // 合并后的图不写入 call_relations（按函数记录），以 call_graph_file / call_graph_module 区分
+            let graph_type = match req.granularity {
+                deepaudit_core::CallGraphGranularity::Function => "call_graph".to_string(),
+                granularity => format!("call_graph_{}", granularity.as_str()),
+            };
+            match save_code_graph_to_db(&state, project_id, &graph_type, Some(&req.entry_function), &call_graph).await {
                 Ok(id) => {
                     graph_id = Some(id);
                     tracing::info!("Saved call graph to database: id={}", id);
@@ -456,6 +951,54 @@ async fn save_code_graph_to_db(
     Ok(graph_id)
 }
 
+/// 读取文件中 [start_line, end_line] 的源码，用于按需展示符号的完整内容
+pub async fn get_symbol_source(query: web::Query<SymbolSourceQuery>) -> impl Responder {
+    let SymbolSourceQuery { file_path, start_line, end_line } = query.into_inner();
+
+    if start_line == 0 || end_line < start_line {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Invalid line range {}-{}", start_line, end_line)
+        }));
+    }
+
+    let content = match tokio::fs::read_to_string(&file_path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": format!("File not found: {}", file_path)
+            }));
+        }
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to read file: {}", e)
+            }));
+        }
+    };
+
+    let lines: Vec<&str> = content.lines().collect();
+    if start_line > lines.len() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Line {} is past the end of {} ({} lines)", start_line, file_path, lines.len())
+        }));
+    }
+
+    let requested_end = end_line.min(lines.len());
+    let truncated = requested_end - start_line + 1 > MAX_SYMBOL_SOURCE_LINES;
+    let end_line = if truncated {
+        start_line + MAX_SYMBOL_SOURCE_LINES - 1
+    } else {
+        requested_end
+    };
+
+    HttpResponse::Ok().json(SymbolSource {
+        file_path,
+        start_line,
+        end_line,
+        source: lines[start_line - 1..end_line].join("\n"),
+        truncated,
+    })
+}
+
 pub async fn get_code_structure(
     state: web::Data<AppState>,
     path: web::Path<String>,
@@ -488,6 +1031,7 @@ pub async fn get_code_structure(
             kind: format!("{:?}", s.kind),
             file_path: s.file_path.clone(),
             line: s.line as usize,
+            score: None,
         })
         .collect();
 
@@ -497,6 +1041,10 @@ pub async fn get_code_structure(
 #[derive(Serialize, Deserialize)]
 pub struct KnowledgeGraphRequest {
     pub limit: Option<usize>,
+    /// 跳过排序后的前 offset 个节点，与 limit 一起分页
+    pub offset: Option<usize>,
+    /// 只把这些类型的符号作为节点（如 ["class", "interface"]），缺省为全部类型
+    pub node_kinds: Option<Vec<String>>,
     pub project_id: Option<i64>,
     pub project_path: Option<String>,
 }
@@ -504,6 +1052,8 @@ pub struct KnowledgeGraphRequest {
 #[derive(Serialize)]
 pub struct KnowledgeGraphResponse {
     pub graph: GraphData,
+    /// 过滤后、分页前的符号总数
+    pub total_nodes: usize,
 }
 
 #[derive(Serialize)]
@@ -601,9 +1151,24 @@ pub async fn get_knowledge_graph(
         let _ = ensure_cache_loaded(&state, project_id, project_path).await;
     }
 
+    let node_kinds = match &req.node_kinds {
+        Some(kinds) => match kinds
+            .iter()
+            .map(|kind| kind.parse::<deepaudit_core::SymbolKind>())
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(kinds) => Some(kinds),
+            Err(e) => {
+                return HttpResponse::BadRequest().json(serde_json::json!({ "error": e }));
+            }
+        },
+        None => None,
+    };
+
     let mut engine = state.ast_engine.lock().await;
 
     let limit = req.limit.unwrap_or(500);
+    let offset = req.offset.unwrap_or(0);
 
     // 获取所有符号作为节点
     let symbols = match engine.get_all_symbols() {
@@ -616,25 +1181,47 @@ pub async fn get_knowledge_graph(
             tracing::info!("No AST cache loaded, returning empty graph: {}", e);
             return HttpResponse::Ok().json(KnowledgeGraphResponse {
                 graph: GraphData { nodes: vec![], edges: vec![] },
+                total_nodes: 0,
             });
         }
     };
 
-    // 限制节点数量
-    let symbols: Vec<_> = symbols.into_iter().take(limit).collect();
-
-    tracing::info!("get_knowledge_graph: using {} symbols (limited from {})", symbols.len(), limit);
-
-    // 创建节点 - 使用唯一 ID (文件路径:符号名:行号)
+    // 按类型过滤，按名称排序后再分页，保证相同 offset/limit 下结果稳定
+    let mut symbols: Vec<_> = match &node_kinds {
+        Some(kinds) => symbols.into_iter().filter(|s| kinds.contains(&s.kind)).collect(),
+        None => symbols,
+    };
+    symbols.sort_by(|a, b| {
+        a.name
+            .cmp(&b.name)
+            .then_with(|| a.file_path.cmp(&b.file_path))
+            .then(a.line.cmp(&b.line))
+    });
+    let total_nodes = symbols.len();
+    let symbols: Vec<_> = symbols.into_iter().skip(offset).take(limit).collect();
+
+    tracing::info!(
+        "get_knowledge_graph: using {} of {} symbols (offset {}, limit {})",
+        symbols.len(),
+        total_nodes,
+        offset,
+        limit
+    );
+
+    // 创建节点 - 使用唯一 ID (文件路径:符号名:行号)，同一位置的重复符号只保留一个节点
+    let mut node_ids = std::collections::HashSet::new();
     let nodes: Vec<GraphNode> = symbols
         .iter()
-        .map(|s| {
+        .filter_map(|s| {
             let unique_id = format!("{}:{}:{}", s.file_path, s.name, s.line);
-            GraphNode {
-                id: unique_id.clone(),
+            if !node_ids.insert(unique_id.clone()) {
+                return None;
+            }
+            Some(GraphNode {
+                id: unique_id,
                 label: s.name.clone(),
                 node_type: format!("{:?}", s.kind),
-            }
+            })
         })
         .collect();
 
@@ -659,8 +1246,11 @@ pub async fn get_knowledge_graph(
         let source_id = format!("{}:{}:{}", symbol.file_path, symbol.name, symbol.line);
 
         match symbol.kind {
-            // 类/接口/结构体：包含方法和字段的关系
-            deepaudit_core::SymbolKind::Class | deepaudit_core::SymbolKind::Interface | deepaudit_core::SymbolKind::Struct => {
+            // 类/接口/结构体/枚举：包含方法和字段的关系
+            deepaudit_core::SymbolKind::Class
+            | deepaudit_core::SymbolKind::Interface
+            | deepaudit_core::SymbolKind::Struct
+            | deepaudit_core::SymbolKind::Enum => {
                 // 继承关系
                 for parent_class in &symbol.parent_classes {
                     if let Some(parent_ids) = name_to_ids.get(parent_class) {
@@ -680,13 +1270,16 @@ pub async fn get_knowledge_graph(
                 // 查找同一文件中属于这个类的方法
                 if let Some(file_syms) = file_symbols.get(&symbol.file_path) {
                     for other in file_syms {
-                        if other.line > symbol.line && other.line < symbol.line + 100 {
+                        // 解析器已记录所属类型（如 Rust impl 块中的方法）时直接建立包含关系
+                        let owned = other.metadata.get("ownerClass").and_then(|v| v.as_str())
+                            == Some(symbol.name.as_str());
+                        if owned || (other.line > symbol.line && other.line < symbol.line + 100) {
                             match other.kind {
                                 deepaudit_core::SymbolKind::Method | deepaudit_core::SymbolKind::Function => {
                                     // 检查是否可能是这个类的成员
                                     let other_code_lower = other.code.to_lowercase();
                                     let symbol_name_lower = symbol.name.to_lowercase();
-                                    if other_code_lower.contains(&symbol_name_lower) || other.package.contains(&symbol.name) {
+                                    if owned || other_code_lower.contains(&symbol_name_lower) || other.package.contains(&symbol.name) {
                                         let target_id = format!("{}:{}:{}", other.file_path, other.name, other.line);
                                         edges.push(GraphEdge {
                                             id: format!("edge_{}", edge_id),
@@ -755,9 +1348,225 @@ pub async fn get_knowledge_graph(
         }
     }
 
+    // 按 (source, target, label) 去重，并丢弃端点不在节点集合中的边
+    let mut seen_edges = std::collections::HashSet::new();
+    edges.retain(|e: &GraphEdge| {
+        node_ids.contains(&e.source)
+            && node_ids.contains(&e.target)
+            && seen_edges.insert((e.source.clone(), e.target.clone(), e.label.clone()))
+    });
+    for (i, edge) in edges.iter_mut().enumerate() {
+        edge.id = format!("edge_{}", i);
+    }
+
     HttpResponse::Ok().json(KnowledgeGraphResponse {
         graph: GraphData { nodes, edges },
+        total_nodes,
+    })
+}
+
+/// 根据项目ID查询项目路径
+async fn get_project_path(state: &AppState, project_id: i64) -> Option<String> {
+    sqlx::query_scalar::<_, String>("SELECT path FROM projects WHERE id = ?")
+        .bind(project_id)
+        .fetch_optional(&state.db)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// 在项目中执行任意 Tree-sitter 查询，返回捕获结果
+pub async fn run_adhoc_query(req: web::Json<AdhocQueryRequest>) -> impl Responder {
+    let req = req.into_inner();
+    let project_path = std::path::PathBuf::from(&req.project_path);
+    if !project_path.is_dir() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Project path not found: {}", req.project_path)
+        }));
+    }
+
+    let result = web::block(move || {
+        deepaudit_core::run_adhoc_query(
+            &project_path,
+            &req.language,
+            &req.query,
+            MAX_ADHOC_QUERY_MATCHES,
+        )
+    })
+    .await;
+
+    match result {
+        Ok(Ok(matches)) => HttpResponse::Ok().json(matches),
+        // 查询编译失败（语法错误或语言不支持）
+        Ok(Err(e)) => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": e
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Query execution failed: {}", e)
+        })),
+    }
+}
+
+/// 解析一段未保存的代码并返回其中的符号，不读写任何缓存或数据库
+pub async fn parse_buffer(req: web::Json<ParseBufferRequest>) -> impl Responder {
+    let req = req.into_inner();
+    // 符号的 file_path 使用请求中的文件名，未提供时按语言合成一个
+    let file_path = match (&req.filename, &req.language) {
+        (Some(filename), _) => std::path::PathBuf::from(filename),
+        (None, Some(language)) => {
+            let language = deepaudit_core::rules::model::Language::from(language.as_str());
+            match language.extensions().first() {
+                Some(ext) => std::path::PathBuf::from(format!("buffer.{}", ext)),
+                None => {
+                    return HttpResponse::BadRequest().json(serde_json::json!({
+                        "error": format!("Unsupported language: {}", language.as_str())
+                    }));
+                }
+            }
+        }
+        (None, None) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Either language or filename is required"
+            }));
+        }
+    };
+
+    let result = web::block(move || {
+        let mut parser = deepaudit_core::ASTParser::new();
+        if !parser.supports(&file_path) {
+            return Ok(None);
+        }
+        parser.parse_file(&file_path, &req.content).map(Some)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(Some(symbols))) => {
+            let symbols: Vec<serde_json::Value> = symbols.iter().map(|s| s.to_dict()).collect();
+            HttpResponse::Ok().json(symbols)
+        }
+        Ok(Ok(None)) => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Unsupported language for this buffer"
+        })),
+        Ok(Err(e)) => HttpResponse::UnprocessableEntity().json(serde_json::json!({
+            "error": e
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Parsing failed: {}", e)
+        })),
+    }
+}
+
+/// 为指定路径建立（或增量更新）索引，使用独立的引擎以免影响当前加载的项目
+fn index_project_for_diff(project_path: &str) -> Result<deepaudit_core::CacheData, String> {
+    let engine = deepaudit_core::ASTEngine::new(crate::state::CACHE_DIR);
+    engine.use_repository(project_path);
+    engine.scan_project(project_path)?;
+    engine.cache_data()
+}
+
+/// 比较两个项目的符号清单，列出各自独有的函数/类等定义
+pub async fn diff_projects(req: web::Json<DiffProjectsRequest>) -> impl Responder {
+    let req = req.into_inner();
+    for path in [&req.project_a, &req.project_b] {
+        if !std::path::Path::new(path).is_dir() {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("Project path not found: {}", path)
+            }));
+        }
+    }
+
+    let result = web::block(move || -> Result<deepaudit_core::SymbolDiff, String> {
+        let a = index_project_for_diff(&req.project_a)?;
+        let b = index_project_for_diff(&req.project_b)?;
+        Ok(deepaudit_core::diff_symbols(&a, &b))
     })
+    .await;
+
+    match result {
+        Ok(Ok(diff)) => HttpResponse::Ok().json(diff),
+        Ok(Err(e)) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to index project: {}", e)
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Symbol diff failed: {}", e)
+        })),
+    }
+}
+
+/// 获取项目中的文件列表，并附带每个文件的符号数量和漏洞数量
+pub async fn get_project_files(
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let project_id = path.into_inner();
+
+    // 确保缓存已加载，否则引擎中可能是其他项目的数据
+    let project_path = get_project_path(&state, project_id).await;
+    let cache_loaded = match &project_path {
+        Some(project_path) => ensure_cache_loaded(&state, project_id, project_path).await.is_ok(),
+        None => false,
+    };
+
+    let (symbol_counts, parse_health) = if cache_loaded {
+        let engine = state.ast_engine.lock().await;
+        let counts = engine.get_file_symbol_counts().unwrap_or_default();
+        let parse_health = engine.get_file_parse_health().unwrap_or_default();
+        drop(engine);
+
+        // 发现中存储的是相对项目根目录的路径，符号路径也统一为相对路径以便合并
+        let root = std::path::PathBuf::from(project_path.as_deref().unwrap_or_default());
+        let relative = |file_path: String| {
+            std::path::Path::new(&file_path)
+                .strip_prefix(&root)
+                .map(|p| p.to_string_lossy().replace('\\', "/"))
+                .unwrap_or(file_path)
+        };
+        let symbol_counts: std::collections::HashMap<_, _> = counts
+            .into_iter()
+            .map(|(file_path, count)| (relative(file_path), count))
+            .collect();
+        let parse_health: std::collections::HashMap<_, _> = parse_health
+            .into_iter()
+            .map(|(file_path, health)| (relative(file_path), health))
+            .collect();
+        (symbol_counts, parse_health)
+    } else {
+        tracing::info!("No AST cache loaded for project {}, symbol counts will be empty", project_id);
+        (std::collections::HashMap::new(), std::collections::HashMap::new())
+    };
+
+    let finding_counts = match sqlx::query_as::<_, (String, i64)>(
+        "SELECT file_path, COUNT(*) FROM findings WHERE project_id = ? GROUP BY file_path"
+    )
+    .bind(project_id)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(rows) => rows.into_iter().collect::<std::collections::HashMap<_, _>>(),
+        Err(e) => {
+            tracing::error!("Failed to count findings per file: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to count findings: {}", e)
+            }));
+        }
+    };
+
+    let mut file_paths: Vec<&String> = symbol_counts.keys().chain(finding_counts.keys()).collect();
+    file_paths.sort();
+    file_paths.dedup();
+
+    let files: Vec<FileSymbolSummary> = file_paths
+        .into_iter()
+        .map(|file_path| FileSymbolSummary {
+            file_path: file_path.clone(),
+            symbol_count: symbol_counts.get(file_path).copied().unwrap_or(0),
+            finding_count: finding_counts.get(file_path).copied().unwrap_or(0),
+            parse_health: parse_health.get(file_path).copied(),
+        })
+        .collect();
+
+    HttpResponse::Ok().json(files)
 }
 
 /// 获取项目的 AST 索引历史
@@ -848,3 +1657,482 @@ pub async fn get_graph_history(
 
     HttpResponse::Ok().json(history)
 }
+
+/// 导入的 AST 缓存文件大小上限
+const MAX_AST_CACHE_IMPORT_BYTES: u64 = 512 * 1024 * 1024;
+
+/// 定位项目的 AST 缓存目录，并确认其位于缓存根目录之下
+fn project_cache_manager(project_path: &str) -> Result<deepaudit_core::CacheManager, String> {
+    let mut cache_manager = deepaudit_core::CacheManager::new(crate::state::CACHE_DIR);
+    cache_manager.use_repository(project_path);
+
+    std::fs::create_dir_all(cache_manager.get_cache_dir())
+        .map_err(|e| format!("Failed to create cache directory: {}", e))?;
+    let root = std::fs::canonicalize(cache_manager.get_base_cache_dir())
+        .map_err(|e| format!("Failed to resolve cache root: {}", e))?;
+    let dir = std::fs::canonicalize(cache_manager.get_cache_dir())
+        .map_err(|e| format!("Failed to resolve cache directory: {}", e))?;
+    if dir == root || !dir.starts_with(&root) {
+        return Err(format!("Cache directory {} is outside the cache root", dir.display()));
+    }
+
+    Ok(cache_manager)
+}
+
+/// 下载项目的 AST 索引缓存文件（ast_index.bin），供其他机器导入
+pub async fn export_ast_cache(
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let project_id = path.into_inner();
+    let Some(project_path) = get_project_path(&state, project_id).await else {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("Project {} not found", project_id)
+        }));
+    };
+
+    let cache_manager = match project_cache_manager(&project_path) {
+        Ok(cache_manager) => cache_manager,
+        Err(e) => {
+            tracing::error!("Failed to locate AST cache for project {}: {}", project_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({ "error": e }));
+        }
+    };
+
+    let cache_file = cache_manager.cache_file();
+    let file = match actix_files::NamedFile::open_async(&cache_file).await {
+        Ok(file) => file,
+        Err(_) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": format!("No AST cache built for project {}", project_id)
+            }));
+        }
+    };
+
+    file.set_content_disposition(actix_web::http::header::ContentDisposition {
+        disposition: actix_web::http::header::DispositionType::Attachment,
+        parameters: vec![actix_web::http::header::DispositionParam::Filename(format!(
+            "ast_index_{}.bin",
+            project_id
+        ))],
+    })
+    .into_response(&req)
+}
+
+/// 上传 AST 索引缓存文件并安装到项目的缓存目录，避免在每台机器上重新建立索引
+pub async fn import_ast_cache(
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+    mut payload: Multipart,
+) -> impl Responder {
+    let project_id = path.into_inner();
+    let Some(project_path) = get_project_path(&state, project_id).await else {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("Project {} not found", project_id)
+        }));
+    };
+
+    // 缓存文件需要整体反序列化校验，读取过程计入全局上传容量
+    let mut reservation = state.upload_budget.reserve();
+    let mut data: Option<Vec<u8>> = None;
+    loop {
+        match payload.try_next().await {
+            Ok(Some(mut field)) => {
+                if field.name() != Some("file") {
+                    continue;
+                }
+                match read_field_to_vec(&mut field, MAX_AST_CACHE_IMPORT_BYTES, &mut reservation).await {
+                    Ok(bytes) => data = Some(bytes),
+                    Err(e) => return e.to_response(),
+                }
+            }
+            Ok(None) => break,
+            Err(e) => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": format!("Failed to read multipart: {}", e)
+                }));
+            }
+        }
+    }
+
+    let Some(data) = data else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "No file uploaded"
+        }));
+    };
+
+    let cache_data = match deepaudit_core::CacheData::from_bytes(&data) {
+        Ok(cache_data) => cache_data,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({ "error": e }));
+        }
+    };
+    let files = cache_data.index.len();
+    let symbols: usize = cache_data.index.values().map(|f| f.symbols.len()).sum();
+
+    let cache_manager = match project_cache_manager(&project_path) {
+        Ok(cache_manager) => cache_manager,
+        Err(e) => {
+            tracing::error!("Failed to locate AST cache for project {}: {}", project_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({ "error": e }));
+        }
+    };
+
+    // 先写入同目录下的临时文件再替换，避免读取到写了一半的缓存
+    let cache_file = cache_manager.cache_file();
+    let installed = tempfile::NamedTempFile::new_in(cache_manager.get_cache_dir())
+        .and_then(|mut tmp| {
+            std::io::Write::write_all(&mut tmp, &data)?;
+            tmp.persist(&cache_file).map_err(|e| e.error)?;
+            Ok(())
+        });
+    if let Err(e) = installed {
+        tracing::error!("Failed to install AST cache for project {}: {}", project_id, e);
+        return HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to install AST cache: {}", e)
+        }));
+    }
+
+    // 若当前加载的正是该项目，重新从缓存文件加载
+    let engine = state.ast_engine.lock().await;
+    let mut cache_state = state.ast_cache_state.lock().await;
+    if cache_state.current_project_id == Some(project_id) {
+        engine.use_repository(&project_path);
+        cache_state.symbol_count = symbols;
+    }
+    drop(cache_state);
+    drop(engine);
+
+    tracing::info!(
+        "Imported AST cache for project {} ({} files, {} symbols)",
+        project_id, files, symbols
+    );
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "project_id": project_id,
+        "files": files,
+        "symbols": symbols,
+        "build_time": cache_data.build_time,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::response_json;
+
+    /// 解析内存中的文件并作为当前索引载入 AST 引擎
+    async fn load_files(state: &AppState, files: &[(&str, &str)]) {
+        let mut parser = deepaudit_core::ASTParser::new();
+        let index = files
+            .iter()
+            .map(|(path, content)| {
+                let file_index = deepaudit_core::FileIndex {
+                    mtime: 0,
+                    symbols: parser.parse_file(std::path::Path::new(path), content).unwrap(),
+                    parse_health: None,
+                };
+                (path.to_string(), file_index)
+            })
+            .collect();
+        state.ast_engine.lock().await.load_from_cache_data(deepaudit_core::CacheData {
+            index,
+            class_map: std::collections::HashMap::new(),
+            build_time: String::new(),
+        });
+    }
+
+    async fn knowledge_graph(state: &web::Data<AppState>, request: serde_json::Value) -> serde_json::Value {
+        let request = web::Json(serde_json::from_value(request).unwrap());
+        response_json(get_knowledge_graph(state.clone(), request).await).await
+    }
+
+    fn cache_upload(data: &[u8]) -> Multipart {
+        let boundary = "deepaudit-test-boundary";
+        let mut body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"ast_index.bin\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend_from_slice(data);
+        body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+        let mut headers = actix_web::http::header::HeaderMap::new();
+        headers.insert(
+            actix_web::http::header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={boundary}").parse().unwrap(),
+        );
+        let stream = futures_util::stream::once(async move {
+            Ok::<_, actix_web::error::PayloadError>(web::Bytes::from(body))
+        });
+        Multipart::new(&headers, stream)
+    }
+
+    #[actix_web::test]
+    async fn exported_cache_can_be_imported_into_another_project() {
+        let (state, _dir) = AppState::for_tests().await;
+        let source = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        for (id, path) in [(1, source.path()), (2, target.path())] {
+            sqlx::query("INSERT INTO projects (id, uuid, name, path) VALUES (?, ?, 'demo', ?)")
+                .bind(id)
+                .bind(format!("p{}", id))
+                .bind(path.to_string_lossy().to_string())
+                .execute(&state.db)
+                .await
+                .unwrap();
+        }
+        let source_cache = project_cache_manager(&source.path().to_string_lossy()).unwrap();
+        let target_cache = project_cache_manager(&target.path().to_string_lossy()).unwrap();
+        let mut parser = deepaudit_core::ASTParser::new();
+        let symbols = parser
+            .parse_file(std::path::Path::new("app.py"), "def main():\n    pass\n")
+            .unwrap();
+        source_cache
+            .save_cache(&deepaudit_core::CacheData {
+                index: [("app.py".to_string(), deepaudit_core::FileIndex { mtime: 0, symbols, parse_health: None })]
+                    .into_iter()
+                    .collect(),
+                class_map: std::collections::HashMap::new(),
+                build_time: "2024-01-01T00:00:00Z".to_string(),
+            })
+            .unwrap();
+        let state = web::Data::new(state);
+        let request = actix_web::test::TestRequest::default().to_http_request();
+
+        let missing = export_ast_cache(state.clone(), web::Path::from(2), request.clone()).await;
+        assert_eq!(missing.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+        let exported = export_ast_cache(state.clone(), web::Path::from(1), request).await;
+        assert_eq!(exported.status(), actix_web::http::StatusCode::OK);
+        let bytes = actix_web::body::to_bytes(exported.into_body()).await.unwrap();
+        assert_eq!(bytes.as_ref(), std::fs::read(source_cache.cache_file()).unwrap());
+
+        let (status, imported) = crate::api::response_status_json(
+            import_ast_cache(state.clone(), web::Path::from(2), cache_upload(&bytes)).await,
+        )
+        .await;
+        assert_eq!(status, actix_web::http::StatusCode::OK);
+        assert_eq!(imported["files"], 1);
+        assert_eq!(imported["symbols"], 1);
+        assert_eq!(imported["build_time"], "2024-01-01T00:00:00Z");
+        let installed = target_cache.load_cache().unwrap();
+        assert_eq!(installed.index["app.py"].symbols[0].name, "main");
+
+        let (status, _) = crate::api::response_status_json(
+            import_ast_cache(state.clone(), web::Path::from(2), cache_upload(b"not a cache")).await,
+        )
+        .await;
+        assert_eq!(status, actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(std::fs::read(target_cache.cache_file()).unwrap(), bytes.as_ref());
+
+        let _ = std::fs::remove_dir_all(source_cache.get_cache_dir());
+        let _ = std::fs::remove_dir_all(target_cache.get_cache_dir());
+    }
+
+    #[actix_web::test]
+    async fn symbol_source_returns_requested_line_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("app.py");
+        let content: String = (1..=MAX_SYMBOL_SOURCE_LINES + 10).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&file, content).unwrap();
+        let source = |file_path: &std::path::Path, start_line: usize, end_line: usize| {
+            let query = web::Query(SymbolSourceQuery {
+                file_path: file_path.to_string_lossy().to_string(),
+                start_line,
+                end_line,
+            });
+            async move { crate::api::response_status_json(get_symbol_source(query).await).await }
+        };
+
+        let (status, range) = source(&file, 2, 4).await;
+        assert_eq!(status, actix_web::http::StatusCode::OK);
+        assert_eq!(range["source"], "line 2\nline 3\nline 4");
+        assert_eq!((range["end_line"].as_u64(), range["truncated"].as_bool()), (Some(4), Some(false)));
+
+        // 超过文件末尾时截到最后一行，超过行数上限时截断
+        let (_, tail) = source(&file, MAX_SYMBOL_SOURCE_LINES + 9, MAX_SYMBOL_SOURCE_LINES + 100).await;
+        assert_eq!(tail["end_line"].as_u64(), Some(MAX_SYMBOL_SOURCE_LINES as u64 + 10));
+        let (_, long) = source(&file, 1, MAX_SYMBOL_SOURCE_LINES + 10).await;
+        assert_eq!(long["end_line"].as_u64(), Some(MAX_SYMBOL_SOURCE_LINES as u64));
+        assert_eq!(long["truncated"], true);
+
+        let missing = dir.path().join("missing.py");
+        assert_eq!(source(&missing, 1, 2).await.0, actix_web::http::StatusCode::NOT_FOUND);
+        assert_eq!(source(&file, 5, 4).await.0, actix_web::http::StatusCode::BAD_REQUEST);
+        let past_end = MAX_SYMBOL_SOURCE_LINES + 11;
+        assert_eq!(source(&file, past_end, past_end).await.0, actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn knowledge_graph_filters_node_kinds_and_paginates() {
+        let (state, _dir) = AppState::for_tests().await;
+        load_files(
+            &state,
+            &[
+                ("base.rb", "class Base\n  def run\n    helper()\n  end\nend\n"),
+                ("child.rb", "class Child < Base\n  def helper\n    run()\n  end\nend\n"),
+                ("other.rb", "class Other\nend\n"),
+            ],
+        )
+        .await;
+        let state = web::Data::new(state);
+        let edge_types = |graph: &serde_json::Value| {
+            let mut types: Vec<String> = graph["graph"]["edges"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|e| e["type"].as_str().unwrap().to_string())
+                .collect();
+            types.sort();
+            types.dedup();
+            types
+        };
+
+        let full = knowledge_graph(&state, serde_json::json!({})).await;
+        assert!(edge_types(&full).contains(&"call".to_string()));
+
+        let graph = knowledge_graph(&state, serde_json::json!({ "node_kinds": ["class"] })).await;
+        let nodes = graph["graph"]["nodes"].as_array().unwrap();
+        let labels: Vec<&str> = nodes.iter().map(|n| n["label"].as_str().unwrap()).collect();
+        assert_eq!(labels, ["Base", "Child", "Other"]);
+        assert!(nodes.iter().all(|n| n["type"] == "Class"));
+        assert_eq!(graph["total_nodes"], 3);
+        // 只保留两端都在当前页的边：没有调用和包含关系，只有继承
+        assert_eq!(edge_types(&graph), ["inheritance"]);
+
+        let page = knowledge_graph(&state, serde_json::json!({ "node_kinds": ["class"], "offset": 1, "limit": 1 })).await;
+        assert_eq!(page["graph"]["nodes"][0]["label"], "Child");
+        assert_eq!(page["graph"]["nodes"].as_array().unwrap().len(), 1);
+        assert_eq!(page["total_nodes"], 3);
+        // 父类不在当前页时不返回继承边
+        assert!(page["graph"]["edges"].as_array().unwrap().is_empty());
+
+        let request = web::Json(serde_json::from_value(serde_json::json!({ "node_kinds": ["bogus"] })).unwrap());
+        let (status, _) = crate::api::response_status_json(get_knowledge_graph(state.clone(), request).await).await;
+        assert_eq!(status, actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn unsaved_buffers_are_parsed_without_touching_the_index() {
+        let parse = |request: serde_json::Value| async move {
+            let request = web::Json(serde_json::from_value(request).unwrap());
+            crate::api::response_status_json(parse_buffer(request).await).await
+        };
+        let content = "def load(path):\n    return open(path)\n\ndef save():\n    pass\n";
+
+        let (status, symbols) = parse(serde_json::json!({ "content": content, "language": "python" })).await;
+        assert_eq!(status, actix_web::http::StatusCode::OK);
+        let functions: Vec<(&str, u64, &str)> = symbols
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|s| s["type"] == "Function")
+            .map(|s| (s["name"].as_str().unwrap(), s["startLine"].as_u64().unwrap(), s["file"].as_str().unwrap()))
+            .collect();
+        assert_eq!(functions, [("load", 1, "buffer.py"), ("save", 4, "buffer.py")]);
+
+        // 文件名优先于语言
+        let (_, named) = parse(serde_json::json!({ "content": content, "language": "java", "filename": "src/io.py" })).await;
+        assert_eq!(named[0]["file"], "src/io.py");
+
+        for request in [
+            serde_json::json!({ "content": content, "language": "cobol" }),
+            serde_json::json!({ "content": content, "filename": "notes.txt" }),
+            serde_json::json!({ "content": content }),
+        ] {
+            assert_eq!(parse(request).await.0, actix_web::http::StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[actix_web::test]
+    async fn knowledge_graph_dedupes_repeated_call_edges() {
+        let (state, _dir) = AppState::for_tests().await;
+        load_files(
+            &state,
+            &[("app.py", "def main():\n    helper()\n    helper()\n    helper()\n\ndef helper():\n    pass\n")],
+        )
+        .await;
+        let state = web::Data::new(state);
+
+        let graph = knowledge_graph(&state, serde_json::json!({})).await;
+        let edges = graph["graph"]["edges"].as_array().unwrap();
+        let mut keys: Vec<(&str, &str, &str)> = edges
+            .iter()
+            .map(|e| (e["source"].as_str().unwrap(), e["target"].as_str().unwrap(), e["label"].as_str().unwrap()))
+            .collect();
+        let total = keys.len();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), total);
+        let function_id = |name: &str| {
+            graph["graph"]["nodes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|n| n["label"] == name && n["type"] == "Function")
+                .map(|n| n["id"].as_str().unwrap().to_string())
+                .unwrap()
+        };
+        let (main, helper) = (function_id("main"), function_id("helper"));
+        let main_to_helper = keys
+            .iter()
+            .filter(|(source, target, _)| *source == main && *target == helper)
+            .count();
+        assert_eq!(main_to_helper, 1);
+        // 三处调用各一条 main -> 调用点 的边，MethodCall 和函数体两种来源不重复计入
+        assert_eq!(keys.iter().filter(|(source, _, _)| *source == main).count(), 4);
+
+        // 截断节点后，边的两个端点都必须仍在节点集合中
+        for limit in 1..=4 {
+            let graph = knowledge_graph(&state, serde_json::json!({ "limit": limit })).await;
+            let nodes: Vec<&str> = graph["graph"]["nodes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|n| n["id"].as_str().unwrap())
+                .collect();
+            assert_eq!(nodes.len(), limit);
+            for edge in graph["graph"]["edges"].as_array().unwrap() {
+                assert!(nodes.contains(&edge["source"].as_str().unwrap()));
+                assert!(nodes.contains(&edge["target"].as_str().unwrap()));
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn symbols_round_trip_through_symbols_table() {
+        let (state, _dir) = AppState::for_tests().await;
+        sqlx::query("INSERT INTO projects (id, uuid, name, path) VALUES (1, 'p1', 'demo', '/tmp/demo')")
+            .execute(&state.db)
+            .await
+            .unwrap();
+        let metadata = |pairs: serde_json::Value| {
+            serde_json::from_value::<std::collections::HashMap<String, serde_json::Value>>(pairs).unwrap()
+        };
+        let symbols = vec![
+            deepaudit_core::Symbol::new("Child".into(), deepaudit_core::SymbolKind::Class, "app.py".into(), 3, String::new())
+                .with_end_line(9)
+                .with_parent_classes(vec!["Base".into(), "Mixin".into()]),
+            deepaudit_core::Symbol::new("run".into(), deepaudit_core::SymbolKind::Method, "app.py".into(), 4, String::new())
+                .with_end_line(8)
+                .with_metadata(metadata(serde_json::json!({
+                    "ownerClass": "Child",
+                    "signature": "def run(self, x)",
+                    "complexity": 3,
+                }))),
+            deepaudit_core::Symbol::new("save".into(), deepaudit_core::SymbolKind::MethodCall, "app.py".into(), 5, String::new())
+                .with_metadata(metadata(serde_json::json!({"callerClass": "Child"}))),
+        ];
+
+        let index_id = save_ast_index_to_db(&state, 1, "/tmp/demo", 1, &symbols, &Default::default())
+            .await
+            .unwrap();
+        let loaded = load_symbols_from_db(&state, index_id).await.unwrap();
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&symbols).unwrap()
+        );
+    }
+}
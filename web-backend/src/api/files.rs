@@ -1,4 +1,6 @@
 use actix_web::{web, HttpResponse, Responder};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::path::{Path as StdPath, PathBuf};
 
@@ -12,20 +14,122 @@ pub struct ListFilesRequest {
     pub directory: String,
     #[serde(default)]
     pub recursive: bool,
+    /// 只列出匹配这些 glob 的文件（逗号分隔，相对 directory 匹配，如 `**/*.py,src/**`）
+    pub include_globs: Option<String>,
+    /// 跳过匹配这些 glob 的文件和目录（逗号分隔），在默认跳过的目录之外额外生效
+    pub exclude_globs: Option<String>,
+    /// 只列出这些扩展名的文件（逗号分隔，不区分大小写，如 `py,js`）
+    pub extensions: Option<String>,
+}
+
+/// list_files 的过滤条件，未指定的条件不生效
+struct ListFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+    extensions: Option<Vec<String>>,
+}
+
+impl ListFilter {
+    fn from_request(query: &ListFilesRequest) -> Result<Self, globset::Error> {
+        let extensions = query.extensions.as_deref().map(|list| {
+            split_list(list)
+                .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                .collect()
+        });
+        Ok(Self {
+            include: query.include_globs.as_deref().map(build_glob_set).transpose()?,
+            exclude: query.exclude_globs.as_deref().map(build_glob_set).transpose()?,
+            extensions,
+        })
+    }
+
+    /// 目录是否被 exclude_globs 排除（排除后不再进入）
+    fn excludes_dir(&self, relative: &StdPath) -> bool {
+        self.exclude.as_ref().is_some_and(|set| set.is_match(relative))
+    }
+
+    fn accepts_file(&self, relative: &StdPath) -> bool {
+        if self.exclude.as_ref().is_some_and(|set| set.is_match(relative)) {
+            return false;
+        }
+        if self.include.as_ref().is_some_and(|set| !set.is_match(relative)) {
+            return false;
+        }
+        match &self.extensions {
+            Some(extensions) => relative
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| extensions.contains(&ext.to_lowercase())),
+            None => true,
+        }
+    }
+}
+
+fn split_list(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',').map(str::trim).filter(|item| !item.is_empty())
+}
+
+fn build_glob_set(list: &str) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in split_list(list) {
+        builder.add(Glob::new(pattern)?);
+    }
+    builder.build()
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct SearchFilesRequest {
     pub query: String,
     pub path: String,
+    /// 是否区分大小写（默认不区分）
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// 是否整词匹配（默认子串匹配）
+    #[serde(default)]
+    pub whole_word: bool,
+    /// 搜索范围：文件名（默认）、文件内容或两者
+    #[serde(default)]
+    pub mode: SearchMode,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    #[default]
+    Filename,
+    Content,
+    Both,
 }
 
+/// 搜索结果数量上限，防止大目录下返回过多结果
+const MAX_SEARCH_RESULTS: usize = 1000;
+
+/// 内容搜索时跳过超过该大小的文件
+const MAX_CONTENT_SEARCH_FILE_SIZE: u64 = 2 * 1024 * 1024;
+
 #[derive(Serialize)]
 pub struct FileInfo {
     pub path: String,
     pub name: String,
 }
 
+/// 文件内容匹配结果
+#[derive(Serialize)]
+pub struct SearchResult {
+    pub file: String,
+    pub line: usize,
+    pub content: String,
+}
+
+/// 文件列表和搜索时跳过的目录/文件
+fn is_excluded_name(name: &str) -> bool {
+    name.starts_with('.')
+        || name == "node_modules"
+        || name == "target"
+        || name == "__pycache__"
+        || name == "dist"
+}
+
 pub fn configure_files_routes(cfg: &mut web::ServiceConfig) {
     cfg
         .route("/read", web::get().to(read_file))
@@ -57,9 +161,18 @@ pub async fn list_files(query: web::Query<ListFilesRequest>) -> impl Responder {
         return HttpResponse::Ok().json(vec![] as Vec<String>);
     }
 
+    let filter = match ListFilter::from_request(&query) {
+        Ok(filter) => filter,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("无效的 glob: {}", e)
+            }));
+        }
+    };
+
     // 默认递归列出所有文件
     let mut entries = vec![];
-    match _list_files_recursive(&path, &mut entries).await {
+    match _list_files_recursive(&path, &path, &filter, &mut entries).await {
         Ok(_) => {
             entries.sort();
             HttpResponse::Ok().json(entries)
@@ -70,8 +183,13 @@ pub async fn list_files(query: web::Query<ListFilesRequest>) -> impl Responder {
     }
 }
 
-// 递归列出所有文件
-async fn _list_files_recursive(dir: &StdPath, entries: &mut Vec<String>) -> Result<(), anyhow::Error> {
+// 递归列出所有文件，glob 按相对 root 的路径匹配
+async fn _list_files_recursive(
+    root: &StdPath,
+    dir: &StdPath,
+    filter: &ListFilter,
+    entries: &mut Vec<String>,
+) -> Result<(), anyhow::Error> {
     let mut rd = tokio::fs::read_dir(dir).await?;
 
     while let Some(entry) = rd.next_entry().await? {
@@ -80,19 +198,19 @@ async fn _list_files_recursive(dir: &StdPath, entries: &mut Vec<String>) -> Resu
         // 过滤隐藏目录和特定目录
         if let Some(name) = path.file_name() {
             if let Some(name_str) = name.to_str() {
-                if name_str.starts_with('.') ||
-                   name_str == "node_modules" ||
-                   name_str == "target" ||
-                   name_str == "__pycache__" ||
-                   name_str == ".git" ||
-                   name_str == "dist" {
+                if is_excluded_name(name_str) {
                     continue;
                 }
             }
         }
 
+        let relative = path.strip_prefix(root).unwrap_or(&path);
         if path.is_dir() {
-            Box::pin(_list_files_recursive(&path, entries)).await?;
+            if !filter.excludes_dir(relative) {
+                Box::pin(_list_files_recursive(root, &path, filter, entries)).await?;
+            }
+        } else if !filter.accepts_file(relative) {
+            continue;
         } else if let Some(path_str) = path.to_str() {
             entries.push(path_str.to_string());
         }
@@ -101,53 +219,157 @@ async fn _list_files_recursive(dir: &StdPath, entries: &mut Vec<String>) -> Resu
     Ok(())
 }
 
+/// 根据大小写和整词选项构建匹配用的正则
+fn build_search_regex(query: &str, case_sensitive: bool, whole_word: bool) -> Result<Regex, regex::Error> {
+    let mut pattern = regex::escape(query);
+    if whole_word {
+        pattern = format!(r"\b{}\b", pattern);
+    }
+    if !case_sensitive {
+        pattern = format!("(?i){}", pattern);
+    }
+    Regex::new(&pattern)
+}
+
 pub async fn search_files(query: web::Query<SearchFilesRequest>) -> impl Responder {
     let path = PathBuf::from(&query.path);
-    let query_str = &query.query;
 
     if !path.exists() {
         return HttpResponse::Ok().json(vec![] as Vec<FileInfo>);
     }
 
-    match _search_files_recursive(&path, query_str).await {
-        Ok(results) => HttpResponse::Ok().json(results),
+    let matcher = match build_search_regex(&query.query, query.case_sensitive, query.whole_word) {
+        Ok(matcher) => matcher,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("无效的搜索条件: {}", e)
+            }));
+        }
+    };
+
+    let mode = query.mode;
+    if mode == SearchMode::Filename {
+        let mut results = vec![];
+        return match _search_files_recursive(&path, &matcher, &mut results).await {
+            Ok(_) => HttpResponse::Ok().json(results),
+            Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("搜索文件失败: {}", e)
+            }))
+        };
+    }
+
+    let searched = web::block(move || _search_with_walk(&path, &matcher, mode == SearchMode::Both)).await;
+    match searched {
+        Ok((files, matches)) if mode == SearchMode::Both => HttpResponse::Ok().json(serde_json::json!({
+            "files": files,
+            "matches": matches
+        })),
+        Ok((_, matches)) => HttpResponse::Ok().json(matches),
         Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
             "error": format!("搜索文件失败: {}", e)
         }))
     }
 }
 
+/// 使用 ignore 遍历目录，搜索文件内容（可选同时匹配文件名）
+fn _search_with_walk(
+    root: &StdPath,
+    matcher: &Regex,
+    include_filenames: bool,
+) -> (Vec<FileInfo>, Vec<SearchResult>) {
+    let mut files = vec![];
+    let mut matches = vec![];
+
+    let walker = ignore::WalkBuilder::new(root)
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || !entry.file_name().to_str().map(is_excluded_name).unwrap_or(false)
+        })
+        .build();
+
+    for entry in walker.flatten() {
+        if files.len() >= MAX_SEARCH_RESULTS && matches.len() >= MAX_SEARCH_RESULTS {
+            break;
+        }
+
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+
+        if include_filenames && files.len() < MAX_SEARCH_RESULTS {
+            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                if matcher.is_match(name) {
+                    files.push(FileInfo {
+                        path: path.to_string_lossy().to_string(),
+                        name: name.to_string(),
+                    });
+                }
+            }
+        }
+
+        if matches.len() >= MAX_SEARCH_RESULTS {
+            continue;
+        }
+        let too_large = entry
+            .metadata()
+            .map(|m| m.len() > MAX_CONTENT_SEARCH_FILE_SIZE)
+            .unwrap_or(true);
+        if too_large {
+            continue;
+        }
+
+        // 非 UTF-8（二进制）文件直接跳过
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+
+        for (index, line) in content.lines().enumerate() {
+            if matches.len() >= MAX_SEARCH_RESULTS {
+                break;
+            }
+            if matcher.is_match(line) {
+                matches.push(SearchResult {
+                    file: path.to_string_lossy().to_string(),
+                    line: index + 1,
+                    content: line.trim().to_string(),
+                });
+            }
+        }
+    }
+
+    (files, matches)
+}
+
 async fn _search_files_recursive(
     dir: &StdPath,
-    query: &str,
-) -> Result<Vec<FileInfo>, anyhow::Error> {
-    let mut results = vec![];
+    matcher: &Regex,
+    results: &mut Vec<FileInfo>,
+) -> Result<(), anyhow::Error> {
     let mut entries = tokio::fs::read_dir(dir).await?;
 
     while let Some(entry) = entries.next_entry().await? {
+        if results.len() >= MAX_SEARCH_RESULTS {
+            break;
+        }
+
         let path = entry.path();
 
         if let Some(os_name) = path.file_name() {
             if let Some(name) = os_name.to_str() {
-                if name.starts_with('.') ||
-                   name == "node_modules" ||
-                   name == "target" ||
-                   name == "__pycache__" ||
-                   name == ".git" ||
-                   name == "dist" {
+                if is_excluded_name(name) {
                     continue;
                 }
             }
         }
 
         if path.is_dir() {
-            match Box::pin(_search_files_recursive(&path, query)).await {
-                Ok(mut sub_results) => results.append(&mut sub_results),
-                Err(_) => continue,
-            }
+            // 子目录读取失败时跳过
+            let _ = Box::pin(_search_files_recursive(&path, matcher, results)).await;
         } else if let Some(os_name) = path.file_name() {
             if let Some(name) = os_name.to_str() {
-                if name.to_lowercase().contains(&query.to_lowercase()) {
+                if matcher.is_match(name) {
                     results.push(FileInfo {
                         path: path.to_string_lossy().to_string(),
                         name: name.to_string(),
@@ -157,5 +379,124 @@ async fn _search_files_recursive(
         }
     }
 
-    Ok(results)
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::response_json;
+
+    async fn search(root: &StdPath, request: serde_json::Value) -> serde_json::Value {
+        let mut request = request;
+        request["path"] = root.to_string_lossy().into();
+        let query = web::Query(serde_json::from_value(request).unwrap());
+        response_json(search_files(query).await).await
+    }
+
+    fn names(results: &serde_json::Value) -> Vec<String> {
+        let mut names: Vec<String> = results
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["name"].as_str().unwrap().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    async fn list(root: &StdPath, request: serde_json::Value) -> (actix_web::http::StatusCode, Vec<String>) {
+        let mut request = request;
+        request["directory"] = root.to_string_lossy().into();
+        let query = web::Query(serde_json::from_value(request).unwrap());
+        let (status, body) = crate::api::response_status_json(list_files(query).await).await;
+        let files = body
+            .as_array()
+            .map(|files| {
+                files
+                    .iter()
+                    .map(|f| StdPath::new(f.as_str().unwrap()).strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"))
+                    .collect()
+            })
+            .unwrap_or_default();
+        (status, files)
+    }
+
+    #[actix_web::test]
+    async fn list_files_applies_extension_filter_and_globs() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["app.py", "README.md", "src/db.PY", "src/ui.js", "tests/test_app.py", "node_modules/dep.py"] {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+
+        // 未指定过滤条件时与之前一致，只跳过默认目录
+        let (_, all) = list(dir.path(), serde_json::json!({})).await;
+        assert_eq!(all, ["README.md", "app.py", "src/db.PY", "src/ui.js", "tests/test_app.py"]);
+
+        let (_, python) = list(dir.path(), serde_json::json!({"extensions": ".py"})).await;
+        assert_eq!(python, ["app.py", "src/db.PY", "tests/test_app.py"]);
+
+        let (_, without_tests) = list(
+            dir.path(),
+            serde_json::json!({"extensions": "py, js", "exclude_globs": "tests"}),
+        )
+        .await;
+        assert_eq!(without_tests, ["app.py", "src/db.PY", "src/ui.js"]);
+
+        let (_, included) = list(dir.path(), serde_json::json!({"include_globs": "src/**"})).await;
+        assert_eq!(included, ["src/db.PY", "src/ui.js"]);
+
+        let (status, _) = list(dir.path(), serde_json::json!({"include_globs": "src/[a"})).await;
+        assert_eq!(status, actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn filename_search_honours_case_and_whole_word_options() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["Config.py", "config_loader.py", "reconfig.py", "main.py"] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+
+        let results = search(dir.path(), serde_json::json!({"query": "config"})).await;
+        assert_eq!(names(&results), ["Config.py", "config_loader.py", "reconfig.py"]);
+
+        let results = search(dir.path(), serde_json::json!({"query": "config", "case_sensitive": true})).await;
+        assert_eq!(names(&results), ["config_loader.py", "reconfig.py"]);
+
+        let results = search(dir.path(), serde_json::json!({"query": "config", "whole_word": true})).await;
+        assert_eq!(names(&results), ["Config.py"]);
+
+        let results = search(
+            dir.path(),
+            serde_json::json!({"query": "config", "whole_word": true, "case_sensitive": true}),
+        )
+        .await;
+        assert!(names(&results).is_empty());
+    }
+
+    #[actix_web::test]
+    async fn content_mode_returns_matching_lines_and_skips_excluded_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("app.py"), "import os\npassword = load_secret()\n").unwrap();
+        std::fs::write(dir.path().join("password.txt"), "nothing here\n").unwrap();
+        std::fs::create_dir(dir.path().join("node_modules")).unwrap();
+        std::fs::write(dir.path().join("node_modules").join("dep.js"), "password = 1\n").unwrap();
+
+        let results = search(dir.path(), serde_json::json!({"query": "password", "mode": "content"})).await;
+        let results = results.as_array().unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0]["file"].as_str().unwrap().ends_with("app.py"));
+        assert_eq!(results[0]["line"], 2);
+        assert_eq!(results[0]["content"], "password = load_secret()");
+
+        let both = search(dir.path(), serde_json::json!({"query": "password", "mode": "both"})).await;
+        assert_eq!(names(&both["files"]), ["password.txt"]);
+        assert_eq!(both["matches"].as_array().unwrap().len(), 1);
+
+        // 默认仍只匹配文件名
+        let filenames = search(dir.path(), serde_json::json!({"query": "password"})).await;
+        assert_eq!(names(&filenames), ["password.txt"]);
+    }
 }
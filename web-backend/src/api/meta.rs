@@ -0,0 +1,70 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::Serialize;
+
+/// 严重程度的展示配置，前端据此渲染标签和颜色，不再自行维护映射
+#[derive(Serialize)]
+pub struct SeverityLevel {
+    /// 与发现中 severity 字段一致的小写值
+    pub value: &'static str,
+    pub label: &'static str,
+    /// 等级，越大越严重
+    pub rank: u32,
+    /// Tailwind 调色板颜色名
+    pub color: &'static str,
+}
+
+pub fn configure_meta_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/severities", web::get().to(get_severities));
+}
+
+fn severity_color(severity: &deepaudit_core::Severity) -> &'static str {
+    match severity {
+        deepaudit_core::Severity::Critical => "red",
+        deepaudit_core::Severity::High => "orange",
+        deepaudit_core::Severity::Medium => "yellow",
+        deepaudit_core::Severity::Low => "blue",
+        deepaudit_core::Severity::Info => "gray",
+    }
+}
+
+/// 按从高到低的顺序列出严重程度
+pub async fn get_severities() -> impl Responder {
+    let levels: Vec<SeverityLevel> = deepaudit_core::Severity::ALL
+        .iter()
+        .map(|severity| SeverityLevel {
+            value: severity.as_str(),
+            label: severity.label(),
+            rank: severity.rank(),
+            color: severity_color(severity),
+        })
+        .collect();
+
+    HttpResponse::Ok().json(levels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::response_json;
+
+    #[actix_web::test]
+    async fn severities_are_listed_from_highest_to_lowest() {
+        let levels = response_json(get_severities().await).await;
+        let levels: Vec<(&str, &str, u64)> = levels
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|l| (l["value"].as_str().unwrap(), l["label"].as_str().unwrap(), l["rank"].as_u64().unwrap()))
+            .collect();
+        assert_eq!(
+            levels,
+            [
+                ("critical", "Critical", 4),
+                ("high", "High", 3),
+                ("medium", "Medium", 2),
+                ("low", "Low", 1),
+                ("info", "Info", 0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::Serialize;
+
+use crate::state::AppState;
+
+pub fn configure_metrics_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("", web::get().to(get_metrics));
+}
+
+#[derive(Serialize)]
+pub struct DbPoolMetrics {
+    /// 已建立的连接数
+    pub size: u32,
+    pub idle: usize,
+    pub in_use: usize,
+    pub max_connections: u32,
+}
+
+#[derive(Serialize)]
+pub struct AstCacheMetrics {
+    /// 当前已加载 AST 索引的项目，未加载时为 None
+    pub project_id: Option<i64>,
+    pub symbol_count: usize,
+}
+
+#[derive(Serialize)]
+pub struct Metrics {
+    /// 正在进行的扫描数（含 WebSocket、门禁、上传扫描）
+    pub active_scans: usize,
+    pub db_pool: DbPoolMetrics,
+    pub ast_cache: AstCacheMetrics,
+}
+
+/// 运行时指标，供监控面板和就绪检查使用
+pub async fn get_metrics(state: web::Data<AppState>) -> impl Responder {
+    let size = state.db.size();
+    let idle = state.db.num_idle();
+    let db_pool = DbPoolMetrics {
+        size,
+        idle,
+        in_use: (size as usize).saturating_sub(idle),
+        max_connections: state.db.options().get_max_connections(),
+    };
+
+    let ast_cache = {
+        let cache_state = state.ast_cache_state.lock().await;
+        AstCacheMetrics {
+            project_id: cache_state.current_project_id,
+            symbol_count: cache_state.symbol_count,
+        }
+    };
+
+    HttpResponse::Ok().json(Metrics {
+        active_scans: state.scan_tracker.active(),
+        db_pool,
+        ast_cache,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::response_json;
+
+    #[actix_web::test]
+    async fn metrics_report_active_scans_pool_and_ast_cache() {
+        let (state, _dir) = AppState::for_tests().await;
+        state.ast_cache_state.lock().await.symbol_count = 42;
+        let state = web::Data::new(state);
+
+        let idle = response_json(get_metrics(state.clone()).await).await;
+        for key in ["active_scans", "db_pool", "ast_cache"] {
+            assert!(idle.get(key).is_some(), "missing {}", key);
+        }
+        for key in ["size", "idle", "in_use", "max_connections"] {
+            assert!(idle["db_pool"].get(key).is_some(), "missing db_pool.{}", key);
+        }
+        assert_eq!(idle["active_scans"], 0);
+        assert_eq!(idle["ast_cache"]["project_id"], serde_json::Value::Null);
+        assert_eq!(idle["ast_cache"]["symbol_count"], 42);
+
+        // 扫描的 guard 存活期间计入 active_scans
+        let scan = state.scan_tracker.begin();
+        let _second = state.scan_tracker.begin();
+        assert_eq!(response_json(get_metrics(state.clone()).await).await["active_scans"], 2);
+        drop(scan);
+        assert_eq!(response_json(get_metrics(state.clone()).await).await["active_scans"], 1);
+    }
+}
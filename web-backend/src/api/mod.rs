@@ -5,6 +5,9 @@ pub mod project;
 pub mod scanner;
 pub mod files;
 pub mod rules;
+pub mod meta;
+pub mod admin;
+pub mod metrics;
 
 pub fn create_api_router() -> Scope {
     web::scope("/api")
@@ -13,6 +16,29 @@ pub fn create_api_router() -> Scope {
         .service(scanner_routes())
         .service(files_routes())
         .service(rules_routes())
+        .service(meta_routes())
+        .service(admin_routes())
+        .service(metrics_routes())
+}
+
+/// 读取处理函数返回的 JSON 响应体
+#[cfg(test)]
+pub(crate) async fn response_json(response: impl actix_web::Responder) -> serde_json::Value {
+    response_status_json(response).await.1
+}
+
+/// 读取处理函数返回的状态码和 JSON 响应体
+#[cfg(test)]
+pub(crate) async fn response_status_json(
+    response: impl actix_web::Responder,
+) -> (actix_web::http::StatusCode, serde_json::Value) {
+    let request = actix_web::test::TestRequest::default().to_http_request();
+    let response = response.respond_to(&request);
+    let status = response.status();
+    let Ok(body) = actix_web::body::to_bytes(response.into_body()).await else {
+        panic!("failed to read response body");
+    };
+    (status, serde_json::from_slice(&body).unwrap())
 }
 
 fn project_routes() -> Scope {
@@ -39,3 +65,18 @@ fn rules_routes() -> Scope {
     web::scope("/rules")
         .configure(rules::configure_rules_routes)
 }
+
+fn meta_routes() -> Scope {
+    web::scope("/meta")
+        .configure(meta::configure_meta_routes)
+}
+
+fn admin_routes() -> Scope {
+    web::scope("/admin")
+        .configure(admin::configure_admin_routes)
+}
+
+fn metrics_routes() -> Scope {
+    web::scope("/metrics")
+        .configure(metrics::configure_metrics_routes)
+}
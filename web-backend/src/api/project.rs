@@ -6,6 +6,7 @@ use uuid::Uuid;
 use futures_util::TryStreamExt;
 
 use crate::state::AppState;
+use crate::upload::{stream_field_to_file, MAX_UPLOAD_FILE_BYTES};
 
 #[derive(Serialize, Deserialize, FromRow)]
 pub struct Project {
@@ -27,6 +28,52 @@ pub struct UploadProjectRequest {
     pub name: String,
 }
 
+/// 上传过程中创建的项目目录守卫：除非显式调用 `keep`，否则在 drop 时删除目录，
+/// 避免解压或写库失败后残留半成品目录
+struct ProjectDirGuard {
+    path: std::path::PathBuf,
+    keep: bool,
+}
+
+impl ProjectDirGuard {
+    fn new(path: std::path::PathBuf) -> Self {
+        Self { path, keep: false }
+    }
+
+    fn keep(&mut self) {
+        self.keep = true;
+    }
+}
+
+impl Drop for ProjectDirGuard {
+    fn drop(&mut self) {
+        if self.keep {
+            return;
+        }
+        match std::fs::remove_dir_all(&self.path) {
+            Ok(_) => tracing::info!("Removed incomplete project directory: {:?}", self.path),
+            Err(e) => tracing::warn!("Failed to remove incomplete project directory {:?}: {}", self.path, e),
+        }
+    }
+}
+
+/// 按字面规则规范化路径（处理 `.` 和 `..`，不访问文件系统）
+fn normalize_path(path: &std::path::Path) -> std::path::PathBuf {
+    use std::path::Component;
+
+    let mut normalized = std::path::PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
 pub fn configure_project_routes(cfg: &mut web::ServiceConfig) {
     cfg
         // RESTful 风格路由
@@ -34,7 +81,9 @@ pub fn configure_project_routes(cfg: &mut web::ServiceConfig) {
         .route("/upload", web::post().to(upload_project))    // POST /api/projects/upload
         .route("", web::get().to(list_projects))             // GET /api/projects
         .route("/{uuid}", web::get().to(get_project))        // GET /api/projects/{uuid}
-        .route("/{uuid}", web::delete().to(delete_project)); // DELETE /api/projects/{uuid}
+        .route("/{uuid}", web::delete().to(delete_project))  // DELETE /api/projects/{uuid}
+        .route("/{uuid}/severity_overrides", web::get().to(get_severity_overrides))
+        .route("/{uuid}/severity_overrides", web::put().to(set_severity_overrides));
 }
 
 async fn create_project(
@@ -85,9 +134,21 @@ async fn upload_project(
     tracing::info!("Starting project upload...");
 
     let mut name = String::new();
-    let mut file_data: Option<Vec<u8>> = None;
+    let mut upload: Option<(tempfile::NamedTempFile, u64)> = None;
     let mut filename = String::new();
 
+    // 创建项目目录
+    let projects_dir = std::path::PathBuf::from("./data/projects");
+    if let Err(e) = std::fs::create_dir_all(&projects_dir) {
+        tracing::error!("Failed to create projects directory: {}", e);
+        return HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to create projects directory: {}", e)
+        }));
+    }
+
+    // 上传内容计入全局上传容量，直到请求处理结束
+    let mut reservation = state.upload_budget.reserve();
+
     // 解析 multipart 表单 - 使用循环处理所有字段
     loop {
         match payload.try_next().await {
@@ -137,23 +198,24 @@ async fn upload_project(
                         }));
                     }
 
-                    // 读取文件数据
-                    let limit = 1024 * 1024 * 1024; // 1GB limit for file
-                    match field.bytes(limit).await {
-                        Ok(Ok(data)) => {
-                            file_data = Some(Vec::from(data.as_ref()));
-                            tracing::info!("File data received: {} bytes", file_data.as_ref().map(|d| d.len()).unwrap_or(0));
-                        }
-                        Ok(Err(e)) => {
-                            tracing::error!("Failed to read file data: {}", e);
+                    // 按块写入临时文件，避免整个压缩包驻留内存；失败时临时文件随 drop 删除
+                    let staging = match tempfile::NamedTempFile::new_in(&projects_dir) {
+                        Ok(file) => file,
+                        Err(e) => {
+                            tracing::error!("Failed to create staging file: {}", e);
                             return HttpResponse::InternalServerError().json(serde_json::json!({
-                                "error": format!("Failed to read file: {}", e)
+                                "error": format!("Failed to create zip file: {}", e)
                             }));
                         }
-                        Err(_) => {
-                            return HttpResponse::InternalServerError().json(serde_json::json!({
-                                "error": "File size limit exceeded"
-                            }));
+                    };
+                    match stream_field_to_file(&mut field, staging.path(), MAX_UPLOAD_FILE_BYTES, &mut reservation).await {
+                        Ok(size) => {
+                            tracing::info!("File data received: {} bytes", size);
+                            upload = Some((staging, size));
+                        }
+                        Err(e) => {
+                            tracing::error!("Failed to receive file data: {:?}", e);
+                            return e.to_response();
                         }
                     }
                 }
@@ -180,8 +242,8 @@ async fn upload_project(
         }));
     }
 
-    let file_data = match file_data {
-        Some(data) => data,
+    let (staging, file_size) = match upload {
+        Some(upload) => upload,
         None => {
             tracing::error!("No file data received");
             return HttpResponse::BadRequest().json(serde_json::json!({
@@ -192,15 +254,6 @@ async fn upload_project(
 
     tracing::info!("Uploading project: {} from file: {}", name, filename);
 
-    // 创建项目目录
-    let projects_dir = std::path::PathBuf::from("./data/projects");
-    if let Err(e) = std::fs::create_dir_all(&projects_dir) {
-        tracing::error!("Failed to create projects directory: {}", e);
-        return HttpResponse::InternalServerError().json(serde_json::json!({
-            "error": format!("Failed to create projects directory: {}", e)
-        }));
-    }
-
     let project_id = Uuid::new_v4();
     let project_dir = projects_dir.join(format!("{}_{}", name.replace(" ", "_"), project_id));
     if let Err(e) = std::fs::create_dir_all(&project_dir) {
@@ -210,28 +263,21 @@ async fn upload_project(
         }));
     }
 
+    // 任何提前返回的错误路径都会通过守卫清理该目录
+    let mut dir_guard = ProjectDirGuard::new(project_dir.clone());
+
     tracing::info!("Created project directory: {:?}", project_dir);
 
-    // 保存上传的 ZIP 文件
+    // 将暂存的 ZIP 文件移入项目目录
     let zip_path = project_dir.join("upload.zip");
-    match std::fs::File::create(&zip_path) {
-        Ok(mut file) => {
-            if let Err(e) = std::io::Write::write_all(&mut file, &file_data) {
-                tracing::error!("Failed to write zip file: {}", e);
-                return HttpResponse::InternalServerError().json(serde_json::json!({
-                    "error": format!("Failed to write zip file: {}", e)
-                }));
-            }
-        }
-        Err(e) => {
-            tracing::error!("Failed to create zip file: {}", e);
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Failed to create zip file: {}", e)
-            }));
-        }
+    if let Err(e) = staging.persist(&zip_path) {
+        tracing::error!("Failed to write zip file: {}", e);
+        return HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to write zip file: {}", e)
+        }));
     }
 
-    tracing::info!("Saved ZIP file: {}, size: {} bytes", zip_path.display(), file_data.len());
+    tracing::info!("Saved ZIP file: {}, size: {} bytes", zip_path.display(), file_size);
 
     // 解压 ZIP 文件
     let extract_dir = project_dir.join("code");
@@ -277,11 +323,19 @@ async fn upload_project(
             }
         };
 
-        let enclosed_name = file.enclosed_name()
-            .map(|p| p.to_path_buf())
-            .unwrap_or_else(|| std::path::PathBuf::from("unknown"));
-
-        let file_path = extract_dir.join(enclosed_name);
+        // 拒绝包含 `..` 或绝对路径的条目（zip-slip）
+        let file_path = match file.enclosed_name()
+            .map(|p| extract_dir.join(p))
+            .filter(|p| normalize_path(p).starts_with(normalize_path(&extract_dir)))
+        {
+            Some(path) => path,
+            None => {
+                tracing::error!("Rejected zip entry escaping extract directory: {}", file.name());
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": format!("Invalid path in archive: {}", file.name())
+                }));
+            }
+        };
 
         // 创建目录
         if let Some(parent) = file_path.parent() {
@@ -347,6 +401,9 @@ async fn upload_project(
         }
     };
 
+    // 项目已写入数据库，目录由项目记录接管
+    dir_guard.keep();
+
     let id = result.last_insert_rowid();
     tracing::info!("Project inserted with ID: {}, UUID: {}", id, project_uuid);
 
@@ -443,152 +500,334 @@ async fn delete_project(
 
     tracing::info!("Deleting project {} (ID: {}), cleanup scheduled for: {}", uuid, project_id, project_path);
 
-    // 使用事务删除所有关联数据
-    let mut tx = match state.db.begin().await {
-        Ok(tx) => tx,
-        Err(e) => {
-            tracing::error!("Failed to begin transaction: {}", e);
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Failed to begin transaction: {}", e)
-            }));
-        }
-    };
-
-    // 1. 删除 findings 表中的关联记录
-    match sqlx::query("DELETE FROM findings WHERE project_id = ?")
-        .bind(project_id)
-        .execute(&mut *tx)
+    // 使用事务删除所有关联数据，锁竞争时重新执行整个事务
+    if let Err(e) = state
+        .db_retry
+        .run("Deleting project", || delete_project_records(&state, project_id))
         .await
     {
-        Ok(result) => {
-            tracing::info!("Deleted {} findings for project {}", result.rows_affected(), project_id);
-        }
-        Err(e) => {
-            tracing::error!("Failed to delete findings: {}", e);
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Failed to delete findings: {}", e)
-            }));
-        }
+        return HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to delete project: {}", e)
+        }));
     }
 
-    // 2. 删除 scans 表中的关联记录
-    match sqlx::query("DELETE FROM scans WHERE project_id = ?")
-        .bind(project_id)
-        .execute(&mut *tx)
-        .await
-    {
-        Ok(result) => {
-            tracing::info!("Deleted {} scan records for project {}", result.rows_affected(), project_id);
-        }
-        Err(e) => {
-            tracing::error!("Failed to delete scans: {}", e);
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Failed to delete scans: {}", e)
-            }));
+    // 异步清理文件系统
+    let project_path_clone = project_path.clone();
+    tokio::spawn(async move {
+        if let Err(e) = tokio::fs::remove_dir_all(&project_path_clone).await {
+            tracing::error!("Failed to cleanup project directory {:?}: {}", project_path_clone, e);
+        } else {
+            tracing::info!("Successfully cleaned up project directory: {:?}", project_path_clone);
         }
+    });
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "message": "Project deleted successfully"
+    }))
+}
+
+/// 在一个事务中删除项目及其全部关联数据，按依赖顺序：
+/// finding_tags -> findings，扫描数据 -> scans，call_relations -> code_graphs -> symbols -> ast_indices，最后是项目记录
+async fn delete_project_records(state: &AppState, project_id: i64) -> Result<(), sqlx::Error> {
+    let mut tx = state.db.begin().await?;
+
+    for (what, sql) in [
+        (
+            "finding tags",
+            "DELETE FROM finding_tags WHERE finding_id IN (SELECT finding_id FROM findings WHERE project_id = ?)",
+        ),
+        ("findings", "DELETE FROM findings WHERE project_id = ?"),
+        ("file hashes", "DELETE FROM file_hashes WHERE project_id = ?"),
+        ("severity overrides", "DELETE FROM severity_overrides WHERE project_id = ?"),
+        (
+            "CWE counts",
+            "DELETE FROM scan_cwe_counts WHERE scan_id IN (SELECT id FROM scans WHERE project_id = ?)",
+        ),
+        ("scan records", "DELETE FROM scans WHERE project_id = ?"),
+        ("call relations", "DELETE FROM call_relations WHERE project_id = ?"),
+        ("code graphs", "DELETE FROM code_graphs WHERE project_id = ?"),
+        ("symbols", "DELETE FROM symbols WHERE project_id = ?"),
+        ("AST indices", "DELETE FROM ast_indices WHERE project_id = ?"),
+        ("project records", "DELETE FROM projects WHERE id = ?"),
+    ] {
+        let result = sqlx::query(sql)
+            .bind(project_id)
+            .execute(&mut *tx)
+            .await
+            .inspect_err(|e| tracing::error!("Failed to delete {}: {}", what, e))?;
+        tracing::info!("Deleted {} {} for project {}", result.rows_affected(), what, project_id);
     }
 
-    // 3. 删除 AST 相关数据（依赖关系：call_relations -> code_graphs -> symbols -> ast_indices）
-    match sqlx::query("DELETE FROM call_relations WHERE project_id = ?")
-        .bind(project_id)
-        .execute(&mut *tx)
+    tx.commit()
         .await
-    {
-        Ok(result) => {
-            tracing::info!("Deleted {} call relations for project {}", result.rows_affected(), project_id);
+        .inspect_err(|e| tracing::error!("Failed to commit transaction: {}", e))
+}
+
+#[derive(Deserialize)]
+pub struct SetSeverityOverridesRequest {
+    pub overrides: Vec<deepaudit_core::SeverityOverride>,
+}
+
+async fn project_id_by_uuid(state: &AppState, uuid: &str) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar("SELECT id FROM projects WHERE uuid = ?")
+        .bind(uuid)
+        .fetch_optional(&state.db)
+        .await
+}
+
+/// 获取项目的严重程度改写（按生效顺序）
+async fn get_severity_overrides(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let uuid = path.into_inner();
+    let project_id = match project_id_by_uuid(&state, &uuid).await {
+        Ok(Some(id)) => id,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": format!("Project {} not found", uuid)
+            }));
         }
         Err(e) => {
-            tracing::error!("Failed to delete call relations: {}", e);
+            tracing::error!("Failed to fetch project: {}", e);
             return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Failed to delete call relations: {}", e)
+                "error": format!("Failed to fetch project: {}", e)
             }));
         }
-    }
+    };
 
-    match sqlx::query("DELETE FROM code_graphs WHERE project_id = ?")
-        .bind(project_id)
-        .execute(&mut *tx)
-        .await
-    {
-        Ok(result) => {
-            tracing::info!("Deleted {} code graphs for project {}", result.rows_affected(), project_id);
-        }
+    match crate::api::scanner::load_severity_overrides(&state, project_id).await {
+        Ok(overrides) => HttpResponse::Ok().json(serde_json::json!({ "overrides": overrides })),
         Err(e) => {
-            tracing::error!("Failed to delete code graphs: {}", e);
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Failed to delete code graphs: {}", e)
-            }));
+            tracing::error!("Failed to fetch severity overrides: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to fetch severity overrides: {}", e)
+            }))
         }
     }
+}
 
-    match sqlx::query("DELETE FROM symbols WHERE project_id = ?")
-        .bind(project_id)
-        .execute(&mut *tx)
-        .await
+/// 替换项目的严重程度改写，下次扫描起生效（规则集哈希随之变化，增量扫描会重新扫描所有文件）
+async fn set_severity_overrides(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: web::Json<SetSeverityOverridesRequest>,
+) -> impl Responder {
+    let uuid = path.into_inner();
+
+    let overrides = match req
+        .overrides
+        .iter()
+        .map(deepaudit_core::SeverityOverride::normalized)
+        .collect::<Result<Vec<_>, _>>()
     {
-        Ok(result) => {
-            tracing::info!("Deleted {} symbols for project {}", result.rows_affected(), project_id);
-        }
+        Ok(overrides) => overrides,
         Err(e) => {
-            tracing::error!("Failed to delete symbols: {}", e);
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Failed to delete symbols: {}", e)
-            }));
+            return HttpResponse::BadRequest().json(serde_json::json!({ "error": e }));
         }
-    }
+    };
 
-    match sqlx::query("DELETE FROM ast_indices WHERE project_id = ?")
-        .bind(project_id)
-        .execute(&mut *tx)
-        .await
-    {
-        Ok(result) => {
-            tracing::info!("Deleted {} AST indices for project {}", result.rows_affected(), project_id);
+    let project_id = match project_id_by_uuid(&state, &uuid).await {
+        Ok(Some(id)) => id,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": format!("Project {} not found", uuid)
+            }));
         }
         Err(e) => {
-            tracing::error!("Failed to delete AST indices: {}", e);
+            tracing::error!("Failed to fetch project: {}", e);
             return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Failed to delete AST indices: {}", e)
+                "error": format!("Failed to fetch project: {}", e)
             }));
         }
+    };
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        sqlx::query("DELETE FROM severity_overrides WHERE project_id = ?")
+            .bind(project_id)
+            .execute(&mut *tx)
+            .await?;
+        for (position, rule) in overrides.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO severity_overrides (project_id, position, match_cwe, rule_id, severity)
+                 VALUES (?, ?, ?, ?, ?)"
+            )
+            .bind(project_id)
+            .bind(position as i64)
+            .bind(&rule.match_cwe)
+            .bind(&rule.rule_id)
+            .bind(rule.severity.as_str())
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
     }
+    .await;
 
-    // 4. 删除项目记录
-    match sqlx::query("DELETE FROM projects WHERE id = ?")
-        .bind(project_id)
-        .execute(&mut *tx)
-        .await
-    {
-        Ok(_) => {
-            tracing::info!("Deleted project {}", project_id);
+    match result {
+        Ok(()) => {
+            tracing::info!("Set {} severity overrides for project {}", overrides.len(), uuid);
+            HttpResponse::Ok().json(serde_json::json!({ "overrides": overrides }))
         }
         Err(e) => {
-            tracing::error!("Failed to delete project: {}", e);
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Failed to delete project: {}", e)
-            }));
+            tracing::error!("Failed to save severity overrides: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to save severity overrides: {}", e)
+            }))
         }
     }
+}
 
-    // 提交事务
-    if let Err(e) = tx.commit().await {
-        tracing::error!("Failed to commit transaction: {}", e);
-        return HttpResponse::InternalServerError().json(serde_json::json!({
-            "error": format!("Failed to commit transaction: {}", e)
-        }));
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::response_status_json;
+
+    /// 构造包含 name 和 file 两个字段的 multipart 表单
+    fn upload_form(name: &str, filename: &str, data: &[u8]) -> Multipart {
+        let boundary = "deepaudit-test-boundary";
+        let mut body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\n{name}\r\n\
+             --{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{filename}\"\r\n\
+             Content-Type: application/zip\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend_from_slice(data);
+        body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+        let mut headers = actix_web::http::header::HeaderMap::new();
+        headers.insert(
+            actix_web::http::header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={boundary}").parse().unwrap(),
+        );
+        let stream = futures_util::stream::once(async move {
+            Ok::<_, actix_web::error::PayloadError>(web::Bytes::from(body))
+        });
+        Multipart::new(&headers, stream)
     }
 
-    // 异步清理文件系统
-    let project_path_clone = project_path.clone();
-    tokio::spawn(async move {
-        if let Err(e) = tokio::fs::remove_dir_all(&project_path_clone).await {
-            tracing::error!("Failed to cleanup project directory {:?}: {}", project_path_clone, e);
-        } else {
-            tracing::info!("Successfully cleaned up project directory: {:?}", project_path_clone);
+    /// ./data/projects 下以该项目名开头的目录
+    fn project_dirs(name: &str) -> Vec<std::path::PathBuf> {
+        std::fs::read_dir("./data/projects")
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.file_name().unwrap().to_string_lossy().starts_with(name))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    async fn upload(state: &web::Data<AppState>, name: &str, data: &[u8]) -> (actix_web::http::StatusCode, serde_json::Value) {
+        let request = actix_web::test::TestRequest::default().to_http_request();
+        response_status_json(upload_project(state.clone(), upload_form(name, "project.zip", data), request).await).await
+    }
+
+    #[actix_web::test]
+    async fn failed_extraction_removes_project_directory() {
+        let (state, _dir) = AppState::for_tests().await;
+        let state = web::Data::new(state);
+        let name = format!("broken_{}", Uuid::new_v4().simple());
+
+        let (status, body) = upload(&state, &name, b"this is not a zip archive").await;
+        assert_eq!(status, actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(body["error"].as_str().unwrap().contains("zip archive"));
+        assert!(project_dirs(&name).is_empty());
+        let projects: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM projects").fetch_one(&state.db).await.unwrap();
+        assert_eq!(projects, 0);
+    }
+
+    fn zip_archive(entries: &[(&str, &str)]) -> Vec<u8> {
+        use std::io::Write;
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            writer.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
         }
-    });
+        writer.finish().unwrap().into_inner()
+    }
 
-    HttpResponse::Ok().json(serde_json::json!({
-        "message": "Project deleted successfully"
-    }))
+    #[actix_web::test]
+    async fn archive_entries_escaping_extract_dir_are_rejected() {
+        let (state, _dir) = AppState::for_tests().await;
+        let state = web::Data::new(state);
+        let name = format!("zipslip_{}", Uuid::new_v4().simple());
+        let marker = format!("evil_{}.txt", Uuid::new_v4().simple());
+
+        for entry in [format!("../../{}", marker), format!("/tmp/{}", marker)] {
+            let archive = zip_archive(&[("app.py", "print(1)\n"), (&entry, "pwned")]);
+            let (status, body) = upload(&state, &name, &archive).await;
+            assert_eq!(status, actix_web::http::StatusCode::BAD_REQUEST, "{}", entry);
+            assert!(body["error"].as_str().unwrap().contains("Invalid path in archive"));
+        }
+        assert!(project_dirs(&name).is_empty());
+        assert!(!std::path::Path::new("./data").join(&marker).exists());
+        assert!(!std::path::Path::new("/tmp").join(&marker).exists());
+
+        // 正常的压缩包仍然可以上传
+        let archive = zip_archive(&[("src/app.py", "print(1)\n")]);
+        let (status, _) = upload(&state, &name, &archive).await;
+        assert!(status.is_success());
+        let dirs = project_dirs(&name);
+        assert_eq!(dirs.len(), 1);
+        assert!(dirs[0].join("code/src/app.py").is_file());
+        std::fs::remove_dir_all(&dirs[0]).unwrap();
+    }
+
+    #[actix_web::test]
+    async fn severity_overrides_remap_stored_findings_and_keep_original() {
+        let (state, dir) = AppState::for_tests().await;
+        let project = dir.path().join("project");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(project.join("app.py"), "import hashlib\nhashlib.md5(data)\n").unwrap();
+        let project_path = project.to_string_lossy().to_string();
+        sqlx::query("INSERT INTO projects (id, uuid, name, path) VALUES (1, 'p1', 'demo', ?)")
+            .bind(&project_path)
+            .execute(&state.db)
+            .await
+            .unwrap();
+        let state = web::Data::new(state);
+
+        let request = web::Json(
+            serde_json::from_value(serde_json::json!({
+                "overrides": [{ "match_cwe": "328", "severity": "critical" }]
+            }))
+            .unwrap(),
+        );
+        let (status, saved) =
+            response_status_json(set_severity_overrides(state.clone(), web::Path::from("p1".to_string()), request).await)
+                .await;
+        assert_eq!(status, actix_web::http::StatusCode::OK);
+        assert_eq!(saved["overrides"][0]["match_cwe"], "CWE-328");
+
+        let scan: crate::api::scanner::ScanRequest = serde_json::from_value(serde_json::json!({
+            "project_path": project_path,
+            "project_id": 1,
+            "rules": null,
+        }))
+        .unwrap();
+        crate::api::scanner::run_scan(state.clone(), web::Json(scan)).await;
+
+        let stored: Vec<(String, Option<String>)> = sqlx::query_as(
+            "SELECT severity, original_severity FROM findings WHERE detector = 'WeakCryptoScanner'"
+        )
+        .fetch_all(&state.db)
+        .await
+        .unwrap();
+        assert_eq!(stored, vec![("critical".to_string(), Some("medium".to_string()))]);
+
+        // 同时设置 match_cwe 和 rule_id 的改写被拒绝
+        let invalid = web::Json(
+            serde_json::from_value(serde_json::json!({
+                "overrides": [{ "match_cwe": "CWE-328", "rule_id": "r", "severity": "low" }]
+            }))
+            .unwrap(),
+        );
+        let (status, _) =
+            response_status_json(set_severity_overrides(state.clone(), web::Path::from("p1".to_string()), invalid).await)
+                .await;
+        assert_eq!(status, actix_web::http::StatusCode::BAD_REQUEST);
+    }
 }
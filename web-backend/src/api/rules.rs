@@ -21,6 +21,17 @@ pub struct RuleResponse {
     pub category: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cwe: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub report_all_captures: bool,
+    /// 执行优先级，数值越大越先执行
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+    /// 只对匹配这些 glob 的文件路径生效
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_paths: Option<Vec<String>>,
+    /// 不对匹配这些 glob 的文件路径生效
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclude_paths: Option<Vec<String>>,
 }
 
 impl From<deepaudit_core::rules::model::Rule> for RuleResponse {
@@ -29,16 +40,70 @@ impl From<deepaudit_core::rules::model::Rule> for RuleResponse {
             id: rule.id,
             name: rule.name,
             description: rule.description,
-            severity: format!("{:?}", rule.severity).to_lowercase(),
-            language: rule.language,
+            severity: rule.severity.to_string(),
+            language: rule.language.to_string(),
             pattern: rule.pattern,
             query: rule.query,
             category: rule.category,
             cwe: rule.cwe,
+            report_all_captures: rule.report_all_captures,
+            priority: rule.priority,
+            include_paths: rule.include_paths,
+            exclude_paths: rule.exclude_paths,
         }
     }
 }
 
+/// 创建/更新规则的响应：规则本身以及非阻塞的质量警告
+#[derive(Serialize)]
+pub struct RuleWriteResponse {
+    #[serde(flatten)]
+    pub rule: RuleResponse,
+    pub warnings: Vec<deepaudit_core::RuleLint>,
+}
+
+/// 规则是否有去除空白后非空的 pattern 或 query（空正则会在每个位置匹配，扫描时被跳过）
+fn has_matcher(rule: &RuleResponse) -> bool {
+    [&rule.pattern, &rule.query]
+        .into_iter()
+        .flatten()
+        .any(|value| !value.trim().is_empty())
+}
+
+/// 对前端提交的规则进行质量检查
+fn lint_rule_response(rule: &RuleResponse) -> Vec<deepaudit_core::RuleLint> {
+    let mut warnings = Vec::new();
+
+    let severity = match rule.severity.parse::<deepaudit_core::Severity>() {
+        Ok(severity) => severity,
+        Err(_) => {
+            warnings.push(deepaudit_core::RuleLint {
+                field: "severity".to_string(),
+                message: format!("Unknown or missing severity '{}'", rule.severity),
+            });
+            deepaudit_core::Severity::Medium
+        }
+    };
+
+    let core_rule = deepaudit_core::Rule {
+        id: rule.id.clone(),
+        name: rule.name.clone(),
+        description: rule.description.clone(),
+        severity,
+        language: deepaudit_core::Language::from(rule.language.as_str()),
+        pattern: rule.pattern.clone(),
+        query: rule.query.clone(),
+        category: rule.category.clone(),
+        cwe: rule.cwe.clone(),
+        report_all_captures: rule.report_all_captures,
+        priority: rule.priority,
+        include_paths: rule.include_paths.clone(),
+        exclude_paths: rule.exclude_paths.clone(),
+    };
+    warnings.extend(deepaudit_core::lint_rule(&core_rule));
+    warnings
+}
+
 /// 规则统计信息
 #[derive(Serialize)]
 pub struct RuleStats {
@@ -46,6 +111,8 @@ pub struct RuleStats {
     pub by_severity: serde_json::Value,
     pub by_language: serde_json::Value,
     pub by_category: serde_json::Value,
+    /// 加载规则时的诊断信息（如重复的规则ID）
+    pub diagnostics: Vec<String>,
 }
 
 pub fn configure_rules_routes(cfg: &mut web::ServiceConfig) {
@@ -53,6 +120,8 @@ pub fn configure_rules_routes(cfg: &mut web::ServiceConfig) {
         .route("", web::get().to(get_rules))
         .route("", web::post().to(create_rule))
         .route("/stats", web::get().to(get_rule_stats))
+        .route("/validate", web::get().to(validate_rules))
+        .route("/import_url", web::post().to(import_rules_from_url))
         .route("/{rule_id}", web::get().to(get_rule_by_id))
         .route("/{rule_id}", web::put().to(update_rule))
         .route("/{rule_id}", web::delete().to(delete_rule));
@@ -60,10 +129,9 @@ pub fn configure_rules_routes(cfg: &mut web::ServiceConfig) {
 
 /// 获取所有规则列表
 pub async fn get_rules(
-    _state: web::Data<AppState>,
+    state: web::Data<AppState>,
 ) -> impl Responder {
-    // 从项目根目录的 rules 目录加载规则（web-backend 在项目根目录下）
-    let rules_path = std::path::Path::new("../rules");
+    let rules_path = state.rules_dir.as_path();
 
     if !rules_path.exists() {
         return HttpResponse::NotFound().json(serde_json::json!({
@@ -89,12 +157,12 @@ pub async fn get_rules(
 
 /// 根据ID获取单个规则详情
 pub async fn get_rule_by_id(
-    _state: web::Data<AppState>,
+    state: web::Data<AppState>,
     path: web::Path<String>,
 ) -> impl Responder {
     let rule_id = path.into_inner();
 
-    let rules_path = std::path::Path::new("../rules");
+    let rules_path = state.rules_dir.as_path();
 
     if !rules_path.exists() {
         return HttpResponse::NotFound().json(serde_json::json!({
@@ -124,11 +192,61 @@ pub async fn get_rule_by_id(
     }
 }
 
+/// 单条规则的校验结果；rule_id 为空表示无法解析的规则文件或被忽略的重复规则
+#[derive(Serialize)]
+pub struct RuleValidation {
+    pub rule_id: Option<String>,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 编译规则目录中的所有规则（正则和 Tree-sitter 查询），报告每条规则能否使用；
+/// 编译失败的规则在扫描时会被跳过
+pub async fn validate_rules(
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let rules_path = state.rules_dir.as_path();
+
+    if !rules_path.exists() {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Rules directory not found"
+        }));
+    }
+
+    match deepaudit_core::rules::loader::load_rules_with_diagnostics(rules_path) {
+        Ok((core_rules, diagnostics)) => {
+            let mut results: Vec<RuleValidation> = core_rules
+                .iter()
+                .map(|rule| {
+                    let error = deepaudit_core::validate_rule(rule).err();
+                    RuleValidation {
+                        rule_id: Some(rule.id.clone()),
+                        ok: error.is_none(),
+                        error,
+                    }
+                })
+                .collect();
+            results.extend(diagnostics.into_iter().map(|message| RuleValidation {
+                rule_id: None,
+                ok: false,
+                error: Some(message),
+            }));
+            HttpResponse::Ok().json(results)
+        }
+        Err(e) => {
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to load rules: {}", e)
+            }))
+        }
+    }
+}
+
 /// 获取规则统计信息
 pub async fn get_rule_stats(
-    _state: web::Data<AppState>,
+    state: web::Data<AppState>,
 ) -> impl Responder {
-    let rules_path = std::path::Path::new("../rules");
+    let rules_path = state.rules_dir.as_path();
 
     if !rules_path.exists() {
         return HttpResponse::NotFound().json(serde_json::json!({
@@ -136,14 +254,14 @@ pub async fn get_rule_stats(
         }));
     }
 
-    match deepaudit_core::rules::loader::load_rules_from_dir(rules_path) {
-        Ok(core_rules) => {
+    match deepaudit_core::rules::loader::load_rules_with_diagnostics(rules_path) {
+        Ok((core_rules, diagnostics)) => {
             let total = core_rules.len();
 
             // 按严重级别统计
             let mut by_severity = serde_json::Map::new();
             for rule in &core_rules {
-                let severity = format!("{:?}", rule.severity).to_lowercase();
+                let severity = rule.severity.to_string();
                 let count = by_severity.entry(severity).or_insert(serde_json::json!(0));
                 if let Some(n) = count.as_i64() {
                     *count = serde_json::json!(n + 1);
@@ -153,7 +271,7 @@ pub async fn get_rule_stats(
             // 按语言统计
             let mut by_language = serde_json::Map::new();
             for rule in &core_rules {
-                let count = by_language.entry(rule.language.clone()).or_insert(serde_json::json!(0));
+                let count = by_language.entry(rule.language.to_string()).or_insert(serde_json::json!(0));
                 if let Some(n) = count.as_i64() {
                     *count = serde_json::json!(n + 1);
                 }
@@ -175,6 +293,7 @@ pub async fn get_rule_stats(
                 by_severity: serde_json::to_value(by_severity).unwrap_or_default(),
                 by_language: serde_json::to_value(by_language).unwrap_or_default(),
                 by_category: serde_json::to_value(by_category).unwrap_or_default(),
+                diagnostics,
             };
 
             HttpResponse::Ok().json(stats)
@@ -207,6 +326,21 @@ fn rule_to_yaml(rule: &RuleResponse) -> String {
     if let Some(query) = &rule.query {
         yaml.push_str(&format!("query: {}\n", query));
     }
+    if rule.report_all_captures {
+        yaml.push_str("report_all_captures: true\n");
+    }
+    if let Some(priority) = rule.priority {
+        yaml.push_str(&format!("priority: {}\n", priority));
+    }
+    // glob 以 `*` 开头时在 YAML 中需要加引号
+    for (key, globs) in [("include_paths", &rule.include_paths), ("exclude_paths", &rule.exclude_paths)] {
+        if let Some(globs) = globs {
+            yaml.push_str(&format!("{}:\n", key));
+            for glob in globs {
+                yaml.push_str(&format!("  - {:?}\n", glob));
+            }
+        }
+    }
     yaml
 }
 
@@ -223,12 +357,97 @@ fn save_rule_to_file(rule: &RuleResponse, rules_path: &std::path::Path) -> Resul
     Ok(())
 }
 
+#[derive(Deserialize)]
+pub struct ImportUrlRequest {
+    pub url: String,
+}
+
+/// 从远程 URL 导入规则包并保存到规则目录，已存在的规则ID会被跳过
+pub async fn import_rules_from_url(
+    state: web::Data<AppState>,
+    req: web::Json<ImportUrlRequest>,
+) -> impl Responder {
+    let rules_path = state.rules_dir.as_path();
+
+    if !req.url.starts_with("http://") && !req.url.starts_with("https://") {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Only http(s) URLs are supported"
+        }));
+    }
+
+    let remote_rules = match deepaudit_core::load_rules_from_url(&req.url).await {
+        Ok(rules) => rules,
+        Err(deepaudit_core::error::CoreError::Network(e)) => {
+            return HttpResponse::BadGateway().json(serde_json::json!({
+                "error": e
+            }));
+        }
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": e.to_string()
+            }));
+        }
+    };
+
+    if !rules_path.exists() {
+        if let Err(e) = fs::create_dir_all(rules_path) {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to create rules directory: {}", e)
+            }));
+        }
+    }
+
+    let mut existing_ids: std::collections::HashSet<String> =
+        match deepaudit_core::rules::loader::load_rules_from_dir(rules_path) {
+            Ok(rules) => rules.into_iter().map(|r| r.id).collect(),
+            Err(_) => std::collections::HashSet::new(),
+        };
+
+    let mut imported = Vec::new();
+    let mut skipped = Vec::new();
+    for rule in remote_rules {
+        if !existing_ids.insert(rule.id.clone()) {
+            skipped.push(rule.id);
+            continue;
+        }
+
+        let rule = RuleResponse::from(rule);
+        if let Err(e) = save_rule_to_file(&rule, rules_path) {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to save rule {}: {}", rule.id, e)
+            }));
+        }
+        imported.push(rule.id);
+    }
+    if !imported.is_empty() {
+        state.rules_changed();
+    }
+
+    tracing::info!(
+        "Imported {} rules from {} ({} skipped)",
+        imported.len(),
+        req.url,
+        skipped.len()
+    );
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "imported": imported,
+        "skipped": skipped
+    }))
+}
+
 /// 创建新规则
 pub async fn create_rule(
-    _state: web::Data<AppState>,
+    state: web::Data<AppState>,
     rule: web::Json<RuleResponse>,
 ) -> impl Responder {
-    let rules_path = std::path::Path::new("../rules");
+    let rules_path = state.rules_dir.as_path();
+
+    if !has_matcher(&rule) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Rule must have a non-empty pattern or query"
+        }));
+    }
 
     // 确保规则目录存在
     if !rules_path.exists() {
@@ -251,11 +470,17 @@ pub async fn create_rule(
         }));
     }
 
+    let warnings = lint_rule_response(&rule);
+
     // 保存规则到文件
     match save_rule_to_file(&rule, rules_path) {
         Ok(_) => {
-            tracing::info!("Created new rule: {}", rule.id);
-            HttpResponse::Ok().json(rule.into_inner())
+            state.rules_changed();
+            tracing::info!("Created new rule: {} ({} warnings)", rule.id, warnings.len());
+            HttpResponse::Ok().json(RuleWriteResponse {
+                rule: rule.into_inner(),
+                warnings,
+            })
         }
         Err(e) => {
             HttpResponse::InternalServerError().json(serde_json::json!({
@@ -267,12 +492,18 @@ pub async fn create_rule(
 
 /// 更新规则
 pub async fn update_rule(
-    _state: web::Data<AppState>,
+    state: web::Data<AppState>,
     path: web::Path<String>,
     rule: web::Json<RuleResponse>,
 ) -> impl Responder {
     let rule_id = path.into_inner();
-    let rules_path = std::path::Path::new("../rules");
+    let rules_path = state.rules_dir.as_path();
+
+    if !has_matcher(&rule) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Rule must have a non-empty pattern or query"
+        }));
+    }
 
     if !rules_path.exists() {
         return HttpResponse::NotFound().json(serde_json::json!({
@@ -303,11 +534,17 @@ pub async fn update_rule(
         let _ = fs::remove_file(&old_file);
     }
 
+    let warnings = lint_rule_response(&rule_data);
+
     // 保存更新后的规则
     match save_rule_to_file(&rule_data, rules_path) {
         Ok(_) => {
-            tracing::info!("Updated rule: {}", rule_data.id);
-            HttpResponse::Ok().json(rule_data)
+            state.rules_changed();
+            tracing::info!("Updated rule: {} ({} warnings)", rule_data.id, warnings.len());
+            HttpResponse::Ok().json(RuleWriteResponse {
+                rule: rule_data,
+                warnings,
+            })
         }
         Err(e) => {
             HttpResponse::InternalServerError().json(serde_json::json!({
@@ -319,11 +556,11 @@ pub async fn update_rule(
 
 /// 删除规则
 pub async fn delete_rule(
-    _state: web::Data<AppState>,
+    state: web::Data<AppState>,
     path: web::Path<String>,
 ) -> impl Responder {
     let rule_id = path.into_inner();
-    let rules_path = std::path::Path::new("../rules");
+    let rules_path = state.rules_dir.as_path();
 
     if !rules_path.exists() {
         return HttpResponse::NotFound().json(serde_json::json!({
@@ -342,6 +579,7 @@ pub async fn delete_rule(
 
     match fs::remove_file(&file_path) {
         Ok(_) => {
+            state.rules_changed();
             tracing::info!("Deleted rule: {}", rule_id);
             HttpResponse::Ok().json(serde_json::json!({
                 "success": true,
@@ -355,3 +593,103 @@ pub async fn delete_rule(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::response_json;
+
+    #[actix_web::test]
+    async fn validation_reports_each_rule_status() {
+        let (state, _dir) = AppState::for_tests().await;
+        std::fs::create_dir_all(&state.rules_dir).unwrap();
+        std::fs::write(
+            state.rules_dir.join("pack.yaml"),
+            r#"name: "Pack"
+version: "1.0"
+rules:
+  - id: "valid-eval"
+    name: "Eval"
+    description: "eval call"
+    severity: "high"
+    language: "python"
+    pattern: "eval\\("
+  - id: "broken-regex"
+    name: "Broken regex"
+    description: "unclosed group"
+    severity: "high"
+    language: "python"
+    pattern: "eval("
+  - id: "broken-query"
+    name: "Broken query"
+    description: "unbalanced query"
+    severity: "high"
+    language: "python"
+    query: "(call function: (identifier) @match"
+"#,
+        )
+        .unwrap();
+        std::fs::write(state.rules_dir.join("broken.yaml"), "rules: [").unwrap();
+
+        let results = response_json(validate_rules(web::Data::new(state)).await).await;
+        let status = |id: &str| {
+            let result = results
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|r| r["rule_id"] == id)
+                .unwrap();
+            (result["ok"].as_bool().unwrap(), result.get("error").is_some())
+        };
+        assert_eq!(status("valid-eval"), (true, false));
+        assert_eq!(status("broken-regex"), (false, true));
+        assert_eq!(status("broken-query"), (false, true));
+        // 无法解析的规则文件以空 rule_id 报告
+        assert!(results
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|r| r["rule_id"].is_null() && r["ok"] == false));
+    }
+
+    #[actix_web::test]
+    async fn blank_patterns_are_rejected_on_create_and_update() {
+        let (state, _dir) = AppState::for_tests().await;
+        let state = web::Data::new(state);
+        let rule = |pattern: Option<&str>, query: Option<&str>| {
+            web::Json(
+                serde_json::from_value::<RuleResponse>(serde_json::json!({
+                    "id": "custom-eval",
+                    "name": "Eval",
+                    "description": "eval call",
+                    "severity": "high",
+                    "language": "python",
+                    "pattern": pattern,
+                    "query": query
+                }))
+                .unwrap(),
+            )
+        };
+
+        for (pattern, query) in [(Some(""), None), (Some("   "), Some("\n")), (None, None)] {
+            let (status, body) =
+                crate::api::response_status_json(create_rule(state.clone(), rule(pattern, query)).await).await;
+            assert_eq!(status, actix_web::http::StatusCode::BAD_REQUEST);
+            assert_eq!(body["error"], "Rule must have a non-empty pattern or query");
+        }
+        assert!(!state.rules_dir.exists());
+
+        let (status, _) =
+            crate::api::response_status_json(create_rule(state.clone(), rule(Some("eval\\("), None)).await).await;
+        assert!(status.is_success());
+        let (status, _) = crate::api::response_status_json(
+            update_rule(state.clone(), web::Path::from("custom-eval".to_string()), rule(Some(" "), None)).await,
+        )
+        .await;
+        assert_eq!(status, actix_web::http::StatusCode::BAD_REQUEST);
+        // 被拒绝的更新不影响已有规则
+        let rules = deepaudit_core::rules::loader::load_rules_from_dir(&state.rules_dir).unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].pattern.as_deref(), Some("eval\\("));
+    }
+}
@@ -8,7 +8,9 @@ use tokio::sync::Mutex;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod api;
+mod rules_cache;
 mod state;
+mod upload;
 
 use api::create_api_router;
 use state::AppState;
@@ -22,11 +24,11 @@ async fn health_check() -> impl Responder {
 
 #[actix_web::main]
 async fn main() -> Result<()> {
-    // 初始化日志
+    // 初始化日志（core 库通过 log 门面输出，由 tracing-log 桥接）
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "deepaudit_web=debug,actix_web=info".into()),
+                .unwrap_or_else(|_| "deepaudit_web=debug,deepaudit_core=info,actix_web=info".into()),
         )
         .with(tracing_subscriber::fmt::layer())
         .init();
@@ -0,0 +1,151 @@
+// 规则缓存 - 编译好的扫描规则常驻内存，rules 目录中的规则文件变化时由文件监听重新编译
+
+use deepaudit_core::LoadedRules;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// 默认规则目录：项目根目录的 rules 目录（web-backend 在项目根目录下运行）
+const DEFAULT_RULES_DIR: &str = "../rules";
+
+/// 规则目录，可通过 `RULES_DIR` 环境变量覆盖；扫描、规则管理接口和检测器列表都使用该目录
+pub fn rules_dir_from_env() -> PathBuf {
+    std::env::var("RULES_DIR")
+        .ok()
+        .filter(|dir| !dir.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_RULES_DIR))
+}
+
+/// 已编译的规则集，扫描开始时取当前版本，重新编译不影响进行中的扫描
+pub struct RulesCache {
+    dir: PathBuf,
+    current: RwLock<Arc<LoadedRules>>,
+    /// 持有监听器，drop 时停止监听
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl RulesCache {
+    /// 编译目录中的规则并开始监听目录变化
+    pub fn watch(dir: impl Into<PathBuf>) -> notify::Result<Arc<Self>> {
+        let dir = dir.into();
+        let cache = Arc::new(Self {
+            current: RwLock::new(Arc::new(LoadedRules::load_from_dir(&dir))),
+            dir,
+            watcher: Mutex::new(None),
+        });
+
+        // 回调只持有弱引用，避免缓存与监听器互相持有
+        let weak = Arc::downgrade(&cache);
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            match event {
+                Ok(event) if is_rules_change(&event) => {
+                    if let Some(cache) = weak.upgrade() {
+                        cache.reload();
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Rules watcher error: {}", e),
+            }
+        })?;
+        watcher.watch(&cache.dir, RecursiveMode::Recursive)?;
+        *cache.watcher.lock().unwrap_or_else(|e| e.into_inner()) = Some(watcher);
+
+        tracing::info!(
+            "Watching {} for rule changes ({} rules loaded)",
+            cache.dir.display(),
+            cache.current().rule_count()
+        );
+        Ok(cache)
+    }
+
+    /// 当前编译好的规则集
+    pub fn current(&self) -> Arc<LoadedRules> {
+        Arc::clone(&self.current.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// 重新读取并编译规则目录
+    pub fn reload(&self) {
+        let rules = Arc::new(LoadedRules::load_from_dir(&self.dir));
+        tracing::info!(
+            "Reloaded rules from {} ({} rules, hash {})",
+            self.dir.display(),
+            rules.rule_count(),
+            rules.hash()
+        );
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = rules;
+    }
+}
+
+/// 事件是否涉及规则文件（只读访问和编辑器临时文件不触发重新编译）
+fn is_rules_change(event: &Event) -> bool {
+    !matches!(event.kind, EventKind::Access(_)) && event.paths.iter().any(|path| is_rule_file(path))
+}
+
+fn is_rule_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "yaml" || extension == "yml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn write_rule(dir: &Path, pattern: &str) {
+        std::fs::write(
+            dir.join("custom.yaml"),
+            format!(
+                r#"name: "Custom"
+version: "1.0"
+rules:
+  - id: "custom-call"
+    name: "Custom call"
+    description: "Flags a custom call"
+    severity: "high"
+    language: "python"
+    pattern: "{}"
+"#,
+                pattern
+            ),
+        )
+        .unwrap();
+    }
+
+    async fn rule_lines(cache: &RulesCache, project: &Path) -> Vec<usize> {
+        let options = deepaudit_core::ScanOptions {
+            rules: Some(cache.current()),
+            ..Default::default()
+        };
+        deepaudit_core::scan_directory(&project.to_string_lossy(), &options)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|f| f.detector == "RegexRule: custom-call")
+            .map(|f| f.line_start)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn edited_rule_file_is_picked_up_by_cached_scanner() {
+        let rules_dir = tempfile::tempdir().unwrap();
+        let project = tempfile::tempdir().unwrap();
+        std::fs::write(project.path().join("app.py"), "first_call()\nsecond_call()\n").unwrap();
+        write_rule(rules_dir.path(), "first_call");
+
+        let cache = RulesCache::watch(rules_dir.path()).unwrap();
+        assert_eq!(rule_lines(&cache, project.path()).await, vec![1]);
+
+        write_rule(rules_dir.path(), "second_call");
+        // 由文件监听触发重新编译，不手动 reload；写入过程中可能收到多个事件，等待最后一次编译完成
+        let mut lines = Vec::new();
+        for _ in 0..100 {
+            lines = rule_lines(&cache, project.path()).await;
+            if lines == [2] {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(lines, vec![2]);
+    }
+}
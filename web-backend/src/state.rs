@@ -1,10 +1,29 @@
-use deepaudit_core::ASTEngine;
-use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use crate::rules_cache::{rules_dir_from_env, RulesCache};
+use crate::upload::UploadBudget;
+use deepaudit_core::{ASTEngine, LoadedRules, ParseErrorMode};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
 use sqlx::{Pool, Sqlite};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
+/// 默认数据库连接池大小，可通过 `DB_MAX_CONNECTIONS` 环境变量覆盖
+const DEFAULT_DB_MAX_CONNECTIONS: u32 = 5;
+
+/// 等待数据库锁释放的最长时间
+const DB_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 事务因锁竞争失败时的默认最大尝试次数，可通过 `DB_RETRY_ATTEMPTS` 环境变量覆盖
+const DEFAULT_DB_RETRY_ATTEMPTS: u32 = 3;
+
+/// 第一次重试前的等待时间，之后每次翻倍
+const DB_RETRY_BASE_DELAY: Duration = Duration::from_millis(200);
+
+/// AST 索引和扫描结果缓存所在目录
+pub const CACHE_DIR: &str = ".deepaudit_cache";
+
 /// AST缓存状态跟踪
 #[derive(Default)]
 pub struct AstCacheState {
@@ -13,28 +32,208 @@ pub struct AstCacheState {
     pub symbol_count: usize,
 }
 
+/// 正在进行的扫描计数，供 `/api/metrics` 上报
+#[derive(Default)]
+pub struct ScanTracker {
+    active: AtomicUsize,
+}
+
+impl ScanTracker {
+    /// 登记一次扫描，返回的 guard drop 时注销
+    pub fn begin(self: &Arc<Self>) -> ActiveScan {
+        self.active.fetch_add(1, Ordering::AcqRel);
+        ActiveScan {
+            tracker: Arc::clone(self),
+        }
+    }
+
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::Acquire)
+    }
+}
+
+/// 一次进行中的扫描，drop 时计数减一（包括出错提前返回的情况）
+pub struct ActiveScan {
+    tracker: Arc<ScanTracker>,
+}
+
+impl Drop for ActiveScan {
+    fn drop(&mut self) {
+        self.tracker.active.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// 数据库事务的重试策略：仅在 SQLite 返回 busy/locked 时按指数退避重试
+#[derive(Debug, Clone, Copy)]
+pub struct DbRetryPolicy {
+    /// 包括第一次在内的最大尝试次数
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+}
+
+impl Default for DbRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_DB_RETRY_ATTEMPTS,
+            base_delay: DB_RETRY_BASE_DELAY,
+        }
+    }
+}
+
+impl DbRetryPolicy {
+    pub fn from_env() -> Self {
+        let max_attempts = std::env::var("DB_RETRY_ATTEMPTS")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or(DEFAULT_DB_RETRY_ATTEMPTS);
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+
+    /// 执行 `op`，遇到锁竞争错误时等待后重新执行整个操作
+    ///
+    /// `op` 每次调用都应开启新的事务：失败的事务在 drop 时回滚，重试不会留下部分写入。
+    /// 其他错误（约束冲突、SQL 错误等）立即返回。
+    pub async fn run<T, F, Fut>(&self, operation: &str, mut op: F) -> Result<T, sqlx::Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, sqlx::Error>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match op().await {
+                Err(e) if attempt < self.max_attempts && is_transient_db_error(&e) => {
+                    let delay = self.base_delay.saturating_mul(1 << (attempt - 1).min(16));
+                    tracing::warn!(
+                        "{} hit a locked database (attempt {} of {}), retrying in {:?}: {}",
+                        operation,
+                        attempt,
+                        self.max_attempts,
+                        delay,
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// 是否为可重试的锁竞争错误（SQLITE_BUSY / SQLITE_LOCKED 及其扩展错误码）
+pub fn is_transient_db_error(error: &sqlx::Error) -> bool {
+    let sqlx::Error::Database(db_error) = error else {
+        return false;
+    };
+    // sqlx 返回扩展错误码，低 8 位为主错误码
+    let primary = db_error
+        .code()
+        .and_then(|code| code.parse::<i32>().ok())
+        .map(|code| code & 0xff);
+    match primary {
+        Some(code) => code == 5 || code == 6,
+        None => {
+            let message = db_error.message().to_lowercase();
+            message.contains("database is locked") || message.contains("database table is locked")
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct AppState {
     pub ast_engine: Arc<Mutex<ASTEngine>>,
     pub db: Pool<Sqlite>,
     pub ast_cache_state: Arc<Mutex<AstCacheState>>,
+    pub upload_budget: Arc<UploadBudget>,
+    pub scan_tracker: Arc<ScanTracker>,
+    pub db_retry: DbRetryPolicy,
+    /// 规则目录，见 [`rules_dir_from_env`]
+    pub rules_dir: std::path::PathBuf,
+    /// 编译好的扫描规则，规则目录无法监听时为 None（每次扫描从磁盘加载）
+    pub rules: Option<Arc<RulesCache>>,
 }
 
 impl AppState {
     pub async fn new() -> anyhow::Result<Self> {
-        // 初始化 AST 引擎
-        let ast_engine = ASTEngine::new(".deepaudit_cache");
+        // 初始化 AST 引擎，解析错误模式可通过 `AST_PARSE_ERROR_MODE`（ignore/record/warn）覆盖
+        let parse_error_mode = match std::env::var("AST_PARSE_ERROR_MODE") {
+            Ok(v) => v.parse::<ParseErrorMode>().unwrap_or_else(|e| {
+                println!("{}, falling back to record", e);
+                ParseErrorMode::Record
+            }),
+            Err(_) => ParseErrorMode::Record,
+        };
+        let ast_engine = ASTEngine::new(CACHE_DIR).with_parse_error_mode(parse_error_mode);
         let ast_engine = Arc::new(Mutex::new(ast_engine));
 
         // 初始化数据库
         let db = init_db().await?;
 
+        // 目录不存在时先创建，之后通过规则管理接口添加的规则同样会被监听到
+        let rules_dir = rules_dir_from_env();
+        println!("Rules directory: {}", rules_dir.display());
+        let rules = match std::fs::create_dir_all(&rules_dir) {
+            Ok(()) => RulesCache::watch(&rules_dir)
+                .inspect_err(|e| tracing::warn!("Failed to watch rules directory, loading rules per scan: {}", e))
+                .ok(),
+            Err(e) => {
+                tracing::warn!("Failed to create rules directory {}: {}", rules_dir.display(), e);
+                None
+            }
+        };
+
         Ok(Self {
             ast_engine,
             db,
             ast_cache_state: Arc::new(Mutex::new(AstCacheState::default())),
+            upload_budget: Arc::new(UploadBudget::from_env()),
+            scan_tracker: Arc::new(ScanTracker::default()),
+            db_retry: DbRetryPolicy::from_env(),
+            rules_dir,
+            rules,
         })
     }
+
+    /// 供扫描使用的规则集：监听中的缓存，或者（无法监听时）从规则目录重新加载
+    pub fn scan_rules(&self) -> Option<Arc<LoadedRules>> {
+        Some(match &self.rules {
+            Some(rules) => rules.current(),
+            None => Arc::new(LoadedRules::load_from_dir(&self.rules_dir)),
+        })
+    }
+
+    /// 规则管理接口写入规则文件后立即重新编译，不等待文件监听事件
+    pub fn rules_changed(&self) {
+        if let Some(rules) = &self.rules {
+            rules.reload();
+        }
+    }
+}
+
+#[cfg(test)]
+impl AppState {
+    /// 使用临时目录中新建数据库的状态；返回的目录需在测试结束前保持存活
+    pub async fn for_tests() -> (Self, tempfile::TempDir) {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let db = open_db(&dir.path().join("test.db"), DEFAULT_DB_MAX_CONNECTIONS)
+            .await
+            .expect("open test database");
+        let state = Self {
+            ast_engine: Arc::new(Mutex::new(ASTEngine::new(&dir.path().join("cache").to_string_lossy()))),
+            db,
+            ast_cache_state: Arc::new(Mutex::new(AstCacheState::default())),
+            upload_budget: Arc::new(UploadBudget::from_env()),
+            scan_tracker: Arc::new(ScanTracker::default()),
+            db_retry: DbRetryPolicy::default(),
+            rules_dir: dir.path().join("rules"),
+            rules: None,
+        };
+        (state, dir)
+    }
 }
 
 async fn init_db() -> anyhow::Result<Pool<Sqlite>> {
@@ -44,12 +243,32 @@ async fn init_db() -> anyhow::Result<Pool<Sqlite>> {
 
     println!("Database path: {}", db_path.display());
 
+    let max_connections = std::env::var("DB_MAX_CONNECTIONS")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_DB_MAX_CONNECTIONS);
+
+    println!("Database pool size: {}", max_connections);
+
+    let pool = open_db(&db_path, max_connections).await?;
+
+    println!("Database initialized successfully");
+
+    Ok(pool)
+}
+
+/// 打开（不存在时创建）数据库，创建缺少的表和列
+async fn open_db(db_path: &std::path::Path, max_connections: u32) -> anyhow::Result<Pool<Sqlite>> {
     // 使用 SqliteConnectOptions 来确保数据库文件可以被创建
+    // WAL 模式允许扫描结果写入期间并发读取，busy_timeout 避免立即返回 "database is locked"
     let options = SqliteConnectOptions::from_str(&format!("sqlite://{}", db_path.display()))?
-        .create_if_missing(true);
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .busy_timeout(DB_BUSY_TIMEOUT);
 
     let pool = SqlitePoolOptions::new()
-        .max_connections(5)
+        .max_connections(max_connections)
         .connect_with(options)
         .await
         .map_err(|e| anyhow::anyhow!("Failed to connect to database: {}", e))?;
@@ -78,6 +297,9 @@ async fn init_db() -> anyhow::Result<Pool<Sqlite>> {
             description TEXT,
             code_snippet TEXT,
             status TEXT DEFAULT 'new',
+            scan_id INTEGER,
+            original_severity TEXT,
+            merged_detectors TEXT,
             created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
             FOREIGN KEY(project_id) REFERENCES projects(id)
         );
@@ -90,9 +312,59 @@ async fn init_db() -> anyhow::Result<Pool<Sqlite>> {
             findings_found INTEGER DEFAULT 0,
             started_at DATETIME DEFAULT CURRENT_TIMESTAMP,
             completed_at DATETIME,
+            is_baseline INTEGER DEFAULT 0,
+            cwe_counted INTEGER DEFAULT 0,
+            walk_ms INTEGER,
+            read_ms INTEGER,
+            regex_ms INTEGER,
+            rule_ms INTEGER,
+            total_ms INTEGER,
+            FOREIGN KEY(project_id) REFERENCES projects(id)
+        );
+
+        -- 项目级严重程度改写，按 position 顺序取第一条命中的
+        CREATE TABLE IF NOT EXISTS severity_overrides (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL,
+            position INTEGER NOT NULL DEFAULT 0,
+            match_cwe TEXT,
+            rule_id TEXT,
+            severity TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
             FOREIGN KEY(project_id) REFERENCES projects(id)
         );
 
+        -- 审查时为发现添加的自由标签
+        CREATE TABLE IF NOT EXISTS finding_tags (
+            finding_id TEXT NOT NULL,
+            tag TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY(finding_id, tag),
+            FOREIGN KEY(finding_id) REFERENCES findings(finding_id)
+        );
+
+        -- 每次扫描中各 CWE 的发现数（含增量扫描复用的发现），用于趋势统计
+        CREATE TABLE IF NOT EXISTS scan_cwe_counts (
+            scan_id INTEGER NOT NULL,
+            cwe TEXT NOT NULL,
+            count INTEGER NOT NULL,
+            PRIMARY KEY(scan_id, cwe),
+            FOREIGN KEY(scan_id) REFERENCES scans(id)
+        );
+
+        -- 增量扫描：每个文件上次扫描时的内容哈希和规则集哈希，scan_id 为产生其发现的扫描
+        CREATE TABLE IF NOT EXISTS file_hashes (
+            project_id INTEGER NOT NULL,
+            file_path TEXT NOT NULL,
+            content_hash TEXT NOT NULL,
+            ruleset_hash TEXT NOT NULL,
+            scan_id INTEGER NOT NULL,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY(project_id, file_path),
+            FOREIGN KEY(project_id) REFERENCES projects(id),
+            FOREIGN KEY(scan_id) REFERENCES scans(id)
+        );
+
         -- AST 索引历史表
         CREATE TABLE IF NOT EXISTS ast_indices (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -158,13 +430,127 @@ async fn init_db() -> anyhow::Result<Pool<Sqlite>> {
         CREATE INDEX IF NOT EXISTS idx_graphs_type ON code_graphs(graph_type);
         CREATE INDEX IF NOT EXISTS idx_calls_project ON call_relations(project_id);
         CREATE INDEX IF NOT EXISTS idx_indices_project ON ast_indices(project_id);
+        CREATE INDEX IF NOT EXISTS idx_findings_project_file ON findings(project_id, file_path);
+        CREATE INDEX IF NOT EXISTS idx_finding_tags_tag ON finding_tags(tag);
         "#,
     )
     .execute(&pool)
     .await
     .map_err(|e| anyhow::anyhow!("Failed to create tables: {}", e))?;
 
-    println!("Database initialized successfully");
+    // 旧版本数据库补充新增的列
+    ensure_column(&pool, "scans", "is_baseline", "INTEGER DEFAULT 0").await?;
+    ensure_column(&pool, "findings", "scan_id", "INTEGER").await?;
+    ensure_column(&pool, "scans", "cwe_counted", "INTEGER DEFAULT 0").await?;
+    ensure_column(&pool, "ast_indices", "parse_health", "TEXT").await?;
+    ensure_column(&pool, "findings", "original_severity", "TEXT").await?;
+    ensure_column(&pool, "findings", "merged_detectors", "TEXT").await?;
+    for column in ["walk_ms", "read_ms", "regex_ms", "rule_ms", "total_ms"] {
+        ensure_column(&pool, "scans", column, "INTEGER").await?;
+    }
 
     Ok(pool)
 }
+
+/// 表中缺少指定列时执行 ALTER TABLE 添加（CREATE TABLE IF NOT EXISTS 不会更新已有表）
+async fn ensure_column(
+    pool: &Pool<Sqlite>,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> anyhow::Result<()> {
+    let columns: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info(?)")
+        .bind(table)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to inspect table {}: {}", table, e))?;
+
+    if !columns.iter().any(|c| c == column) {
+        sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+            .execute(pool)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to add column {}.{}: {}", table, column, e))?;
+        println!("Added column {}.{}", table, column);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 与扫描结果入库相同形态的事务：先写扫描记录，再批量写发现，最后更新扫描状态
+    async fn store_scan(pool: &Pool<Sqlite>, worker: usize) -> Result<(), sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        let scan_id: i64 =
+            sqlx::query_scalar("INSERT INTO scans (project_id, status) VALUES (1, 'running') RETURNING id")
+                .fetch_one(&mut *tx)
+                .await?;
+        for i in 0..200 {
+            sqlx::query("INSERT INTO findings (project_id, finding_id, file_path, scan_id) VALUES (1, ?, 'app.py', ?)")
+                .bind(format!("{}-{}", worker, i))
+                .bind(scan_id)
+                .execute(&mut *tx)
+                .await?;
+            tokio::task::yield_now().await;
+        }
+        sqlx::query("UPDATE scans SET status = 'completed', findings_found = 200 WHERE id = ?")
+            .bind(scan_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+
+    #[tokio::test]
+    async fn concurrent_scan_stores_do_not_fail_with_lock_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = open_db(&dir.path().join("test.db"), DEFAULT_DB_MAX_CONNECTIONS)
+            .await
+            .unwrap();
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode").fetch_one(&pool).await.unwrap();
+        assert_eq!(journal_mode, "wal");
+        sqlx::query("INSERT INTO projects (id, uuid, name, path) VALUES (1, 'p1', 'demo', '/tmp/demo')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // 不经过 DbRetryPolicy，锁竞争只能由 busy_timeout 等待解决
+        let (first, second) = tokio::join!(
+            tokio::spawn({
+                let pool = pool.clone();
+                async move { store_scan(&pool, 1).await }
+            }),
+            tokio::spawn({
+                let pool = pool.clone();
+                async move { store_scan(&pool, 2).await }
+            }),
+        );
+        first.unwrap().unwrap();
+        second.unwrap().unwrap();
+
+        let completed: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM scans WHERE status = 'completed'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let findings: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM findings").fetch_one(&pool).await.unwrap();
+        assert_eq!((completed, findings), (2, 400));
+    }
+
+    #[tokio::test]
+    async fn retry_policy_does_not_retry_logic_errors() {
+        let policy = DbRetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+        };
+        let mut attempts = 0;
+        let result: Result<(), sqlx::Error> = policy
+            .run("Test", || {
+                attempts += 1;
+                async { Err(sqlx::Error::RowNotFound) }
+            })
+            .await;
+        assert!(matches!(result, Err(sqlx::Error::RowNotFound)));
+        assert_eq!(attempts, 1);
+    }
+}
@@ -0,0 +1,262 @@
+// Upload - multipart 文件流式写盘及全局上传容量限制
+
+use actix_multipart::Field;
+use actix_web::HttpResponse;
+use futures_util::TryStreamExt;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+
+/// 单个上传文件的最大字节数
+pub const MAX_UPLOAD_FILE_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// 所有请求同时上传中的字节总数上限，可通过 `UPLOAD_MAX_INFLIGHT_BYTES` 环境变量覆盖
+const DEFAULT_MAX_INFLIGHT_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// 跨请求共享的上传容量
+pub struct UploadBudget {
+    in_flight: AtomicU64,
+    max_in_flight: u64,
+}
+
+impl UploadBudget {
+    pub fn new(max_in_flight: u64) -> Self {
+        Self {
+            in_flight: AtomicU64::new(0),
+            max_in_flight,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let max_in_flight = std::env::var("UPLOAD_MAX_INFLIGHT_BYTES")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or(DEFAULT_MAX_INFLIGHT_BYTES);
+        Self::new(max_in_flight)
+    }
+
+    /// 创建一个初始为 0 字节的预留，随上传进度增长，drop 时归还
+    pub fn reserve(self: &Arc<Self>) -> UploadReservation {
+        UploadReservation {
+            budget: Arc::clone(self),
+            bytes: 0,
+        }
+    }
+
+    fn try_acquire(&self, bytes: u64) -> bool {
+        self.in_flight
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+                current
+                    .checked_add(bytes)
+                    .filter(|&total| total <= self.max_in_flight)
+            })
+            .is_ok()
+    }
+}
+
+/// 请求持有的上传容量，请求结束（drop）时释放
+pub struct UploadReservation {
+    budget: Arc<UploadBudget>,
+    bytes: u64,
+}
+
+impl UploadReservation {
+    fn grow(&mut self, bytes: u64) -> Result<(), UploadError> {
+        if !self.budget.try_acquire(bytes) {
+            return Err(UploadError::Busy);
+        }
+        self.bytes += bytes;
+        Ok(())
+    }
+}
+
+impl Drop for UploadReservation {
+    fn drop(&mut self) {
+        self.budget.in_flight.fetch_sub(self.bytes, Ordering::AcqRel);
+    }
+}
+
+#[derive(Debug)]
+pub enum UploadError {
+    /// 单个文件超过大小限制
+    TooLarge(u64),
+    /// 服务器上同时上传的总量已达上限
+    Busy,
+    Multipart(String),
+    Io(String),
+}
+
+impl UploadError {
+    pub fn to_response(&self) -> HttpResponse {
+        match self {
+            UploadError::TooLarge(limit) => HttpResponse::PayloadTooLarge().json(serde_json::json!({
+                "error": format!("File size limit of {} bytes exceeded", limit)
+            })),
+            UploadError::Busy => HttpResponse::ServiceUnavailable().json(serde_json::json!({
+                "error": "Too many uploads in progress, please retry later"
+            })),
+            UploadError::Multipart(e) => HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("Failed to read upload: {}", e)
+            })),
+            UploadError::Io(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to write upload: {}", e)
+            })),
+        }
+    }
+}
+
+/// 将 multipart 字段按块写入文件，不在内存中缓存整个文件；返回写入的字节数
+pub async fn stream_field_to_file(
+    field: &mut Field,
+    path: &Path,
+    max_bytes: u64,
+    reservation: &mut UploadReservation,
+) -> Result<u64, UploadError> {
+    let mut file = tokio::fs::File::create(path)
+        .await
+        .map_err(|e| UploadError::Io(e.to_string()))?;
+
+    let mut written = 0u64;
+    while let Some(chunk) = field
+        .try_next()
+        .await
+        .map_err(|e| UploadError::Multipart(e.to_string()))?
+    {
+        let len = chunk.len() as u64;
+        if written + len > max_bytes {
+            return Err(UploadError::TooLarge(max_bytes));
+        }
+        reservation.grow(len)?;
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| UploadError::Io(e.to_string()))?;
+        written += len;
+    }
+
+    file.flush().await.map_err(|e| UploadError::Io(e.to_string()))?;
+    Ok(written)
+}
+
+/// 将 multipart 字段读入内存（用于需要整体处理的内容，如内存中扫描压缩包），同样计入上传容量
+pub async fn read_field_to_vec(
+    field: &mut Field,
+    max_bytes: u64,
+    reservation: &mut UploadReservation,
+) -> Result<Vec<u8>, UploadError> {
+    let mut data = Vec::new();
+    while let Some(chunk) = field
+        .try_next()
+        .await
+        .map_err(|e| UploadError::Multipart(e.to_string()))?
+    {
+        let len = chunk.len() as u64;
+        if data.len() as u64 + len > max_bytes {
+            return Err(UploadError::TooLarge(max_bytes));
+        }
+        reservation.grow(len)?;
+        data.extend_from_slice(&chunk);
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_multipart::Multipart;
+    use actix_web::web::Bytes;
+    use std::sync::Mutex;
+
+    const CHUNK: usize = 64 * 1024;
+    const CHUNKS: usize = 64;
+    const BOUNDARY: &str = "upload-test-boundary";
+
+    /// 逐块产生的单文件 multipart 请求体；产生每一块前记录目标文件当前的大小
+    fn chunked_upload(target: std::path::PathBuf, observed: Arc<Mutex<Vec<u64>>>) -> Multipart {
+        let stream = futures_util::stream::unfold(0usize, move |index| {
+            let target = target.clone();
+            let observed = Arc::clone(&observed);
+            async move {
+                let chunk = match index {
+                    0 => format!(
+                        "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"big.zip\"\r\n\
+                         Content-Type: application/zip\r\n\r\n"
+                    )
+                    .into_bytes(),
+                    i if i <= CHUNKS => {
+                        let written = std::fs::metadata(&target).map(|m| m.len()).unwrap_or(0);
+                        observed.lock().unwrap().push(written);
+                        vec![b'a'; CHUNK]
+                    }
+                    i if i == CHUNKS + 1 => format!("\r\n--{BOUNDARY}--\r\n").into_bytes(),
+                    _ => return None,
+                };
+                // 让出执行权，使服务端在下一块到达前处理已收到的数据
+                tokio::task::yield_now().await;
+                Some((Ok::<_, actix_web::error::PayloadError>(Bytes::from(chunk)), index + 1))
+            }
+        });
+
+        let mut headers = actix_web::http::header::HeaderMap::new();
+        headers.insert(
+            actix_web::http::header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={BOUNDARY}").parse().unwrap(),
+        );
+        Multipart::new(&headers, stream)
+    }
+
+    #[actix_web::test]
+    async fn large_upload_is_written_while_it_is_received() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("upload.zip");
+        let observed = Arc::new(Mutex::new(Vec::new()));
+        let budget = Arc::new(UploadBudget::new(u64::MAX));
+        let mut reservation = budget.reserve();
+
+        let mut form = chunked_upload(target.clone(), Arc::clone(&observed));
+        let mut field = form.try_next().await.unwrap().unwrap();
+        let written = stream_field_to_file(&mut field, &target, MAX_UPLOAD_FILE_BYTES, &mut reservation)
+            .await
+            .unwrap();
+        assert_eq!(written, (CHUNK * CHUNKS) as u64);
+        assert_eq!(std::fs::metadata(&target).unwrap().len(), written);
+        assert_eq!(budget.in_flight.load(Ordering::Acquire), written);
+
+        // 产生后半部分数据时，前面的数据已经写入磁盘（解析器只缓冲少量块）
+        let observed = observed.lock().unwrap();
+        let last = *observed.last().unwrap();
+        assert!(last >= (CHUNK * (CHUNKS - 4)) as u64, "only {} bytes on disk before the last chunk", last);
+
+        drop(reservation);
+        assert_eq!(budget.in_flight.load(Ordering::Acquire), 0);
+    }
+
+    #[actix_web::test]
+    async fn uploads_beyond_in_flight_budget_or_file_limit_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("upload.zip");
+        let observed = Arc::new(Mutex::new(Vec::new()));
+
+        let budget = Arc::new(UploadBudget::new((CHUNK * 8) as u64));
+        // 另一个请求已占用部分容量
+        let mut other = budget.reserve();
+        other.grow((CHUNK * 4) as u64).unwrap();
+        let mut reservation = budget.reserve();
+        let mut form = chunked_upload(target.clone(), Arc::clone(&observed));
+        let mut field = form.try_next().await.unwrap().unwrap();
+        let result = stream_field_to_file(&mut field, &target, MAX_UPLOAD_FILE_BYTES, &mut reservation).await;
+        assert!(matches!(result, Err(UploadError::Busy)));
+        assert_eq!(result.unwrap_err().to_response().status(), actix_web::http::StatusCode::SERVICE_UNAVAILABLE);
+        drop(reservation);
+        drop(other);
+        assert_eq!(budget.in_flight.load(Ordering::Acquire), 0);
+
+        let budget = Arc::new(UploadBudget::new(u64::MAX));
+        let mut reservation = budget.reserve();
+        let mut form = chunked_upload(target.clone(), observed);
+        let mut field = form.try_next().await.unwrap().unwrap();
+        let result = stream_field_to_file(&mut field, &target, (CHUNK * 2) as u64, &mut reservation).await;
+        assert!(matches!(result, Err(UploadError::TooLarge(_))));
+    }
+}